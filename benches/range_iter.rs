@@ -2,9 +2,7 @@
 
 use {
     core::{char, iter::*, ops::RangeInclusive},
-    criterion::{
-        black_box, criterion_group, criterion_main, BatchSize, Bencher, BenchmarkGroup, Criterion,
-    },
+    criterion::{black_box, criterion_group, criterion_main, BatchSize, Bencher, Criterion},
     mileage::{range::Iter, CharRange},
 };
 
@@ -72,7 +70,7 @@ fn custom() -> impl Iterator<Item = char> + Clone {
         #[inline]
         fn next(&mut self) -> Option<Self::Item> {
             // Avoid unstable `<&mut I as ExactSizeIterator>::is_empty`
-            if (&*self).is_empty() {
+            if Iter::is_empty(self) {
                 return None;
             }
 
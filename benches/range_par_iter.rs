@@ -1,9 +1,8 @@
 #![allow(clippy::type_complexity)]
 
-use criterion::BatchSize;
 use {
     core::{char, ops::Range},
-    criterion::{black_box, criterion_group, criterion_main, Bencher, Benchmark, Criterion},
+    criterion::{black_box, criterion_group, criterion_main, BatchSize, Bencher, Criterion},
     mileage::CharRange,
     rayon::{iter::*, prelude::IntoParallelIterator},
 };
@@ -93,18 +92,16 @@ fn bench_ranges(c: &mut Criterion) {
         )
     }
 
-    c.bench(
-        "CharParIter",
-        Benchmark::new("chain_segments (fn())", |b| bench(b, chain_segments()))
-            .with_function("chain_segments ([closure])", |b| {
-                bench(b, chain_segments_())
-            })
-            .with_function("try_from (fn())", |b| bench(b, try_from()))
-            .with_function("try_from ([closure])", |b| bench(b, try_from_()))
-            .with_function("decompress (fn())", |b| bench(b, decompress()))
-            .with_function("decompress ([closure])", |b| bench(b, decompress_()))
-            .with_function("actual", |b| bench(b, actual())),
-    );
+    let mut group = c.benchmark_group("CharParIter");
+    group.bench_function("chain_segments (fn())", |b| bench(b, chain_segments()));
+    group.bench_function("chain_segments ([closure])", |b| {
+        bench(b, chain_segments_())
+    });
+    group.bench_function("try_from (fn())", |b| bench(b, try_from()));
+    group.bench_function("try_from ([closure])", |b| bench(b, try_from_()));
+    group.bench_function("decompress (fn())", |b| bench(b, decompress()));
+    group.bench_function("decompress ([closure])", |b| bench(b, decompress_()));
+    group.bench_function("actual", |b| bench(b, actual()));
 }
 
 criterion_group!(benches, bench_ranges);
@@ -0,0 +1,104 @@
+use {
+    core::char,
+    criterion::{black_box, criterion_group, criterion_main, Criterion},
+    mileage::trie::CharTrie,
+};
+
+const fn level2() -> [u8; 992] {
+    let mut level2 = [0; 992];
+    let mut i = 0;
+    while i < 992 {
+        level2[i] = (i % 4) as u8;
+        i += 1;
+    }
+    level2
+}
+
+const fn level3_0() -> [u8; 256] {
+    let mut level3_0 = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        level3_0[i] = (i % 2) as u8;
+        i += 1;
+    }
+    level3_0
+}
+
+const fn chonks() -> [[u8; 64]; 2] {
+    let mut chonks = [[0; 64]; 2];
+    let mut i = 0;
+    while i < 64 {
+        chonks[0][i] = (i % 4) as u8;
+        chonks[1][i] = (3 - i % 4) as u8;
+        i += 1;
+    }
+    chonks
+}
+
+static LEVEL1: [u64; 32] = [0x5555_5555_5555_5555; 32];
+static LEVEL2: [u8; 992] = level2();
+static LEVEL3_0: [u8; 256] = level3_0();
+static CHONKS: [[u8; 64]; 2] = chonks();
+static LEAVES: [u64; 4] = [0, !0, 0x5555_5555_5555_5555, 0xAAAA_AAAA_AAAA_AAAA];
+static TRIE: CharTrie = CharTrie::from_raw(&LEVEL1, &LEVEL2, (&LEVEL3_0, &CHONKS), &LEAVES);
+
+/// The lookup of the same tables, written out by hand against the statics.
+fn contains_by_hand(c: char) -> bool {
+    let c = c as u32;
+    let chunk = if c < 0x800 {
+        LEVEL1[(c >> 6) as usize]
+    } else if c < 0x10000 {
+        LEAVES[LEVEL2[((c - 0x800) >> 6) as usize] as usize]
+    } else {
+        let c = c - 0x10000;
+        let chonk = &CHONKS[LEVEL3_0[(c >> 12) as usize] as usize];
+        LEAVES[chonk[((c >> 6) & 63) as usize] as usize]
+    };
+    (chunk >> (c & 63)) & 1 == 1
+}
+
+/// Text randomly mixing ASCII, CJK, and emoji.
+fn mixed_text() -> Vec<char> {
+    let mut state = 0x2545_F491_u32;
+    (0..4096)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            let base = match state % 3 {
+                0 => 0x61,
+                1 => 0x4E00,
+                _ => 0x1F300,
+            };
+            char::from_u32(base + (state >> 8) % 26).unwrap()
+        })
+        .collect()
+}
+
+fn bench_contains(c: &mut Criterion) {
+    let text = mixed_text();
+    assert!(text
+        .iter()
+        .all(|&c| TRIE.contains(c) == contains_by_hand(c)));
+
+    let mut group = c.benchmark_group("CharTrie::contains");
+    group.bench_function("by hand", |b| {
+        b.iter(|| {
+            black_box(&text)
+                .iter()
+                .filter(|&&c| contains_by_hand(c))
+                .count()
+        })
+    });
+    group.bench_function("actual", |b| {
+        b.iter(|| {
+            black_box(&text)
+                .iter()
+                .filter(|&&c| TRIE.contains(c))
+                .count()
+        })
+    });
+}
+
+criterion_group!(benches, bench_contains);
+criterion_main!(benches);
@@ -0,0 +1,304 @@
+//! Procedural macros for [mileage](https://docs.rs/mileage).
+//!
+//! These are re-exported from `mileage` behind the `macros` feature;
+//! depend on that rather than on this crate directly.
+
+#![deny(unsafe_code, rust_2018_idioms)]
+#![warn(missing_debug_implementations, missing_docs)]
+
+use {
+    proc_macro::TokenStream,
+    proc_macro2::Span,
+    quote::quote,
+    std::collections::HashMap,
+    syn::{
+        parse::{Parse, ParseStream},
+        punctuated::Punctuated,
+        LitChar, Token,
+    },
+};
+
+/// A single `'a'`, `'a'..'b'`, or `'a'..='b'` in the macro input.
+struct Item {
+    start: LitChar,
+    end: Option<(bool, LitChar)>,
+}
+
+impl Parse for Item {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let start = input.parse()?;
+        let end = if input.peek(Token![..=]) {
+            input.parse::<Token![..=]>()?;
+            Some((true, input.parse()?))
+        } else if input.peek(Token![..]) {
+            input.parse::<Token![..]>()?;
+            Some((false, input.parse()?))
+        } else {
+            None
+        };
+        Ok(Item { start, end })
+    }
+}
+
+impl Item {
+    /// The inclusive `u32` bounds of this item, or an error if it is empty.
+    fn bounds(&self) -> syn::Result<(u32, u32)> {
+        let start = self.start.value() as u32;
+        match &self.end {
+            None => Ok((start, start)),
+            Some((inclusive, end_lit)) => {
+                let end = end_lit.value() as u32;
+                let end = match (*inclusive, end) {
+                    (true, end) => Some(end),
+                    // step backwards over the surrogate gap
+                    (false, 0xE000) => Some(0xD7FF),
+                    (false, end) => end.checked_sub(1),
+                };
+                match end {
+                    Some(end) if start <= end => Ok((start, end)),
+                    _ => Err(syn::Error::new(
+                        end_lit.span(),
+                        format!(
+                            "empty char range {:?}..{}{:?}",
+                            self.start.value(),
+                            if *inclusive { "=" } else { "" },
+                            end_lit.value(),
+                        ),
+                    )),
+                }
+            }
+        }
+    }
+}
+
+/// Sort and merge inclusive ranges into the canonical `CharSet` form.
+///
+/// Ranges which touch in `u32` space are merged; the surrogate gap is
+/// never bridged, matching `CharSetBuf`.
+fn normalize(mut ranges: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+    ranges.sort_unstable();
+    let mut merged: Vec<(u32, u32)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Build a `&'static CharSet` at compile time.
+///
+/// Accepts a comma-separated list of char literals and char ranges
+/// (`'a'..='z'` or `'a'..'{'`), in any order and possibly overlapping.
+/// The ranges are sorted and merged when the macro is expanded, so the
+/// resulting set is valid by construction and costs nothing at runtime.
+///
+/// The expansion refers to `::mileage`, so the crate must not be renamed.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::{charset, set::CharSet};
+/// static IDENT: &CharSet = charset!['a'..='z', '_', 'A'..='Z', '0'..='9'];
+/// assert!(IDENT.contains('q'));
+/// assert!(IDENT.contains('_'));
+/// assert!(!IDENT.contains('-'));
+/// assert_eq!(IDENT.ranges().count(), 4);
+/// ```
+///
+/// Empty ranges are rejected:
+///
+/// ```compile_fail
+/// # use mileage::charset;
+/// let _ = charset!['z'..='a'];
+/// ```
+#[proc_macro]
+pub fn charset(input: TokenStream) -> TokenStream {
+    let ranges = match parse_ranges(input) {
+        Ok(ranges) => ranges,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let ranges = ranges.into_iter().map(|(start, end)| {
+        let start = LitChar::new(char::from_u32(start).unwrap(), Span::call_site());
+        let end = LitChar::new(char::from_u32(end).unwrap(), Span::call_site());
+        quote!(::mileage::CharRange::closed(#start, #end))
+    });
+    let expanded = quote! {
+        {
+            const RANGES: &[::mileage::CharRange] = &[#(#ranges),*];
+            ::mileage::set::CharSet::from_raw(RANGES)
+        }
+    };
+    expanded.into()
+}
+
+/// The tables of a `CharTrie`, as passed to `CharTrie::from_raw`,
+/// with indices wide enough for the number of leaves.
+struct Tables {
+    level1: Vec<u64>,
+    level2: Vec<u16>,
+    level3: (Vec<u8>, Vec<Vec<u16>>),
+    leaves: Vec<u64>,
+}
+
+impl Tables {
+    /// Build the tables of a trie holding sorted, merged ranges.
+    fn new(ranges: &[(u32, u32)]) -> Self {
+        let mut chunks = vec![0u64; 0x11_0000 / 64];
+        for &(start, end) in ranges {
+            // the surrogates are never members
+            for c in (start..=end).filter(|c| !(0xD800..0xE000).contains(c)) {
+                chunks[(c / 64) as usize] |= 1 << (c % 64);
+            }
+        }
+
+        let mut leaves = Vec::new();
+        let mut leaf_indices = HashMap::new();
+        let mut leaf = |chunk: u64| {
+            *leaf_indices.entry(chunk).or_insert_with(|| {
+                leaves.push(chunk);
+                leaves.len() as u16 - 1
+            })
+        };
+        let level1 = chunks[..32].to_vec();
+        let level2 = chunks[32..1024].iter().map(|&chunk| leaf(chunk)).collect();
+        let mut chonks: Vec<Vec<u16>> = Vec::new();
+        let level3 = chunks[1024..]
+            .chunks(64)
+            .map(|chonk| {
+                let chonk = chonk.iter().map(|&chunk| leaf(chunk)).collect();
+                match chonks.iter().position(|it| *it == chonk) {
+                    Some(idx) => idx as u8,
+                    None => {
+                        chonks.push(chonk);
+                        chonks.len() as u8 - 1
+                    }
+                }
+            })
+            .collect();
+
+        Tables {
+            level1,
+            level2,
+            level3: (level3, chonks),
+            leaves,
+        }
+    }
+
+    /// An expression constructing a `CharTrie` from these tables.
+    fn to_tokens(&self) -> proc_macro2::TokenStream {
+        let Tables {
+            level1,
+            level2,
+            level3: (first, second),
+            leaves,
+        } = self;
+        if leaves.len() <= 256 {
+            let level2 = level2.iter().map(|&idx| idx as u8);
+            let second = second
+                .iter()
+                .map(|chonk| chonk.iter().map(|&idx| idx as u8).collect::<Vec<u8>>());
+            quote! {
+                {
+                    const LEVEL1: &[u64; 32] = &[#(#level1),*];
+                    const LEVEL2: &[u8; 992] = &[#(#level2),*];
+                    const LEVEL3: (&[u8; 256], &[[u8; 64]]) = (&[#(#first),*], &[#([#(#second),*]),*]);
+                    const LEAVES: &[u64] = &[#(#leaves),*];
+                    ::mileage::trie::CharTrie::from_raw(LEVEL1, LEVEL2, LEVEL3, LEAVES)
+                }
+            }
+        } else {
+            quote! {
+                {
+                    const LEVEL1: &[u64; 32] = &[#(#level1),*];
+                    const LEVEL2: &[u16; 992] = &[#(#level2),*];
+                    const LEVEL3: (&[u8; 256], &[[u16; 64]]) = (&[#(#first),*], &[#([#(#second),*]),*]);
+                    const LEAVES: &[u64] = &[#(#leaves),*];
+                    ::mileage::trie::CharTrie::from_raw_wide(LEVEL1, LEVEL2, LEVEL3, LEAVES)
+                }
+            }
+        }
+    }
+}
+
+/// Parse the input shared by `charset!` and `chartrie!` into canonical ranges.
+fn parse_ranges(input: TokenStream) -> syn::Result<Vec<(u32, u32)>> {
+    let items = syn::parse::Parser::parse(Punctuated::<Item, Token![,]>::parse_terminated, input)?;
+    let ranges = items.iter().map(Item::bounds).collect::<syn::Result<_>>()?;
+    Ok(normalize(ranges))
+}
+
+/// Build a `CharTrie` at compile time.
+///
+/// Accepts the same input as `charset!`: a comma-separated list of char
+/// literals and char ranges, in any order and possibly overlapping.
+/// The trie tables are generated when the macro is expanded, as
+/// `mileage::trie::generate` would, and expand to constants.
+///
+/// The expansion refers to `::mileage`, so the crate must not be renamed.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::{chartrie, trie::CharTrie};
+/// static LETTERS: CharTrie = chartrie!['a'..='z', 'A'..='Z', '\u{430}'..='\u{44F}'];
+/// assert!(LETTERS.contains('q'));
+/// assert!(LETTERS.contains('ж'));
+/// assert!(!LETTERS.contains('-'));
+/// assert_eq!(LETTERS.ranges().count(), 3);
+/// ```
+#[proc_macro]
+pub fn chartrie(input: TokenStream) -> TokenStream {
+    match parse_ranges(input) {
+        Ok(ranges) => Tables::new(&ranges).to_tokens().into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[rustfmt::skip]
+    fn normalize_merges() {
+        type Ranges = &'static [(u32, u32)];
+        let tests: &[(Ranges, Ranges)] = &[
+            (&[],                                 &[]),
+            (&[(5, 9), (0, 2)],                   &[(0, 2), (5, 9)]),
+            (&[(0, 2), (3, 4)],                   &[(0, 4)]),
+            (&[(0, 9), (2, 4)],                   &[(0, 9)]),
+            (&[(4, 8), (0, 5), (9, 9)],           &[(0, 9)]),
+            (&[(0xE000, 0xE001), (0xD7FE, 0xD7FF)], &[(0xD7FE, 0xD7FF), (0xE000, 0xE001)]),
+        ];
+        for &(input, expected) in tests {
+            assert_eq!(normalize(input.to_vec()), expected);
+        }
+    }
+
+    #[test]
+    fn tables() {
+        let tables = Tables::new(&[
+            (0x41, 0x5A),
+            (0x800, 0x83F),
+            (0xD7FF, 0xE000),
+            (0x10FFFF, 0x10FFFF),
+        ]);
+        assert_eq!(tables.level1[1], 0x07FF_FFFE);
+        assert_eq!(tables.leaves, [!0, 0, 1 << 63, 1]);
+        assert_eq!(tables.level2[0], 0);
+        assert!(tables.level2[1..]
+            .iter()
+            .enumerate()
+            .all(|(i, &idx)| match i + 33 {
+                0x35F => idx == 2,
+                0x380 => idx == 3,
+                _ => idx == 1,
+            }));
+        assert_eq!(tables.level3.1.len(), 2);
+        assert!(tables.level3.0[..255].iter().all(|&idx| idx == 0));
+        assert_eq!(tables.level3.0[255], 1);
+        assert_eq!(tables.level3.1[1][63], 2);
+    }
+}
@@ -0,0 +1,176 @@
+use crate::{
+    parse::{parse_class, ClassError},
+    set::{CharSet, CharSetBuf},
+    CharRange,
+};
+
+/// A single entry point for building a set of codepoints from any source.
+///
+/// Every method adds the described codepoints to the set being built,
+/// and the finished set can be taken out in any of the supported representations.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::SetBuilder;
+/// let ident = SetBuilder::new()
+///     .range('a'..='z')
+///     .range('A'..='Z')
+///     .str("0123456789")
+///     .char('_')
+///     .build();
+/// assert!(ident.contains('_'));
+/// assert_eq!(ident.ranges().len(), 4);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SetBuilder {
+    set: CharSetBuf,
+}
+
+impl SetBuilder {
+    /// A builder for an empty set.
+    pub fn new() -> Self {
+        SetBuilder::default()
+    }
+
+    /// Add a single codepoint.
+    pub fn char(mut self, c: char) -> Self {
+        self.set.insert(c);
+        self
+    }
+
+    /// Add every codepoint produced by an iterator.
+    pub fn chars(mut self, chars: impl IntoIterator<Item = char>) -> Self {
+        self.set.extend(chars);
+        self
+    }
+
+    /// Add every codepoint of a string.
+    pub fn str(self, s: &str) -> Self {
+        self.chars(s.chars())
+    }
+
+    /// Add a range of codepoints.
+    pub fn range(mut self, r: impl Into<CharRange>) -> Self {
+        self.set.insert_range(r.into());
+        self
+    }
+
+    /// Add every range produced by an iterator.
+    pub fn ranges(mut self, ranges: impl IntoIterator<Item = CharRange>) -> Self {
+        self.set.extend(ranges);
+        self
+    }
+
+    /// Add every codepoint of another set.
+    pub fn set(mut self, set: &CharSet) -> Self {
+        self.set.union_with(set);
+        self
+    }
+
+    /// Add every codepoint for which the predicate holds.
+    ///
+    /// This tests every codepoint, so is relatively expensive.
+    pub fn predicate(mut self, f: impl Fn(char) -> bool) -> Self {
+        crate::set::for_each_run(f, |r| {
+            self.set.insert_range(r);
+        });
+        self
+    }
+
+    /// Add every codepoint of a character class, such as `[a-z_]`.
+    ///
+    /// See `parse::parse_class` for the accepted syntax.
+    pub fn class(mut self, class: &str) -> Result<Self, ClassError> {
+        self.set.union_with(&parse_class(class)?);
+        Ok(self)
+    }
+
+    /// Finish building as an owned set.
+    pub fn build(self) -> CharSetBuf {
+        self.set
+    }
+
+    /// Finish building as an owned trie.
+    #[cfg(feature = "owned-trie")]
+    pub fn build_trie(self) -> Result<crate::trie::CharTrieBuf, crate::trie::GenerateError> {
+        crate::trie::CharTrieBuf::from_set(&self.set)
+    }
+
+    /// Finish building as code for a static set.
+    ///
+    /// See `set::generate` for the requirements on the generated code.
+    #[cfg(feature = "new-set")]
+    pub fn generate_set(self) -> proc_macro2::TokenStream {
+        crate::set::generate_from_ranges(self.set.ranges())
+    }
+
+    /// Finish building as code for a static trie.
+    ///
+    /// See `trie::generate` for the requirements on the generated code.
+    #[cfg(all(feature = "trie", feature = "new-trie"))]
+    pub fn generate_trie(self) -> Result<proc_macro2::TokenStream, crate::trie::GenerateError> {
+        crate::trie::generate_from_ranges(self.set.ranges())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn predicate() {
+        let set = SetBuilder::new()
+            .predicate(|c| c.is_ascii_digit() || c == char::MAX)
+            .build();
+        let expected = SetBuilder::new().range('0'..='9').char(char::MAX).build();
+        assert_eq!(set, expected);
+    }
+
+    #[test]
+    fn class() {
+        let set = SetBuilder::new()
+            .char('!')
+            .class("[a-c\\-]")
+            .unwrap()
+            .build();
+        let expected = SetBuilder::new().str("!abc-").build();
+        assert_eq!(set, expected);
+        assert_eq!(
+            SetBuilder::new().class("a-c").unwrap_err(),
+            ClassError::Unbracketed
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "owned-trie")]
+    fn build_trie() {
+        let trie = SetBuilder::new().range('0'..='9').build_trie().unwrap();
+        assert!(trie.contains('7'));
+        assert!(!trie.contains('a'));
+    }
+
+    #[test]
+    #[cfg(all(feature = "trie", feature = "new-trie"))]
+    fn generate_trie() {
+        use alloc::string::ToString;
+
+        let trie = SetBuilder::new().range('\0'..='\x7F').generate_trie();
+        assert_eq!(
+            trie.unwrap().to_string(),
+            crate::trie::generate(|c| c.is_ascii()).unwrap().to_string(),
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "new-set")]
+    fn generate_set() {
+        use alloc::string::ToString;
+
+        let set = SetBuilder::new().str("0123456789").generate_set();
+        assert_eq!(
+            set.to_string(),
+            crate::set::generate(|c| c.is_ascii_digit()).to_string(),
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use {
+    crate::{
+        set,
+        trie::{self, GenerateError},
+        CharRange,
+    },
+    core::{char, fmt, mem},
+};
+
+/// A way to store a static set of codepoints.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Representation {
+    /// A `CharSet`, looked up by binary search over its ranges.
+    CharSet,
+    /// A `CharTrie`, looked up with at most three table reads.
+    CharTrie,
+    /// A `CharTrieBmp`, looked up with at most two table reads.
+    CharTrieBmp,
+}
+
+impl fmt::Display for Representation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// The sizes of each representation of a set, as compared by `best_representation`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Report {
+    /// The representation which was generated.
+    pub chosen: Representation,
+    /// The number of ranges of the set, which a `CharSet` lookup searches.
+    pub range_count: usize,
+    /// The size of the ranges of a `CharSet`, in bytes.
+    pub set_bytes: usize,
+    /// The size of every table of a `CharTrie`, in bytes.
+    pub trie_bytes: usize,
+    /// The size of every table of a `CharTrieBmp`, in bytes,
+    /// if the set has no codepoints past U+FFFF.
+    pub bmp_bytes: Option<usize>,
+}
+
+impl Report {
+    /// The size of the chosen representation, in bytes.
+    pub fn chosen_bytes(&self) -> usize {
+        match self.chosen {
+            Representation::CharSet => self.set_bytes,
+            Representation::CharTrie => self.trie_bytes,
+            Representation::CharTrieBmp => self.bmp_bytes.unwrap_or(self.trie_bytes),
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "CharSet: {} bytes ({} ranges)",
+            self.set_bytes, self.range_count,
+        )?;
+        writeln!(f, "CharTrie: {} bytes", self.trie_bytes)?;
+        match self.bmp_bytes {
+            Some(bytes) => writeln!(f, "CharTrieBmp: {} bytes", bytes)?,
+            None => writeln!(f, "CharTrieBmp: not applicable")?,
+        }
+        write!(f, "chose {} at {} bytes", self.chosen, self.chosen_bytes())
+    }
+}
+
+/// Generate whichever representation of a set is smallest.
+///
+/// This compares a `CharSet` of ranges, a `CharTrie`, and if the set never
+/// leaves the BMP, a `CharTrieBmp`. Ties go to the trie with the fewest table
+/// reads per lookup. Returns the code as `set::generate`, `trie::generate`, or
+/// `trie::generate_bmp` would, along with the sizes that were compared.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::codegen::{best_representation, Representation};
+/// let (_, report) = best_representation(|c| c.is_ascii_digit())?;
+/// assert_eq!(report.chosen, Representation::CharSet);
+/// let (_, report) = best_representation(char::is_uppercase)?;
+/// assert_eq!(report.chosen, Representation::CharTrie);
+/// # Ok::<(), mileage::trie::GenerateError>(())
+/// ```
+pub fn best_representation(
+    f: impl Fn(char) -> bool + Copy,
+) -> Result<(proc_macro2::TokenStream, Report), GenerateError> {
+    let mut range_count = 0;
+    set::for_each_run(f, |_| range_count += 1);
+    let set_bytes = range_count * mem::size_of::<CharRange>();
+
+    let stats = trie::analyze(f)?;
+    let astral = CharRange::closed('\u{10000}', char::MAX).iter().any(f);
+    // The surrogates keep the empty leaf in level2, so a BMP trie has
+    // the same leaves, and only lacks level3 and its single empty block.
+    let bmp_bytes = if astral {
+        None
+    } else {
+        Some(stats.total_bytes - 256 - 64 * if stats.wide { 2 } else { 1 })
+    };
+
+    let candidates = [
+        bmp_bytes.map(|bytes| (bytes, Representation::CharTrieBmp)),
+        Some((stats.total_bytes, Representation::CharTrie)),
+        Some((set_bytes, Representation::CharSet)),
+    ];
+    let (_, chosen) = candidates
+        .iter()
+        .flatten()
+        .min_by_key(|&&(bytes, _)| bytes)
+        .copied()
+        .expect("a trie is always a candidate");
+
+    let tokens = match chosen {
+        Representation::CharSet => set::generate(f),
+        Representation::CharTrie => trie::generate(f)?,
+        Representation::CharTrieBmp => {
+            trie::generate_bmp(f).expect("a BMP trie is only chosen without astral codepoints")
+        }
+    };
+    let report = Report {
+        chosen,
+        range_count,
+        set_bytes,
+        trie_bytes: stats.total_bytes,
+        bmp_bytes,
+    };
+    Ok((tokens, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn best_representation() {
+        use alloc::string::ToString;
+
+        let (tokens, report) = super::best_representation(|c| c.is_ascii_digit()).unwrap();
+        assert_eq!(
+            tokens.to_string(),
+            set::generate(|c| c.is_ascii_digit()).to_string()
+        );
+        assert_eq!(report.range_count, 1);
+        assert_eq!(report.set_bytes, 8);
+        assert_eq!(report.bmp_bytes, Some(256 + 992 + 8));
+        assert_eq!(report.chosen_bytes(), 8);
+
+        // every other codepoint of the BMP
+        let f = |c: char| c as u32 & 1 == 0 && (c as u32) < 0x10000;
+        let (tokens, report) = super::best_representation(f).unwrap();
+        assert_eq!(report.chosen, Representation::CharTrieBmp);
+        assert_eq!(
+            tokens.to_string(),
+            trie::generate_bmp(f).unwrap().to_string()
+        );
+        assert_eq!(report.bmp_bytes, Some(report.trie_bytes - 256 - 64));
+        assert_eq!(
+            report.to_string(),
+            "CharSet: 253952 bytes (31744 ranges)\n\
+             CharTrie: 1584 bytes\n\
+             CharTrieBmp: 1264 bytes\n\
+             chose CharTrieBmp at 1264 bytes",
+        );
+
+        let f = |c: char| c as u32 & 1 == 0;
+        let (_, report) = super::best_representation(f).unwrap();
+        assert_eq!(report.chosen, Representation::CharTrie);
+        assert_eq!(report.bmp_bytes, None);
+    }
+}
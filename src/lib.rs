@@ -15,8 +15,30 @@
 //! - `trie`: Adds the `CharTrie` type.
 //! - `map`: Adds the `CharMap` reference types.
 //! - `owned-set`: Adds the `CharSetBuf` type.
+//! - `case-fold`: Adds Unicode simple case folding to `CharSetBuf`.
+//! - `inline-set`: Stores small `CharSetBuf`s inline rather than on the heap.
+//! - `debug-validate`: Checks that a `CharSetBuf` is still valid after every edit.
+//! - `owned-trie`: Adds the `CharTrieBuf` type.
+//! - `ffi`: Adds a C-compatible `RawTrie` and the `mileage_trie_contains` lookup.
+//! - `const-generate`: Adds the `generate_const!` macro for building static `CharTrie`s
+//!   from ranges during const evaluation.
+//! - `macros`: Adds the `charset!` macro for building static `CharSet`s,
+//!   and with `trie`, the `chartrie!` macro for building static `CharTrie`s.
+//! - `new-set`: Adds code generation support for `CharSet`s.
 //! - `new-trie`: Adds code generation support for `CharTrie`s.
+//! - `trie-source`: Adds code generation for `CharTrie`s as plain source strings.
+//! - `ucd`: Adds parsing of Unicode Character Database property files into `CharSetBuf`s.
 //! - `par-iter`: Adds implementations of `rayon::IntoParallelIterator`.
+//! - `rand`: Adds random sampling of `CharSet` members.
+//! - `proptest`: Adds `proptest` strategies for sets.
+//! - `arbitrary`: Adds `arbitrary::Arbitrary` for `CharSetBuf`.
+//! - `interop-rangemap`: Adds conversions between `CharSetBuf` and `rangemap::RangeInclusiveSet`.
+//! - `interop-ranges`: Adds conversions between `CharSetBuf` and `ranges::Ranges`.
+//! - `serde`: Adds `serde` serialization for tries, and deserialization for `CharTrieBuf`.
+//! - `rkyv`: Adds `rkyv` archives of tries and `CharSetBuf`s, which are used in place.
+//! - `ucd-trie`: Adds conversions between `CharTrieBuf` and the sets of `ucd-trie`.
+//! - `icu`: Adds conversions from ICU4X inversion lists and property sets to `CharSetBuf` and `CharTrieBuf`.
+//! - `test-utils`: Adds `trie::verify`, to check a trie against the predicate it was generated from.
 //!
 //! # Examples
 //!
@@ -37,9 +59,23 @@ extern crate alloc;
 #[cfg(any(feature = "std", test))]
 extern crate std;
 
+#[cfg(all(feature = "set", feature = "owned-set"))]
+mod builder;
+/// Support for choosing how to generate a static set.
+#[cfg(all(
+    feature = "set",
+    feature = "trie",
+    feature = "new-set",
+    feature = "new-trie"
+))]
+pub mod codegen;
 /// Support for the `CharMap` family of types.
 #[cfg(feature = "map")]
 pub mod map;
+/// Support for parsing hexadecimal codepoint notations.
+pub mod parse;
+/// Support for recording where generated tables came from.
+pub mod provenance;
 /// Support for the `CharRange` family of types.
 pub mod range;
 /// Support for the `CharSet` family of types.
@@ -49,8 +85,15 @@ pub mod set;
 #[cfg(feature = "trie")]
 pub mod trie;
 
+#[cfg(all(feature = "set", feature = "owned-set"))]
+pub use builder::SetBuilder;
 pub use range::CharRange;
 
+#[cfg(feature = "macros")]
+pub use mileage_macros::charset;
+#[cfg(all(feature = "macros", feature = "trie"))]
+pub use mileage_macros::chartrie;
+
 pub(crate) const BEFORE_SURROGATE: char = '\u{D7FF}';
 pub(crate) const AFTER_SURROGATE: char = '\u{E000}';
 
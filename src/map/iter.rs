@@ -3,8 +3,8 @@ use {
     core::{iter, slice::Iter as SliceIter},
 };
 
-pub use set::RangeIter;
 use core::iter::FusedIterator;
+pub use set::{Chars, RangeIter};
 
 #[derive(Clone, Debug)]
 pub struct RangeValueIter<'a, T> {
@@ -28,17 +28,32 @@ impl<'a, T> CharMapRef<'a, T> {
         }
     }
 
+    /// Iterate the compact ranges of this mapping, from highest to lowest.
+    pub fn ranges_rev(self) -> iter::Rev<RangeIter<'a>> {
+        self.ranges().rev()
+    }
+
     /// Iterate the codepoints of this mapping.
-    pub fn chars(self) -> impl Iterator<Item = char> + 'a {
-        self.ranges().flat_map(IntoIterator::into_iter)
+    pub fn chars(self) -> Chars<'a> {
+        Chars::new(self.ranges())
+    }
+
+    /// Iterate the codepoints of this mapping, from highest to lowest.
+    pub fn chars_rev(self) -> iter::Rev<Chars<'a>> {
+        self.chars().rev()
     }
 
     /// Iterate the range-value mappings of this mapping.
     pub fn range_values(self) -> RangeValueIter<'a, T> {
         RangeValueIter {
-            raw: self.ranges().zip(self.values.iter())
+            raw: self.ranges().zip(self.values.iter()),
         }
     }
+
+    /// Iterate the range-value mappings of this mapping, from highest to lowest.
+    pub fn range_values_rev(self) -> iter::Rev<RangeValueIter<'a, T>> {
+        self.range_values().rev()
+    }
 }
 
 // forward zip iterators
@@ -72,3 +87,26 @@ impl<'a, T> DoubleEndedIterator for RangeValueIter<'a, T> {
 impl<'a, T> ExactSizeIterator for RangeValueIter<'a, T> {}
 
 impl<'a, T> FusedIterator for RangeValueIter<'a, T> {}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, alloc::vec::Vec};
+
+    #[test]
+    fn reverse_parity() {
+        let ranges = [CharRange::closed('a', 'c'), CharRange::closed('x', 'z')];
+        let map = CharMapRef::from_raw(&ranges, &[1, 2]);
+
+        let mut range_values = map.range_values().collect::<Vec<_>>();
+        range_values.reverse();
+        assert_eq!(map.range_values_rev().collect::<Vec<_>>(), range_values);
+
+        let mut ranges = map.ranges().collect::<Vec<_>>();
+        ranges.reverse();
+        assert_eq!(map.ranges_rev().collect::<Vec<_>>(), ranges);
+
+        let mut chars = map.chars().collect::<Vec<_>>();
+        chars.reverse();
+        assert_eq!(map.chars_rev().collect::<Vec<_>>(), chars);
+    }
+}
@@ -2,7 +2,6 @@ use {
     crate::range::CharRange,
     core::{char, ops::Index},
 };
-use core::ops::Deref;
 
 mod iter;
 
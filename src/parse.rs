@@ -0,0 +1,518 @@
+#[cfg(all(feature = "set", feature = "owned-set"))]
+use crate::{set::CharSetBuf, AFTER_SURROGATE, BEFORE_SURROGATE};
+#[cfg(feature = "ucd")]
+use alloc::{collections::BTreeMap, string::String};
+use {
+    crate::CharRange,
+    core::{char, fmt},
+};
+
+/// An error from parsing a codepoint notation.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ParseError {
+    /// There were no hexadecimal digits.
+    Empty,
+    /// A character that is not a hexadecimal digit was found.
+    InvalidDigit,
+    /// The value is above the highest codepoint.
+    OutOfRange,
+    /// The value is a surrogate codepoint, which is not a `char`.
+    Surrogate,
+    /// The low end of a range is above its high end.
+    Reversed,
+    /// A line of a UCD property file has no property field.
+    ///
+    /// This is only returned by `parse_ucd_properties`, as the error of a `UcdError`;
+    /// parsing a codepoint or range alone never fails this way.
+    MissingProperty,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ParseError::Empty => "no hexadecimal digits in codepoint",
+            ParseError::InvalidDigit => "invalid hexadecimal digit in codepoint",
+            ParseError::OutOfRange => "codepoint is above U+10FFFF",
+            ParseError::Surrogate => "codepoint is a surrogate",
+            ParseError::Reversed => "codepoint range is reversed",
+            ParseError::MissingProperty => "no property for codepoint range",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+/// An error from parsing a character class.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ClassError {
+    /// The class is not enclosed in `[` and `]`, or contains an unescaped bracket.
+    Unbracketed,
+    /// A backslash is not followed by a recognized escape.
+    InvalidEscape,
+    /// A `\u{...}` or `\x..` escape is not a valid codepoint.
+    Codepoint(ParseError),
+    /// The low end of a range is above its high end.
+    Reversed,
+}
+
+impl fmt::Display for ClassError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClassError::Unbracketed => f.write_str("character class is not enclosed in brackets"),
+            ClassError::InvalidEscape => f.write_str("invalid escape in character class"),
+            ClassError::Codepoint(err) => write!(f, "invalid escape in character class: {}", err),
+            ClassError::Reversed => f.write_str("character class range is reversed"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ClassError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClassError::Codepoint(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a codepoint in hexadecimal notation.
+///
+/// The digits may be prefixed with `U+` (as used by Unicode and ICU),
+/// `0x` (as used by many programming languages), or nothing at all
+/// (as used by the Unicode Character Database files).
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::parse::{parse_codepoint, ParseError};
+/// assert_eq!(parse_codepoint("U+1F600"), Ok('\u{1F600}'));
+/// assert_eq!(parse_codepoint("0x1F600"), Ok('\u{1F600}'));
+/// assert_eq!(parse_codepoint("1F600"), Ok('\u{1F600}'));
+/// assert_eq!(parse_codepoint("D800"), Err(ParseError::Surrogate));
+/// ```
+pub fn parse_codepoint(s: &str) -> Result<char, ParseError> {
+    let cp = parse_scalar(s)?;
+    char::from_u32(cp).ok_or(ParseError::Surrogate)
+}
+
+/// Parse a codepoint as `parse_codepoint` does, but allow surrogates.
+fn parse_scalar(s: &str) -> Result<u32, ParseError> {
+    let digits = ["U+", "u+", "0x", "0X"]
+        .iter()
+        .find_map(|prefix| s.strip_prefix(prefix))
+        .unwrap_or(s);
+    if digits.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(ParseError::InvalidDigit);
+    }
+    match u32::from_str_radix(digits, 16) {
+        Ok(cp) if cp <= char::MAX as u32 => Ok(cp),
+        _ => Err(ParseError::OutOfRange),
+    }
+}
+
+/// Parse a codepoint range in the Unicode Character Database notation.
+///
+/// The range is either two codepoints separated by `..` or a single codepoint,
+/// each as accepted by `parse_codepoint`.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::{parse::parse_range, CharRange};
+/// assert_eq!(parse_range("0030..0039"), Ok(CharRange::from('0'..='9')));
+/// assert_eq!(parse_range("U+0041..U+005A"), Ok(CharRange::from('A'..='Z')));
+/// assert_eq!(parse_range("005F"), Ok(CharRange::singleton('_')));
+/// ```
+pub fn parse_range(s: &str) -> Result<CharRange, ParseError> {
+    let (low, high) = parse_scalar_range(s)?;
+    let scalar = |cp| char::from_u32(cp).ok_or(ParseError::Surrogate);
+    Ok(CharRange::closed(scalar(low)?, scalar(high)?))
+}
+
+/// Parse a range as `parse_range` does, but allow surrogates at either end.
+fn parse_scalar_range(s: &str) -> Result<(u32, u32), ParseError> {
+    let mut parts = s.splitn(2, "..");
+    let low = parse_scalar(parts.next().unwrap_or(""))?;
+    let high = match parts.next() {
+        Some(high) => parse_scalar(high)?,
+        None => low,
+    };
+    if low > high {
+        Err(ParseError::Reversed)
+    } else {
+        Ok((low, high))
+    }
+}
+
+/// An error from parsing a Unicode Character Database range list.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct UcdError {
+    /// The line of the invalid range, counting from 1.
+    pub line: usize,
+    /// What was wrong with the range.
+    pub error: ParseError,
+}
+
+impl fmt::Display for UcdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UcdError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Parse a list of ranges in the format of the Unicode Character Database files.
+///
+/// Each line holds a range as accepted by `parse_range`, optionally followed
+/// by `;` and more fields, which are ignored. Comments start with `#`,
+/// and blank lines are skipped. This is the format written by `CharSet::write_ucd`.
+///
+/// The UCD counts surrogates as codepoints, so they are dropped from the
+/// ranges rather than rejected.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::{parse::parse_ucd, CharRange};
+/// let set = parse_ucd("
+/// 0030..0039    ; Hex_Digit # Nd  [10] DIGIT ZERO..DIGIT NINE
+/// 0041..0046    ; Hex_Digit # L&   [6] LATIN CAPITAL LETTER A..LATIN CAPITAL LETTER F
+/// ").unwrap();
+/// assert_eq!(set.ranges().len(), 2);
+/// assert!(set.contains('F'));
+/// ```
+#[cfg(all(feature = "set", feature = "owned-set"))]
+pub fn parse_ucd(s: &str) -> Result<CharSetBuf, UcdError> {
+    let mut set = CharSetBuf::new();
+    for (i, line) in s.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        let field = line.split(';').next().unwrap_or("").trim();
+        if field.is_empty() {
+            continue;
+        }
+        let r = ucd_range(field).map_err(|error| UcdError { line: i + 1, error })?;
+        set.insert_range(r);
+    }
+    Ok(set)
+}
+
+/// Parse a Unicode Character Database property file into the set of each property.
+///
+/// This reads files such as `PropList.txt` and `DerivedCoreProperties.txt`,
+/// where each line holds a range as accepted by `parse_ucd`, then `;` and the
+/// property name. Properties with a value, such as `InCB; Linker`, are named
+/// `InCB=Linker`. Comments, blank lines, and surrogates are handled as `parse_ucd` does.
+///
+/// With `new-trie`, `trie::generate_ucd` generates a `CharTrie` for each property.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::parse::parse_ucd_properties;
+/// let props = parse_ucd_properties("
+/// 0009..000D    ; White_Space # Cc   [5] <control-0009>..<control-000D>
+/// 0020          ; White_Space # Zs       SPACE
+/// 0030..0039    ; Hex_Digit # Nd  [10] DIGIT ZERO..DIGIT NINE
+/// 094D          ; InCB; Linker # Mn       DEVANAGARI SIGN VIRAMA
+/// ").unwrap();
+/// assert_eq!(props.len(), 3);
+/// assert!(props["White_Space"].contains(' '));
+/// assert!(props["Hex_Digit"].contains('7'));
+/// assert!(props["InCB=Linker"].contains('\u{94D}'));
+/// ```
+#[cfg(feature = "ucd")]
+pub fn parse_ucd_properties(s: &str) -> Result<BTreeMap<String, CharSetBuf>, UcdError> {
+    let mut props = BTreeMap::<String, CharSetBuf>::new();
+    for (i, line) in s.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        let mut fields = line.split(';').map(str::trim);
+        let field = fields.next().unwrap_or("");
+        if field.is_empty() {
+            continue;
+        }
+        let err = |error| UcdError { line: i + 1, error };
+        let r = ucd_range(field).map_err(err)?;
+        let mut name = String::from(fields.next().unwrap_or(""));
+        if name.is_empty() {
+            return Err(err(ParseError::MissingProperty));
+        }
+        for value in fields {
+            name.push('=');
+            name.push_str(value);
+        }
+        props.entry(name).or_default().insert_range(r);
+    }
+    Ok(props)
+}
+
+/// Parse a range as `parse_range` does, but drop surrogates from it.
+#[cfg(all(feature = "set", feature = "owned-set"))]
+fn ucd_range(s: &str) -> Result<CharRange, ParseError> {
+    let (low, high) = parse_scalar_range(s)?;
+    // empty if the range holds only surrogates
+    Ok(CharRange::closed(
+        char::from_u32(low).unwrap_or(AFTER_SURROGATE),
+        char::from_u32(high).unwrap_or(BEFORE_SURROGATE),
+    ))
+}
+
+/// Parse a regex-like character class.
+///
+/// The class is enclosed in `[` and `]` and holds any number of codepoints
+/// and `low-high` ranges. A leading `^` negates the class, and a `-` at the
+/// start or end of the class stands for itself. Backslash escapes are
+/// `\n`, `\r`, `\t`, `\0`, `\xHH`, `\u{HHHHHH}`, and `\` before any
+/// ASCII punctuation, such as `\]` or `\-`.
+///
+/// This is also available as the `FromStr` implementation of `CharSetBuf`.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::{parse::parse_class, set::CharSetBuf};
+/// let ident = parse_class("[a-zA-Z0-9_]").unwrap();
+/// assert!(ident.contains('_'));
+/// assert_eq!(ident.ranges().len(), 4);
+///
+/// let non_ascii: CharSetBuf = r"[^\u{0}-\u{7F}]".parse().unwrap();
+/// assert!(!non_ascii.contains('a'));
+/// assert!(non_ascii.contains('\u{80}'));
+/// ```
+#[cfg(all(feature = "set", feature = "owned-set"))]
+pub fn parse_class(s: &str) -> Result<CharSetBuf, ClassError> {
+    let body = s
+        .strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+        .ok_or(ClassError::Unbracketed)?;
+    let (negated, mut rest) = match body.strip_prefix('^') {
+        Some(body) => (true, body),
+        None => (false, body),
+    };
+    let mut set = CharSetBuf::new();
+    while !rest.is_empty() {
+        let (low, after) = class_atom(rest)?;
+        rest = after;
+        let high = match rest.strip_prefix('-') {
+            Some(after) if !after.is_empty() => {
+                let (high, after) = class_atom(after)?;
+                rest = after;
+                high
+            }
+            _ => low,
+        };
+        if low > high {
+            return Err(ClassError::Reversed);
+        }
+        set.insert_range(CharRange::closed(low, high));
+    }
+    Ok(if negated { set.complement() } else { set })
+}
+
+/// Parse one (possibly escaped) codepoint from the front of a class body.
+#[cfg(all(feature = "set", feature = "owned-set"))]
+fn class_atom(s: &str) -> Result<(char, &str), ClassError> {
+    let mut chars = s.chars();
+    match chars.next() {
+        None | Some('[') | Some(']') => Err(ClassError::Unbracketed),
+        Some('\\') => class_escape(chars.as_str()),
+        Some(c) => Ok((c, chars.as_str())),
+    }
+}
+
+/// Parse the remainder of an escape sequence, after the backslash.
+#[cfg(all(feature = "set", feature = "owned-set"))]
+fn class_escape(s: &str) -> Result<(char, &str), ClassError> {
+    let mut chars = s.chars();
+    let c = chars.next().ok_or(ClassError::InvalidEscape)?;
+    let rest = chars.as_str();
+    match c {
+        'n' => Ok(('\n', rest)),
+        'r' => Ok(('\r', rest)),
+        't' => Ok(('\t', rest)),
+        '0' => Ok(('\0', rest)),
+        'x' => {
+            let digits = rest.get(..2).ok_or(ClassError::InvalidEscape)?;
+            if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(ClassError::InvalidEscape);
+            }
+            let c = parse_codepoint(digits).map_err(ClassError::Codepoint)?;
+            Ok((c, &rest[2..]))
+        }
+        'u' => {
+            let rest = rest.strip_prefix('{').ok_or(ClassError::InvalidEscape)?;
+            let end = rest.find('}').ok_or(ClassError::InvalidEscape)?;
+            let c = parse_codepoint(&rest[..end]).map_err(ClassError::Codepoint)?;
+            Ok((c, &rest[end + 1..]))
+        }
+        c if c.is_ascii_punctuation() => Ok((c, rest)),
+        _ => Err(ClassError::InvalidEscape),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codepoints() {
+        #[rustfmt::skip]
+        let test_data = [
+            ("0", Ok('\0')),
+            ("41", Ok('A')),
+            ("0041", Ok('A')),
+            ("U+0041", Ok('A')),
+            ("u+0041", Ok('A')),
+            ("0x41", Ok('A')),
+            ("10FFFF", Ok(char::MAX)),
+            ("00000000000041", Ok('A')),
+            ("", Err(ParseError::Empty)),
+            ("U+", Err(ParseError::Empty)),
+            ("+41", Err(ParseError::InvalidDigit)),
+            ("U+ 41", Err(ParseError::InvalidDigit)),
+            ("G", Err(ParseError::InvalidDigit)),
+            ("110000", Err(ParseError::OutOfRange)),
+            ("FFFFFFFFFF", Err(ParseError::OutOfRange)),
+            ("DFFF", Err(ParseError::Surrogate)),
+        ];
+
+        for &(s, result) in &test_data {
+            assert_eq!(parse_codepoint(s), result, "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn ranges() {
+        #[rustfmt::skip]
+        let test_data = [
+            ("0041..005A", Ok(CharRange::from('A'..='Z'))),
+            ("0041", Ok(CharRange::singleton('A'))),
+            ("0041..0041", Ok(CharRange::singleton('A'))),
+            ("005A..0041", Err(ParseError::Reversed)),
+            ("0041..", Err(ParseError::Empty)),
+            ("..005A", Err(ParseError::Empty)),
+            ("0041...005A", Err(ParseError::InvalidDigit)),
+        ];
+
+        for &(s, result) in &test_data {
+            assert_eq!(parse_range(s), result, "{:?}", s);
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "set", feature = "owned-set"))]
+    fn ucd() {
+        use alloc::vec::Vec;
+
+        #[rustfmt::skip]
+        let test_data: &[(&str, Result<&[CharRange], UcdError>)] = &[
+            ("",                                Ok(&[])),
+            ("# comment only\n\n",              Ok(&[])),
+            ("0041..005A\n005F\n",              Ok(&[CharRange::closed('A', 'Z'), CharRange::singleton('_')])),
+            ("0061..007A ; Lower # a..z",       Ok(&[CharRange::closed('a', 'z')])),
+            ("  0030..0039\t; Digit\r\n0035",    Ok(&[CharRange::closed('0', '9')])),
+            ("D800..DFFF ; Cs",                 Ok(&[])),
+            ("D7FF..E000",                      Ok(&[CharRange::closed('\u{D7FF}', '\u{E000}')])),
+            ("0041\n005A..0041",                Err(UcdError { line: 2, error: ParseError::Reversed })),
+            ("0041\n\n00G1",                     Err(UcdError { line: 3, error: ParseError::InvalidDigit })),
+            ("110000",                          Err(UcdError { line: 1, error: ParseError::OutOfRange })),
+        ];
+
+        for &(s, result) in test_data {
+            let parsed = parse_ucd(s).map(|set| set.ranges().collect::<Vec<_>>());
+            assert_eq!(parsed, result.map(<[_]>::to_vec), "{:?}", s);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "ucd")]
+    fn ucd_properties() {
+        use alloc::vec::Vec;
+
+        let props = parse_ucd_properties(
+            "
+# PropList.txt
+0009..000D    ; White_Space # Cc   [5] <control-0009>..<control-000D>
+0020          ; White_Space # Zs       SPACE
+D800..DFFF    ; Surrogate
+0915..0939    ; InCB; Consonant # Lo  [37] DEVANAGARI LETTER KA..DEVANAGARI LETTER HA
+000A..000C    ; White_Space
+",
+        )
+        .unwrap();
+        let props: Vec<(&str, Vec<CharRange>)> = props
+            .iter()
+            .map(|(name, set)| (&**name, set.ranges().collect()))
+            .collect();
+        assert_eq!(
+            props,
+            [
+                (
+                    "InCB=Consonant",
+                    alloc::vec![CharRange::closed('\u{915}', '\u{939}')]
+                ),
+                ("Surrogate", alloc::vec![]),
+                (
+                    "White_Space",
+                    alloc::vec![CharRange::closed('\t', '\r'), CharRange::singleton(' ')]
+                ),
+            ]
+        );
+
+        #[rustfmt::skip]
+        let test_data = [
+            ("0041",              UcdError { line: 1, error: ParseError::MissingProperty }),
+            ("0041 ;  # Lu",      UcdError { line: 1, error: ParseError::MissingProperty }),
+            ("0041 ; A\n0G ; B", UcdError { line: 2, error: ParseError::InvalidDigit }),
+        ];
+        for &(s, error) in &test_data {
+            assert_eq!(parse_ucd_properties(s), Err(error), "{:?}", s);
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "set", feature = "owned-set"))]
+    fn classes() {
+        use alloc::vec::Vec;
+
+        let all = CharRange::from(..);
+        #[rustfmt::skip]
+        let test_data: &[(&str, Result<&[CharRange], ClassError>)] = &[
+            ("[]",                  Ok(&[])),
+            ("[^]",                 Ok(&[all])),
+            ("[a]",                 Ok(&[CharRange::singleton('a')])),
+            ("[a-z]",               Ok(&[CharRange::closed('a', 'z')])),
+            ("[a-cb-f_]",           Ok(&[CharRange::closed('_', '_'), CharRange::closed('a', 'f')])),
+            ("[-a]",                Ok(&[CharRange::singleton('-'), CharRange::singleton('a')])),
+            ("[a-]",                Ok(&[CharRange::singleton('-'), CharRange::singleton('a')])),
+            (r"[\-\]\[\\]",         Ok(&[CharRange::singleton('-'), CharRange::closed('[', ']')])),
+            (r"[\n\t]",             Ok(&[CharRange::closed('\t', '\n')])),
+            (r"[\x41-\x43]",        Ok(&[CharRange::closed('A', 'C')])),
+            (r"[\u{1F600}-\u{1F64F}]", Ok(&[CharRange::closed('\u{1F600}', '\u{1F64F}')])),
+            (r"[^\u{80}-\u{10FFFF}]", Ok(&[CharRange::closed('\0', '\u{7F}')])),
+            ("a-z",                 Err(ClassError::Unbracketed)),
+            ("[a-z",                Err(ClassError::Unbracketed)),
+            ("[a]z]",               Err(ClassError::Unbracketed)),
+            (r"[\q]",               Err(ClassError::InvalidEscape)),
+            (r"[a\]",               Err(ClassError::InvalidEscape)),
+            (r"[\x4]",              Err(ClassError::InvalidEscape)),
+            (r"[\u41]",             Err(ClassError::InvalidEscape)),
+            (r"[\u{D800}]",         Err(ClassError::Codepoint(ParseError::Surrogate))),
+            ("[z-a]",               Err(ClassError::Reversed)),
+        ];
+
+        for &(s, result) in test_data {
+            let parsed = parse_class(s).map(|set| set.ranges().collect::<Vec<_>>());
+            assert_eq!(parsed, result.map(<[_]>::to_vec), "{:?}", s);
+        }
+    }
+}
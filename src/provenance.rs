@@ -0,0 +1,70 @@
+/// A record of the data a generated table was built from.
+///
+/// Code generation can embed one of these next to a table, so that a deployed
+/// binary can report exactly which data it embeds.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::provenance::{Provenance, SourceFile};
+/// static PROVENANCE: Provenance<'static> = Provenance {
+///     unicode_version: Some("12.1.0"),
+///     sources: &[SourceFile {
+///         path: "PropList.txt",
+///         hash: "sha256:2e2bd8bd4ba0ac1e2b1d5b0d6d6a0c3b2f0d7e5b0e4d8f9d6f0f2a4a3c1b9e7d",
+///     }],
+///     options: "White_Space",
+/// };
+/// assert_eq!(PROVENANCE.sources[0].path, "PropList.txt");
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Provenance<'a> {
+    /// The version of the Unicode Character Database the data is from, if any.
+    pub unicode_version: Option<&'a str>,
+    /// The source files the data was read from.
+    pub sources: &'a [SourceFile<'a>],
+    /// A free-form description of the options the generator was run with.
+    pub options: &'a str,
+}
+
+/// A source file that a generated table was built from.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct SourceFile<'a> {
+    /// The path or name of the file.
+    pub path: &'a str,
+    /// A hash of the file's contents, conventionally prefixed by the algorithm (e.g. `sha256:`).
+    pub hash: &'a str,
+}
+
+#[cfg(feature = "new-trie")]
+impl quote::ToTokens for Provenance<'_> {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let unicode_version = match self.unicode_version {
+            Some(version) => quote::quote!(Some(#version)),
+            None => quote::quote!(None),
+        };
+        let sources = self.sources;
+        let options = self.options;
+        tokens.extend(quote::quote! {
+            Provenance {
+                unicode_version: #unicode_version,
+                sources: &[#(#sources),*],
+                options: #options,
+            }
+        })
+    }
+}
+
+#[cfg(feature = "new-trie")]
+impl quote::ToTokens for SourceFile<'_> {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let path = self.path;
+        let hash = self.hash;
+        tokens.extend(quote::quote! {
+            SourceFile {
+                path: #path,
+                hash: #hash,
+            }
+        })
+    }
+}
@@ -70,7 +70,7 @@ impl Iterator for Iter {
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
         // Avoid unstable `<&mut I as ExactSizeIterator>::is_empty`
-        if (&*self).is_empty() {
+        if Iter::is_empty(self) {
             return None;
         }
 
@@ -93,13 +93,31 @@ impl Iterator for Iter {
     fn last(mut self) -> Option<Self::Item> {
         self.next_back()
     }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        if n >= self.len() {
+            self.low = char::MAX;
+            self.high = '\0';
+            return None;
+        }
+
+        let mut low = self.low as u32 + n as u32;
+        if self.low <= BEFORE_SURROGATE && low > BEFORE_SURROGATE as u32 {
+            low += AFTER_SURROGATE as u32 - (BEFORE_SURROGATE as u32 + 1);
+        }
+        #[allow(unsafe_code)]
+        unsafe {
+            self.low = char::from_u32_unchecked(low);
+        }
+        self.next()
+    }
 }
 
 impl DoubleEndedIterator for Iter {
     #[inline]
     fn next_back(&mut self) -> Option<Self::Item> {
         // Avoid unstable `<&mut I as ExactSizeIterator>::is_empty`
-        if (&*self).is_empty() {
+        if Iter::is_empty(self) {
             return None;
         }
 
@@ -115,11 +133,11 @@ impl ExactSizeIterator for Iter {
     fn len(&self) -> usize {
         #[allow(clippy::range_plus_one)] // for ExactSizeIterator impl
         let len = (self.low as u32..self.high as u32 + 1).len() as u32;
-        ((if self.low <= BEFORE_SURROGATE && self.high >= AFTER_SURROGATE {
+        (if self.low <= BEFORE_SURROGATE && self.high >= AFTER_SURROGATE {
             len - (AFTER_SURROGATE as u32 - (BEFORE_SURROGATE as u32 + 1))
         } else {
             len
-        }) as usize)
+        }) as usize
     }
 }
 
@@ -132,7 +150,7 @@ mod tests {
     use super::*;
     use alloc::vec::Vec;
 
-    fn trivial(r: CharRange) -> impl Iterator<Item = char> + DoubleEndedIterator {
+    fn trivial(r: CharRange) -> impl DoubleEndedIterator<Item = char> {
         (r.low as u32..=r.high as u32).filter_map(char::from_u32)
     }
 
@@ -177,6 +195,17 @@ mod tests {
         assert_eq!(r.iter().collect::<Vec<_>>(), r.iter().collect::<Vec<_>>());
     }
 
+    #[test]
+    fn nth() {
+        let r = CharRange::from(..);
+        for &n in &[0, 1, 0xD7FF, 0xD800, 0xD801, 0x1000, 0x10F7FF, 0x10F800] {
+            assert_eq!(r.iter().nth(n), trivial(r).nth(n), "{}", n);
+        }
+        let mut iter = CharRange::from(BEFORE_SURROGATE..=AFTER_SURROGATE).iter();
+        assert_eq!(iter.nth(1), Some(AFTER_SURROGATE));
+        assert_eq!(iter.next(), None);
+    }
+
     #[test]
     fn alphabet() {
         let r = CharRange::from('a'..='z');
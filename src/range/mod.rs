@@ -30,6 +30,7 @@ mod par_iter;
 /// the range is empty. If you want to iterate in decreasing order, use `.iter().rev()`.
 /// All empty ranges are considered equal no matter the internal state.
 #[derive(Copy, Clone, Eq)]
+#[repr(C)]
 pub struct CharRange {
     /// The lowest codepoint in this range (inclusive).
     pub low: char,
@@ -0,0 +1,93 @@
+use {
+    crate::set::{BytesError, CharSet, CharSetBuf},
+    core::{fmt, mem},
+    rkyv::{
+        munge::munge,
+        rancor::Fallible,
+        ser::{Writer, WriterExt},
+        vec::{ArchivedVec, VecResolver},
+        Archive, Place, Portable, Serialize,
+    },
+};
+
+/// An archived `CharSetBuf`, which can be used in place, without deserializing.
+///
+/// The archive holds the binary serialization of the set, as `CharSet::to_bytes`,
+/// aligned for `CharRange`, so `as_set` borrows the ranges straight from the archive.
+/// The archived bytes are checked by `as_set`, not by `rkyv::access`.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::{set::{ArchivedCharSet, CharSetBuf}, CharRange};
+/// let set: CharSetBuf = CharRange::closed('0', '9').into();
+/// let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&set).unwrap();
+/// let archived = rkyv::access::<ArchivedCharSet, rkyv::rancor::Error>(&bytes).unwrap();
+/// assert_eq!(archived.as_set(), Ok(&*set));
+/// ```
+#[derive(Portable, rkyv::bytecheck::CheckBytes)]
+#[bytecheck(crate = rkyv::bytecheck)]
+#[repr(transparent)]
+pub struct ArchivedCharSet {
+    bytes: ArchivedVec<u8>,
+}
+
+impl ArchivedCharSet {
+    /// Borrow the archived set, checking that it is valid.
+    pub fn as_set(&self) -> Result<&CharSet, BytesError> {
+        CharSet::from_bytes(self.bytes.as_slice())
+    }
+}
+
+impl fmt::Debug for ArchivedCharSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ArchivedCharSet")
+            .field(&self.as_set())
+            .finish()
+    }
+}
+
+impl Archive for CharSetBuf {
+    type Archived = ArchivedCharSet;
+    type Resolver = VecResolver;
+
+    fn resolve(&self, resolver: VecResolver, out: Place<ArchivedCharSet>) {
+        let len = CharSet::BYTES_HEADER.len() + self.as_bytes().len();
+        munge!(let ArchivedCharSet { bytes } = out);
+        ArchivedVec::resolve_from_len(len, resolver, bytes);
+    }
+}
+
+impl<S: Fallible + Writer + ?Sized> Serialize<S> for CharSetBuf {
+    fn serialize(&self, serializer: &mut S) -> Result<VecResolver, S::Error> {
+        // the header is 8 bytes, so the ranges are aligned with it
+        let pos = serializer.align(mem::align_of::<crate::CharRange>())?;
+        serializer.write(&self.to_bytes())?;
+        Ok(VecResolver::from_pos(pos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::CharRange, rkyv::rancor::Error};
+
+    #[test]
+    fn round_trip() {
+        let set: CharSetBuf = "the quick brown fox jumps over the lazy dog"
+            .chars()
+            .collect();
+        let bytes = rkyv::to_bytes::<Error>(&(1u8, set.clone())).unwrap();
+        let archived =
+            rkyv::access::<rkyv::tuple::ArchivedTuple2<u8, ArchivedCharSet>, Error>(&bytes)
+                .unwrap();
+        assert_eq!(archived.1.as_set(), Ok(&*set));
+
+        let empty = rkyv::to_bytes::<Error>(&CharSetBuf::new()).unwrap();
+        let archived = rkyv::access::<ArchivedCharSet, Error>(&empty).unwrap();
+        assert_eq!(archived.as_set(), Ok(CharSet::from_raw(&[])));
+        assert_ne!(
+            archived.as_set(),
+            Ok(CharSet::from_raw(&[CharRange::singleton('a')]))
+        );
+    }
+}
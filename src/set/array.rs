@@ -0,0 +1,333 @@
+use {
+    crate::{
+        range::CharRange,
+        set::{ops, CharSet},
+    },
+    core::{
+        cmp,
+        convert::TryFrom,
+        fmt, hash,
+        ops::{Bound, Deref, Range},
+    },
+};
+
+/// A set of codepoints stored in a fixed-capacity array of at most `N` ranges.
+///
+/// This supports the same edits as `CharSetBuf`, but never allocates.
+/// An edit which would need more than `N` ranges fails with `CapacityError`
+/// and leaves the set unchanged.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::{set::{CapacityError, CharSetArray}, CharRange};
+/// let mut set = CharSetArray::<2>::new();
+/// set.insert_range(CharRange::from('a'..='z'))?;
+/// set.insert('0')?;
+/// assert_eq!(set.insert('_'), Err(CapacityError));
+/// assert!(set.insert('1')?);
+/// assert_eq!(set.range_count(), 2);
+/// # Ok::<(), CapacityError>(())
+/// ```
+#[derive(Copy, Clone)]
+pub struct CharSetArray<const N: usize> {
+    ranges: [CharRange; N],
+    len: usize,
+}
+
+/// The error returned when an edit would need more ranges than a
+/// `CharSetArray` has room for.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct CapacityError;
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("set would need more ranges than its capacity")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CapacityError {}
+
+impl<const N: usize> Deref for CharSetArray<N> {
+    type Target = CharSet;
+
+    fn deref(&self) -> &Self::Target {
+        CharSet::from_raw(&self.ranges[..self.len])
+    }
+}
+
+impl<const N: usize> fmt::Debug for CharSetArray<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CharSetArray")
+            .field("ranges", &&self.ranges[..self.len])
+            .finish()
+    }
+}
+
+impl<const N: usize> fmt::Display for CharSetArray<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<const N: usize> Default for CharSetArray<N> {
+    fn default() -> Self {
+        CharSetArray::new()
+    }
+}
+
+impl<const N: usize> PartialEq for CharSetArray<N> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<const N: usize> Eq for CharSetArray<N> {}
+
+impl<const N: usize> PartialOrd for CharSetArray<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize> Ord for CharSetArray<N> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<const N: usize> hash::Hash for CharSetArray<N> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl<const N: usize> TryFrom<&CharSet> for CharSetArray<N> {
+    type Error = CapacityError;
+
+    fn try_from(set: &CharSet) -> Result<Self, CapacityError> {
+        let mut this = CharSetArray::new();
+        this.splice(0..0, &set.ranges)?;
+        Ok(this)
+    }
+}
+
+impl<const N: usize> CharSetArray<N> {
+    /// Create a new, empty set.
+    pub const fn new() -> Self {
+        CharSetArray {
+            ranges: [CharRange::empty(); N],
+            len: 0,
+        }
+    }
+
+    /// The most compact ranges this set can hold.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Remove all codepoints from this set.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Insert a single codepoint into this set.
+    ///
+    /// Returns whether the codepoint was newly inserted.
+    pub fn insert(&mut self, c: char) -> Result<bool, CapacityError> {
+        self.insert_range(CharRange::singleton(c)).map(|n| n != 0)
+    }
+
+    /// Insert a range of codepoints into this set.
+    ///
+    /// Returns how many codepoints were newly inserted.
+    pub fn insert_range(&mut self, r: CharRange) -> Result<usize, CapacityError> {
+        if r.is_empty() {
+            return Ok(0);
+        }
+        let inserted = r.len() - self.count_in_range(r);
+
+        // low_idx: inclusive index of lowest replaced range
+        // low_char: lowest char of the new inserted range
+        let (mut low_idx, mut low_char) = match self.search(r.low) {
+            Ok(idx) => (idx, self.ranges[idx].low),
+            Err(idx) => (idx, r.low),
+        };
+        // extend left if collapse needed
+        if low_idx > 0 && low_char as u32 - self.ranges[low_idx - 1].high as u32 <= 1 {
+            low_idx -= 1;
+            low_char = self.ranges[low_idx].low;
+        }
+
+        // high_idx: exclusive index of highest replaced range
+        // high_char: highest char of the new inserted range
+        let (mut high_idx, mut high_char) = match self.search(r.high) {
+            Ok(idx) => (idx + 1, self.ranges[idx].high),
+            Err(idx) => (idx, r.high),
+        };
+        // extend right if collapse needed
+        if high_idx < self.len && self.ranges[high_idx].low as u32 - high_char as u32 <= 1 {
+            high_char = self.ranges[high_idx].high;
+            high_idx += 1;
+        }
+
+        self.splice(low_idx..high_idx, &[CharRange::from(low_char..=high_char)])?;
+        Ok(inserted)
+    }
+
+    /// Remove a single codepoint from this set.
+    ///
+    /// Returns whether the codepoint was present in the set.
+    /// This can fail when the codepoint splits a range in two.
+    pub fn remove(&mut self, c: char) -> Result<bool, CapacityError> {
+        self.remove_range(CharRange::singleton(c)).map(|n| n != 0)
+    }
+
+    /// Remove a range of codepoints from this set.
+    ///
+    /// Returns how many codepoints were removed.
+    /// This can fail when the removed range splits a range in two.
+    pub fn remove_range(&mut self, r: CharRange) -> Result<usize, CapacityError> {
+        if r.is_empty() {
+            return Ok(0);
+        }
+
+        // inclusive index of lowest edited range
+        let low = self.search(r.low).unwrap_or_else(|it| it);
+        // exclusive index of highest edited range
+        let high = match self.search(r.high) {
+            Ok(idx) => idx + 1,
+            Err(idx) => idx,
+        };
+        if low == high {
+            return Ok(0);
+        }
+
+        let removed = self.count_in_range(r);
+        // the parts of the edited ranges that stick out of the removed range
+        let below = CharRange::from((
+            Bound::Included(self.ranges[low].low),
+            Bound::Excluded(r.low),
+        ));
+        let above = CharRange::from((
+            Bound::Excluded(r.high),
+            Bound::Included(self.ranges[high - 1].high),
+        ));
+        let (kept, len) = match (below.is_empty(), above.is_empty()) {
+            (true, true) => ([below, above], 0),
+            (false, true) => ([below, above], 1),
+            (true, false) => ([above, below], 1),
+            (false, false) => ([below, above], 2),
+        };
+        self.splice(low..high, &kept[..len])?;
+        Ok(removed)
+    }
+
+    /// Add the codepoints of `other` to this set.
+    pub fn union_with(&mut self, other: &CharSet) -> Result<(), CapacityError> {
+        self.combine_with(other, ops::union)
+    }
+
+    /// Keep only the codepoints also in `other`.
+    pub fn intersection_with(&mut self, other: &CharSet) -> Result<(), CapacityError> {
+        self.combine_with(other, ops::intersection)
+    }
+
+    /// Remove the codepoints of `other` from this set.
+    pub fn difference_with(&mut self, other: &CharSet) -> Result<(), CapacityError> {
+        self.combine_with(other, ops::difference)
+    }
+
+    /// Keep only the codepoints in exactly one of this set and `other`.
+    pub fn symmetric_difference_with(&mut self, other: &CharSet) -> Result<(), CapacityError> {
+        self.combine_with(other, ops::symmetric_difference)
+    }
+
+    /// Replace the ranges at `idx` with `with`, as `Vec::splice`.
+    fn splice(&mut self, idx: Range<usize>, with: &[CharRange]) -> Result<(), CapacityError> {
+        let len = self.len - idx.len() + with.len();
+        if len > N {
+            return Err(CapacityError);
+        }
+        self.ranges
+            .copy_within(idx.end..self.len, idx.start + with.len());
+        self.ranges[idx.start..idx.start + with.len()].copy_from_slice(with);
+        self.len = len;
+        Ok(())
+    }
+
+    /// Replace this set with the result of `op` applied to it and `other`.
+    ///
+    /// The result is built in a scratch array, so this set is left untouched
+    /// if the result does not fit.
+    fn combine_with(&mut self, other: &CharSet, op: ops::Op) -> Result<(), CapacityError> {
+        let mut ranges = [CharRange::empty(); N];
+        let mut len = 0;
+        let mut sweep = ops::Sweep::new(op);
+        while let Some(r) = sweep.next(&self.ranges[..self.len], &other.ranges, op) {
+            *ranges.get_mut(len).ok_or(CapacityError)? = r;
+            len += 1;
+        }
+        *self = CharSetArray { ranges, len };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set<const N: usize>(ranges: &[CharRange]) -> CharSetArray<N> {
+        CharSetArray::try_from(CharSet::from_raw(ranges)).unwrap()
+    }
+
+    #[test]
+    fn insert_remove() {
+        let r = CharRange::closed;
+        let mut array = CharSetArray::<3>::new();
+        assert_eq!(array.insert_range(r('a', 'c')), Ok(3));
+        assert_eq!(array.insert_range(r('x', 'z')), Ok(3));
+        assert_eq!(array.insert('m'), Ok(true));
+        assert_eq!(array.insert('m'), Ok(false));
+        assert_eq!(array.insert('q'), Err(CapacityError));
+        assert_eq!(array.insert('d'), Ok(true));
+        assert_eq!(array.insert_range(r('e', 'l')), Ok(8));
+        assert_eq!(array, set::<3>(&[r('a', 'm'), r('x', 'z')]));
+
+        assert_eq!(array.remove_range(r('b', 'c')), Ok(2));
+        assert_eq!(array.remove('f'), Err(CapacityError));
+        assert_eq!(array, set::<3>(&[r('a', 'a'), r('d', 'm'), r('x', 'z')]));
+        assert_eq!(array.remove_range(r('a', 'x')), Ok(12));
+        assert_eq!(array.remove('f'), Ok(false));
+        assert_eq!(array, set::<3>(&[r('y', 'z')]));
+        assert_eq!(array.remove_range(r('\0', char::MAX)), Ok(2));
+        assert!(array.is_empty());
+    }
+
+    #[test]
+    fn combine() {
+        let r = CharRange::closed;
+        let ranges = [r('b', 'c'), r('f', 'g')];
+        let other = CharSet::from_raw(&ranges);
+
+        let mut array = set::<2>(&[r('a', 'd')]);
+        array.union_with(other).unwrap();
+        assert_eq!(array, set::<2>(&[r('a', 'd'), r('f', 'g')]));
+        assert_eq!(array.symmetric_difference_with(other), Ok(()));
+        assert_eq!(array, set::<2>(&[r('a', 'a'), r('d', 'd')]));
+        assert_eq!(
+            array.union_with(CharSet::from_raw(&[r('x', 'x')])),
+            Err(CapacityError),
+        );
+        assert_eq!(array, set::<2>(&[r('a', 'a'), r('d', 'd')]));
+        assert_eq!(
+            array.difference_with(CharSet::from_raw(&[r('a', 'b')])),
+            Ok(())
+        );
+        assert_eq!(array.intersection_with(other), Ok(()));
+        assert!(array.is_empty());
+
+        assert_eq!(CharSetArray::<1>::try_from(other), Err(CapacityError),);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::set::CharSet;
+
+/// A set with a bitmap of its ASCII members, for fast lookup of ASCII codepoints.
+///
+/// Testing an ASCII codepoint is a single bit test rather than a binary search
+/// of the set's ranges, which helps when the input is mostly ASCII.
+/// Other codepoints fall back to searching the set.
+///
+/// Constructed via `CharSet::with_ascii_cache`. See `CharSet` for more information.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::{set::{AsciiCached, CharSet}, CharRange};
+/// static IDENT: AsciiCached<'_> = CharSet::from_raw(&[
+///     CharRange::closed('0', '9'),
+///     CharRange::closed('A', 'Z'),
+///     CharRange::closed('_', '_'),
+///     CharRange::closed('a', 'z'),
+///     CharRange::closed('\u{C0}', '\u{24F}'),
+/// ])
+/// .with_ascii_cache();
+///
+/// assert!(IDENT.contains('_'));
+/// assert!(IDENT.contains('\u{E9}'));
+/// assert!(!IDENT.contains('-'));
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct AsciiCached<'a> {
+    set: &'a CharSet,
+    ascii: u128,
+}
+
+impl CharSet {
+    /// Pair this set with a bitmap of its ASCII members.
+    pub const fn with_ascii_cache(&self) -> AsciiCached<'_> {
+        let mut ascii = 0u128;
+        let mut i = 0;
+        while i < self.ranges.len() {
+            let r = self.ranges[i];
+            if r.low as u32 >= 0x80 {
+                break;
+            }
+            let high = if r.high as u32 >= 0x80 {
+                0x7F
+            } else {
+                r.high as u32
+            };
+            let mut c = r.low as u32;
+            while c <= high {
+                ascii |= 1 << c;
+                c += 1;
+            }
+            i += 1;
+        }
+        AsciiCached { set: self, ascii }
+    }
+}
+
+impl<'a> AsciiCached<'a> {
+    /// The underlying set.
+    pub fn set(&self) -> &'a CharSet {
+        self.set
+    }
+
+    /// Does this set include this codepoint?
+    #[inline]
+    pub fn contains(&self, c: char) -> bool {
+        if c.is_ascii() {
+            (self.ascii >> c as u32) & 1 == 1
+        } else {
+            self.set.contains(c)
+        }
+    }
+
+    /// Does this set include every codepoint of this string?
+    pub fn contains_all(&self, s: &str) -> bool {
+        s.chars().all(|c| self.contains(c))
+    }
+
+    /// Does this set include any codepoint of this string?
+    pub fn contains_any(&self, s: &str) -> bool {
+        s.chars().any(|c| self.contains(c))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::CharRange};
+
+    #[test]
+    fn agrees_with_set() {
+        let r = CharRange::closed;
+        let sets: &[&[CharRange]] = &[
+            &[],
+            &[r('\0', '\0')],
+            &[r('\0', '\u{7F}')],
+            &[r('\u{7F}', '\u{80}')],
+            &[r('0', '9'), r('a', 'z'), r('\u{100}', '\u{1FF}')],
+            &[r('\u{80}', char::MAX)],
+        ];
+
+        for &ranges in sets {
+            let set = CharSet::from_raw(ranges);
+            let cached = set.with_ascii_cache();
+            for c in CharRange::closed('\0', '\u{2FF}') {
+                assert_eq!(cached.contains(c), set.contains(c), "{:?} in {:?}", c, set);
+            }
+        }
+    }
+}
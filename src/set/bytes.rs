@@ -0,0 +1,210 @@
+use {
+    crate::{
+        set::{CharSet, RangesError},
+        CharRange,
+    },
+    core::{char, convert::TryInto, fmt, mem, slice},
+};
+
+/// Identifies serialized `CharSet` bytes.
+const MAGIC: [u8; 4] = *b"mlgs";
+
+/// Distinguishes native from swapped endianness in serialized `CharSet` bytes.
+const ENDIANNESS_TAG: u32 = 0xFEFF;
+
+/// An error from loading a `CharSet` from bytes.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum BytesError {
+    /// The bytes do not start with `CharSet::BYTES_HEADER`.
+    BadHeader,
+    /// The bytes were written by a platform with the opposite endianness.
+    WrongEndianness,
+    /// The ranges after the header are not aligned for `CharRange`.
+    Misaligned,
+    /// The ranges after the header are not a whole number of ranges long.
+    BadLength,
+    /// A bound of the range at this index is not a valid codepoint.
+    InvalidCodepoint(usize),
+    /// The ranges are not a valid set.
+    InvalidRanges(RangesError),
+}
+
+impl fmt::Display for BytesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BytesError::BadHeader => f.write_str("missing char set header"),
+            BytesError::WrongEndianness => f.write_str("char set has the wrong endianness"),
+            BytesError::Misaligned => f.write_str("char set ranges are misaligned"),
+            BytesError::BadLength => f.write_str("char set ranges have a partial range"),
+            BytesError::InvalidCodepoint(i) => {
+                write!(f, "range {} has a bound that is not a codepoint", i)
+            }
+            BytesError::InvalidRanges(err) => err.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BytesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BytesError::InvalidRanges(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// # Binary serialization
+///
+/// A set is serialized as `CharSet::BYTES_HEADER` followed directly by
+/// `CharSet::as_bytes`: the ranges' low and high codepoints as native endian `u32`s.
+/// Loading it back with `CharSet::from_bytes` borrows the ranges in place,
+/// so the bytes must be aligned to 4 bytes, as `include_bytes!` does not guarantee.
+///
+/// ```
+/// # use mileage::{set::CharSet, CharRange};
+/// #[repr(C, align(4))]
+/// struct Aligned<T: ?Sized>(T);
+///
+/// let ranges = [CharRange::closed('0', '9'), CharRange::closed('a', 'z')];
+/// let set = CharSet::from_raw(&ranges);
+///
+/// let mut buf = Aligned([0u8; 24]);
+/// buf.0[..8].copy_from_slice(&CharSet::BYTES_HEADER);
+/// buf.0[8..].copy_from_slice(set.as_bytes());
+///
+/// assert_eq!(CharSet::from_bytes(&buf.0), Ok(set));
+/// ```
+impl CharSet {
+    /// The header which starts a serialized set.
+    pub const BYTES_HEADER: [u8; 8] = {
+        let tag = ENDIANNESS_TAG.to_ne_bytes();
+        [
+            MAGIC[0], MAGIC[1], MAGIC[2], MAGIC[3], tag[0], tag[1], tag[2], tag[3],
+        ]
+    };
+
+    /// View the ranges of this set as bytes, without the header.
+    #[allow(unsafe_code)]
+    pub fn as_bytes(&self) -> &[u8] {
+        let len = mem::size_of_val(&self.ranges);
+        unsafe { slice::from_raw_parts(self.ranges.as_ptr() as *const u8, len) }
+    }
+
+    /// Serialize this set, including the header.
+    #[cfg(feature = "alloc")]
+    pub fn to_bytes(&self) -> alloc::vec::Vec<u8> {
+        let mut bytes = alloc::vec::Vec::with_capacity(8 + self.as_bytes().len());
+        bytes.extend_from_slice(&CharSet::BYTES_HEADER);
+        bytes.extend_from_slice(self.as_bytes());
+        bytes
+    }
+
+    /// Borrow a serialized set, checking that it is valid.
+    pub fn from_bytes(bytes: &[u8]) -> Result<&CharSet, BytesError> {
+        if bytes.len() < 8 || bytes[..4] != MAGIC {
+            return Err(BytesError::BadHeader);
+        }
+        let (header, body) = bytes.split_at(8);
+        if header != CharSet::BYTES_HEADER {
+            let swapped = ENDIANNESS_TAG.swap_bytes().to_ne_bytes();
+            return Err(if header[4..] == swapped {
+                BytesError::WrongEndianness
+            } else {
+                BytesError::BadHeader
+            });
+        }
+        if body.as_ptr().align_offset(mem::align_of::<CharRange>()) != 0 {
+            return Err(BytesError::Misaligned);
+        }
+        if !body
+            .chunks_exact(mem::size_of::<CharRange>())
+            .remainder()
+            .is_empty()
+        {
+            return Err(BytesError::BadLength);
+        }
+        for (i, bound) in body.chunks_exact(4).enumerate() {
+            let bound = u32::from_ne_bytes(bound.try_into().unwrap());
+            if char::from_u32(bound).is_none() {
+                return Err(BytesError::InvalidCodepoint(i / 2));
+            }
+        }
+        let len = body.len() / mem::size_of::<CharRange>();
+        #[allow(unsafe_code)]
+        let ranges = unsafe {
+            // SAFETY: `CharRange` is `repr(C)` of two `char`s, and the bytes
+            // are aligned, sized, and valid codepoints, as checked above.
+            slice::from_raw_parts(body.as_ptr() as *const CharRange, len)
+        };
+        CharSet::try_from_raw(ranges).map_err(BytesError::InvalidRanges)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C, align(4))]
+    struct Aligned<T: ?Sized>(T);
+
+    #[test]
+    fn round_trip() {
+        let r = CharRange::closed;
+        let sets: &[&[CharRange]] = &[
+            &[],
+            &[r('a', 'z')],
+            &[r('\0', '\u{D7FF}'), r('\u{E000}', char::MAX)],
+        ];
+
+        for &ranges in sets {
+            let set = CharSet::from_raw(ranges);
+            let mut buf = Aligned([0u8; 8 + 16]);
+            let bytes = &mut buf.0[..8 + set.as_bytes().len()];
+            bytes[..8].copy_from_slice(&CharSet::BYTES_HEADER);
+            bytes[8..].copy_from_slice(set.as_bytes());
+            assert_eq!(CharSet::from_bytes(bytes), Ok(set));
+            #[cfg(feature = "alloc")]
+            assert_eq!(&*set.to_bytes(), &*bytes);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let ranges = [CharRange::closed('b', 'c'), CharRange::closed('a', 'a')];
+        let unsorted = CharSet::from_raw(&ranges);
+        let mut buf = Aligned([0u8; 8 + 16 + 4]);
+        buf.0[..8].copy_from_slice(&CharSet::BYTES_HEADER);
+        buf.0[8..24].copy_from_slice(unsorted.as_bytes());
+        let bytes = &mut buf.0;
+
+        assert_eq!(
+            CharSet::from_bytes(&bytes[..24]),
+            Err(BytesError::InvalidRanges(RangesError::Unsorted(1))),
+        );
+        assert_eq!(
+            CharSet::from_bytes(&bytes[..20]),
+            Err(BytesError::BadLength)
+        );
+        assert_eq!(CharSet::from_bytes(&bytes[..4]), Err(BytesError::BadHeader));
+
+        bytes[8..12].copy_from_slice(&0xD800u32.to_ne_bytes());
+        assert_eq!(
+            CharSet::from_bytes(&bytes[..24]),
+            Err(BytesError::InvalidCodepoint(0)),
+        );
+
+        bytes[4..8].copy_from_slice(&ENDIANNESS_TAG.swap_bytes().to_ne_bytes());
+        assert_eq!(
+            CharSet::from_bytes(&bytes[..24]),
+            Err(BytesError::WrongEndianness),
+        );
+
+        bytes.copy_within(..24, 1);
+        bytes[1..9].copy_from_slice(&CharSet::BYTES_HEADER);
+        assert_eq!(
+            CharSet::from_bytes(&bytes[1..]),
+            Err(BytesError::Misaligned)
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use {self::table::CASE_FOLDING_SIMPLE, crate::set::CharSetBuf, alloc::vec::Vec};
+
+#[rustfmt::skip]
+#[allow(clippy::redundant_static_lifetimes)]
+mod table;
+
+impl CharSetBuf {
+    /// Add every simple case equivalent of every member of the set.
+    ///
+    /// This uses the simple (single codepoint) case folding of the Unicode
+    /// Character Database, so that the set matches case insensitively as in a
+    /// regex `(?i)` character class. The Unicode version is 16.0.0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::set::CharSetBuf;
+    /// let mut set = CharSetBuf::from('k'..='k');
+    /// set.close_case_insensitive();
+    /// assert!(set.contains('K'));
+    /// assert!(set.contains('\u{212A}')); // KELVIN SIGN
+    /// assert_eq!(set.len(), 3);
+    /// ```
+    pub fn close_case_insensitive(&mut self) {
+        let mut equivalents = Vec::new();
+        for r in self.ranges() {
+            let start = CASE_FOLDING_SIMPLE.partition_point(|&(c, _)| c < r.low);
+            let entries = CASE_FOLDING_SIMPLE[start..]
+                .iter()
+                .take_while(|&&(c, _)| c <= r.high);
+            for &(_, chars) in entries {
+                equivalents.extend_from_slice(chars);
+            }
+        }
+        self.extend(equivalents);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::CharRange};
+
+    #[test]
+    fn table_is_sorted() {
+        assert!(CASE_FOLDING_SIMPLE.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn close_case_insensitive() {
+        let r = CharRange::closed;
+        let test_data: &[(&[CharRange], &[CharRange])] = &[
+            (&[],                       &[]),
+            (&[r('0', '9')],            &[r('0', '9')]),
+            (&[r('a', 'c')],            &[r('A', 'C'), r('a', 'c')]),
+            (&[r('A', 'C')],            &[r('A', 'C'), r('a', 'c')]),
+            (&[r('s', 's')],            &[r('S', 'S'), r('s', 's'), r('\u{17F}', '\u{17F}')]),
+            (&[r('\u{DF}', '\u{DF}')],  &[r('\u{DF}', '\u{DF}'), r('\u{1E9E}', '\u{1E9E}')]),
+            (&[r('a', 'z')],            &[r('A', 'Z'), r('a', 'z'), r('\u{17F}', '\u{17F}'), r('\u{212A}', '\u{212A}')]),
+        ];
+
+        for &(set, expected) in test_data {
+            let mut set: CharSetBuf = set.iter().copied().collect();
+            set.close_case_insensitive();
+            assert_eq!(set.ranges().collect::<Vec<_>>(), expected);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use {
+    crate::{
+        set::{CharSet, CharSetBuf},
+        CharRange,
+    },
+    ranges::{GenericRange, Ranges},
+};
+
+/// Copies the ranges of the set, whatever their bounds.
+///
+/// `ranges` already steps over the surrogates for `char`, so ranges
+/// either side of them are one range there and two here.
+///
+/// # Examples
+///
+/// ```
+/// # use {mileage::set::CharSetBuf, ranges::{GenericRange, Ranges}};
+/// let mut ranges = Ranges::new();
+/// ranges.insert(GenericRange::new_closed('a', 'f'));
+/// ranges.insert(GenericRange::new_closed_open('0', ':'));
+/// let set = CharSetBuf::from(&ranges);
+/// assert_eq!(set, CharSetBuf::from(&['0'..='9', 'a'..='f']));
+/// assert_eq!(Ranges::from(set), ranges);
+/// ```
+impl From<&Ranges<char>> for CharSetBuf {
+    fn from(ranges: &Ranges<char>) -> Self {
+        ranges
+            .as_slice()
+            .iter()
+            .cloned()
+            .map(CharRange::from)
+            .collect()
+    }
+}
+
+impl From<Ranges<char>> for CharSetBuf {
+    fn from(ranges: Ranges<char>) -> Self {
+        CharSetBuf::from(&ranges)
+    }
+}
+
+impl From<&CharSet> for Ranges<char> {
+    fn from(set: &CharSet) -> Self {
+        set.ranges()
+            .map(|r| GenericRange::new_closed(r.low, r.high))
+            .collect()
+    }
+}
+
+impl From<&CharSetBuf> for Ranges<char> {
+    fn from(set: &CharSetBuf) -> Self {
+        Ranges::from(&**set)
+    }
+}
+
+impl From<CharSetBuf> for Ranges<char> {
+    fn from(set: CharSetBuf) -> Self {
+        Ranges::from(&*set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let set = CharSetBuf::from(&[
+            '\0'..='\u{D7FF}',
+            '\u{E000}'..='\u{E00F}',
+            '\u{10FFFE}'..=char::MAX,
+        ]);
+        let ranges = Ranges::from(&set);
+        assert_eq!(
+            ranges.as_slice(),
+            [
+                GenericRange::new_closed('\0', '\u{E00F}'),
+                GenericRange::new_closed('\u{10FFFE}', char::MAX),
+            ],
+        );
+        assert!(CharSetBuf::from(&ranges) == *set);
+
+        let mut ranges = ranges;
+        ranges.remove(GenericRange::new_open('`', '\u{E001}'));
+        let expected = CharSetBuf::from(&[
+            '\0'..='`',
+            '\u{E001}'..='\u{E00F}',
+            '\u{10FFFE}'..=char::MAX,
+        ]);
+        assert_eq!(CharSetBuf::from(&ranges), expected);
+        assert_eq!(CharSetBuf::from(ranges.clone()), expected);
+        assert_eq!(Ranges::from(expected), ranges);
+        assert_eq!(CharSetBuf::from(Ranges::full()), CharRange::from(..));
+    }
+}
@@ -0,0 +1,128 @@
+use {
+    crate::{
+        set::CharSetBuf,
+        trie::{CharTrieBuf, GenerateError},
+        CharRange, AFTER_SURROGATE, BEFORE_SURROGATE,
+    },
+    core::{char, convert::TryFrom, ops::RangeInclusive},
+    icu_collections::codepointinvlist::CodePointInversionList,
+    icu_properties::CodePointSetDataBorrowed,
+};
+
+/// The codepoints of a range of `u32`s, which may include surrogates.
+///
+/// Surrogates at either end are clipped off, and a range of only surrogates is empty.
+fn char_range(r: RangeInclusive<u32>) -> Option<CharRange> {
+    let low = match *r.start() {
+        0xD800..=0xDFFF => AFTER_SURROGATE,
+        low => char::from_u32(low)?,
+    };
+    let high = match *r.end() {
+        0xD800..=0xDFFF => BEFORE_SURROGATE,
+        high => char::from_u32(high).unwrap_or(char::MAX),
+    };
+    Some(CharRange::closed(low, high)).filter(|r| !r.is_empty())
+}
+
+fn from_ranges(ranges: impl IntoIterator<Item = RangeInclusive<u32>>) -> CharSetBuf {
+    let mut set = CharSetBuf::new();
+    set.extend(ranges.into_iter().filter_map(char_range));
+    set
+}
+
+/// Copies the ranges of the list, without the surrogates it may hold.
+///
+/// # Examples
+///
+/// ```
+/// # use {icu_collections::codepointinvlist::CodePointInversionList, mileage::set::CharSetBuf};
+/// let list: CodePointInversionList<'_> = [0x30..=0x39, 0xD000..=0xDFFF].iter().cloned().collect();
+/// let set = CharSetBuf::from(&list);
+/// assert_eq!(set, CharSetBuf::from(&['0'..='9', '\u{D000}'..='\u{D7FF}']));
+/// ```
+impl From<&CodePointInversionList<'_>> for CharSetBuf {
+    fn from(list: &CodePointInversionList<'_>) -> Self {
+        from_ranges(list.iter_ranges())
+    }
+}
+
+/// Copies the ranges of the property, without the surrogates it may hold.
+impl From<CodePointSetDataBorrowed<'_>> for CharSetBuf {
+    fn from(data: CodePointSetDataBorrowed<'_>) -> Self {
+        from_ranges(data.iter_ranges())
+    }
+}
+
+/// Builds a trie from the ranges of the list, as `CharTrieBuf::from_set`.
+impl TryFrom<&CodePointInversionList<'_>> for CharTrieBuf {
+    type Error = GenerateError;
+
+    fn try_from(list: &CodePointInversionList<'_>) -> Result<Self, GenerateError> {
+        CharTrieBuf::from_set(&CharSetBuf::from(list))
+    }
+}
+
+/// Builds a trie from the ranges of the property, as `CharTrieBuf::from_set`.
+///
+/// # Examples
+///
+/// ```
+/// # use {icu_properties::CodePointSetData, mileage::trie::CharTrieBuf, std::convert::TryFrom};
+/// let list = [0x41..=0x5A, 0x61..=0x7A].iter().cloned().collect();
+/// let data = CodePointSetData::from_code_point_inversion_list(list);
+/// let trie = CharTrieBuf::try_from(data.as_borrowed()).unwrap();
+/// assert!(trie.contains('q'));
+/// assert!(!trie.contains('7'));
+/// ```
+impl TryFrom<CodePointSetDataBorrowed<'_>> for CharTrieBuf {
+    type Error = GenerateError;
+
+    fn try_from(data: CodePointSetDataBorrowed<'_>) -> Result<Self, GenerateError> {
+        CharTrieBuf::from_set(&CharSetBuf::from(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, alloc::vec::Vec, icu_properties::CodePointSetData};
+
+    #[test]
+    #[rustfmt::skip]
+    fn char_range() {
+        assert_eq!(super::char_range(0x61..=0x7A), Some(CharRange::closed('a', 'z')));
+        assert_eq!(super::char_range(0xD000..=0xE000), Some(CharRange::closed('\u{D000}', '\u{E000}')));
+        assert_eq!(super::char_range(0xD800..=0xE000), Some(CharRange::closed('\u{E000}', '\u{E000}')));
+        assert_eq!(super::char_range(0xD000..=0xDFFF), Some(CharRange::closed('\u{D000}', '\u{D7FF}')));
+        assert_eq!(super::char_range(0xD800..=0xDFFF), None);
+        assert_eq!(super::char_range(0x10FFFF..=0x10FFFF), Some(CharRange::singleton(char::MAX)));
+    }
+
+    #[test]
+    fn property() {
+        // only the codepoints either side of the surrogates are kept
+        let list: CodePointInversionList<'static> = [
+            0x30..=0x39,
+            0xD700..=0xD9FF,
+            0xDC00..=0xE0FF,
+            0x1F600..=0x1F64F,
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        let ranges = [
+            CharRange::closed('0', '9'),
+            CharRange::closed('\u{D700}', '\u{D7FF}'),
+            CharRange::closed('\u{E000}', '\u{E0FF}'),
+            CharRange::closed('\u{1F600}', '\u{1F64F}'),
+        ];
+        let set = CharSetBuf::from(&list);
+        assert_eq!(set.ranges().collect::<Vec<_>>(), ranges);
+
+        let data = CodePointSetData::from_code_point_inversion_list(list.clone());
+        assert_eq!(CharSetBuf::from(data.as_borrowed()), set);
+        let trie = CharTrieBuf::try_from(data.as_borrowed()).unwrap();
+        assert_eq!(trie.as_trie().ranges().collect::<Vec<_>>(), ranges);
+        let from_list = CharTrieBuf::try_from(&list).unwrap();
+        assert_eq!(from_list.to_bytes(), trie.to_bytes());
+    }
+}
@@ -1,6 +1,6 @@
 use {
-    crate::{set::CharSet, CharRange},
-    core::{iter, slice::Iter as SliceIter},
+    crate::{range, set::CharSet, CharRange},
+    core::{cmp, iter, slice::Iter as SliceIter},
 };
 
 /// An iterator over ranges of `char`.
@@ -30,12 +30,205 @@ impl CharSet {
         }
     }
 
+    /// Iterate the compact ranges of this set, from highest to lowest.
+    pub fn ranges_rev(&self) -> iter::Rev<RangeIter<'_>> {
+        self.ranges().rev()
+    }
+
     /// Iterate the codepoints of this set.
-    pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
-        self.ranges().flat_map(IntoIterator::into_iter)
+    pub fn chars(&self) -> Chars<'_> {
+        Chars::new(self.ranges())
+    }
+
+    /// Iterate the codepoints of this set, from highest to lowest.
+    pub fn chars_rev(&self) -> iter::Rev<Chars<'_>> {
+        self.chars().rev()
+    }
+}
+
+/// An iterator over the codepoints of a set.
+///
+/// Constructed via `CharSet::chars`. See `CharSet` for more information.
+///
+/// Constructed via `CharMapRef::chars`. See `CharMapRef` for more information.
+#[derive(Clone, Debug)]
+pub struct Chars<'a> {
+    ranges: RangeIter<'a>,
+    front: range::Iter,
+    back: range::Iter,
+    len: usize,
+}
+
+impl<'a> Chars<'a> {
+    pub(crate) fn new(ranges: RangeIter<'a>) -> Self {
+        Chars {
+            len: ranges.clone().map(CharRange::len).sum(),
+            ranges,
+            front: CharRange::empty().iter(),
+            back: CharRange::empty().iter(),
+        }
+    }
+
+    /// Iterate the codepoints of `head`, then `body`, then `tail`,
+    /// which together must contain exactly `len` codepoints.
+    #[cfg(feature = "par-iter")]
+    pub(crate) fn from_parts(
+        head: CharRange,
+        body: &'a [CharRange],
+        tail: CharRange,
+        len: usize,
+    ) -> Self {
+        Chars {
+            ranges: RangeIter { raw: body.iter() },
+            front: head.iter(),
+            back: tail.iter(),
+            len,
+        }
+    }
+}
+
+impl Iterator for Chars<'_> {
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(c) = self.front.next() {
+                self.len -= 1;
+                return Some(c);
+            }
+            match self.ranges.next() {
+                Some(r) => self.front = r.iter(),
+                None => {
+                    let c = self.back.next()?;
+                    self.len -= 1;
+                    return Some(c);
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+
+    #[inline]
+    fn count(self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
+    }
+}
+
+impl DoubleEndedIterator for Chars<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(c) = self.back.next_back() {
+                self.len -= 1;
+                return Some(c);
+            }
+            match self.ranges.next_back() {
+                Some(r) => self.back = r.iter(),
+                None => {
+                    let c = self.front.next_back()?;
+                    self.len -= 1;
+                    return Some(c);
+                }
+            }
+        }
+    }
+}
+
+impl ExactSizeIterator for Chars<'_> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl iter::FusedIterator for Chars<'_> {}
+
+/// An iterator over ranges of `char` clamped to a window.
+///
+/// Constructed via `CharSet::intersect_range`. See `CharSet` for more information.
+#[derive(Clone, Debug)]
+pub struct ClampedRanges<'a> {
+    raw: SliceIter<'a, CharRange>,
+    window: CharRange,
+}
+
+impl CharSet {
+    /// Iterate the compact ranges of this set within a window of codepoints,
+    /// clamping the ranges which stick out of the window.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let alpha = [CharRange::closed('A', 'Z'), CharRange::closed('a', 'z')];
+    /// let within = CharSet::from_raw(&alpha).intersect_range(CharRange::from('X'..='c'));
+    /// assert!(within.eq(vec![CharRange::from('X'..='Z'), CharRange::from('a'..='c')]));
+    /// ```
+    pub fn intersect_range(&self, r: CharRange) -> ClampedRanges<'_> {
+        let ranges = if r.is_empty() {
+            &[]
+        } else {
+            self.overlapping(r)
+        };
+        ClampedRanges {
+            raw: ranges.iter(),
+            window: r,
+        }
+    }
+}
+
+impl ClampedRanges<'_> {
+    #[inline]
+    fn clamp(&self, r: &CharRange) -> CharRange {
+        CharRange::closed(
+            cmp::max(r.low, self.window.low),
+            cmp::min(r.high, self.window.high),
+        )
+    }
+}
+
+impl Iterator for ClampedRanges<'_> {
+    type Item = CharRange;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let r = self.raw.next()?;
+        Some(self.clamp(r))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.raw.size_hint()
     }
 }
 
+impl DoubleEndedIterator for ClampedRanges<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let r = self.raw.next_back()?;
+        Some(self.clamp(r))
+    }
+}
+
+impl ExactSizeIterator for ClampedRanges<'_> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.raw.len()
+    }
+}
+
+impl iter::FusedIterator for ClampedRanges<'_> {}
+
 // forward slice iterators
 
 impl ExactSizeIterator for RangeIter<'_> {
@@ -109,3 +302,78 @@ impl DoubleEndedIterator for RangeIter<'_> {
 }
 
 impl iter::FusedIterator for RangeIter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, alloc::vec::Vec};
+
+    #[test]
+    fn intersect_range() {
+        let ranges = [
+            CharRange::closed('b', 'd'),
+            CharRange::closed('f', 'h'),
+            CharRange::closed('j', 'l'),
+        ];
+        let set = CharSet::from_raw(&ranges);
+        let within = |r| set.intersect_range(r).collect::<Vec<_>>();
+
+        assert_eq!(within(CharRange::empty()), []);
+        assert_eq!(within(CharRange::from(..)), ranges);
+        assert_eq!(within(CharRange::from('e'..='e')), []);
+        assert_eq!(
+            within(CharRange::from('g'..='g')),
+            [CharRange::from('g'..='g')]
+        );
+        assert_eq!(
+            within(CharRange::from('c'..='k')),
+            [
+                CharRange::from('c'..='d'),
+                CharRange::from('f'..='h'),
+                CharRange::from('j'..='k'),
+            ],
+        );
+        assert_eq!(
+            set.intersect_range(CharRange::from('c'..='k')).next_back(),
+            Some(CharRange::from('j'..='k')),
+        );
+    }
+
+    #[test]
+    fn reverse_parity() {
+        let ranges = [
+            CharRange::closed('a', 'c'),
+            CharRange::closed('x', 'z'),
+            CharRange::closed('\u{D7FE}', '\u{E001}'),
+        ];
+        let set = CharSet::from_raw(&ranges);
+
+        let mut ranges = set.ranges().collect::<Vec<_>>();
+        ranges.reverse();
+        assert_eq!(set.ranges_rev().collect::<Vec<_>>(), ranges);
+        assert_eq!(set.ranges().rev().collect::<Vec<_>>(), ranges);
+
+        let mut chars = set.chars().collect::<Vec<_>>();
+        chars.reverse();
+        assert_eq!(set.chars_rev().collect::<Vec<_>>(), chars);
+        assert_eq!(set.chars().rev().collect::<Vec<_>>(), chars);
+
+        let mut mixed = set.chars();
+        assert_eq!(mixed.next(), Some('a'));
+        assert_eq!(mixed.next_back(), Some('\u{E001}'));
+        assert_eq!(mixed.next_back(), Some('\u{E000}'));
+        assert_eq!(mixed.next_back(), Some('\u{D7FF}'));
+        assert_eq!(mixed.len(), chars.len() - 4);
+        assert_eq!(mixed.clone().count(), mixed.len());
+        assert_eq!(mixed.clone().rev().count(), mixed.len());
+
+        let abc = [CharRange::closed('a', 'c')];
+        let mut meet = CharSet::from_raw(&abc).chars();
+        assert_eq!(meet.next_back(), Some('c'));
+        assert_eq!(meet.next(), Some('a'));
+        assert_eq!(meet.len(), 1);
+        assert_eq!(meet.next_back(), Some('b'));
+        assert_eq!(meet.next(), None);
+        assert_eq!(meet.next_back(), None);
+        assert_eq!(meet.len(), 0);
+    }
+}
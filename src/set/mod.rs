@@ -1,17 +1,61 @@
+#[cfg(feature = "trie")]
+use crate::trie::CharTrie;
 use {
-    crate::range::CharRange,
-    core::{char, cmp},
+    crate::{range::CharRange, AFTER_SURROGATE, BEFORE_SURROGATE},
+    core::{
+        char, cmp,
+        fmt::{self, Write},
+        mem,
+        ops::Bound,
+    },
 };
 
+#[cfg(all(feature = "rkyv", feature = "owned-set"))]
+mod archive;
+mod array;
+mod ascii;
+mod bytes;
+#[cfg(feature = "case-fold")]
+mod case_fold;
+#[cfg(feature = "interop-ranges")]
+mod generic_ranges;
+#[cfg(feature = "icu")]
+mod icu;
 mod iter;
+mod ops;
 #[cfg(feature = "owned-set")]
 mod owned;
 #[cfg(feature = "par-iter")]
 mod par_iter;
+#[cfg(feature = "rand")]
+mod random;
+#[cfg(feature = "interop-rangemap")]
+mod range_map;
+mod str;
+#[cfg(all(
+    feature = "owned-set",
+    any(feature = "proptest", feature = "arbitrary")
+))]
+pub mod testing;
 
-pub use self::iter::RangeIter;
+#[cfg(all(feature = "rkyv", feature = "owned-set"))]
+pub use self::archive::ArchivedCharSet;
+#[cfg(feature = "inline-set")]
+pub use self::owned::INLINE_RANGES;
 #[cfg(feature = "owned-set")]
-pub use self::owned::CharSetBuf;
+pub use self::owned::{CharSetBuf, Drain, SetOp};
+#[cfg(feature = "rand")]
+pub use self::random::UniformChar;
+#[cfg(feature = "interop-rangemap")]
+pub use self::range_map::CharStep;
+pub use self::{
+    array::{CapacityError, CharSetArray},
+    ascii::AsciiCached,
+    bytes::BytesError,
+    iter::{Chars, ClampedRanges, RangeIter},
+    ops::{Difference, Gaps, Intersection, Union},
+    str::{Fields, Matches, Split, SplitInclusive},
+};
 
 /// A set slice of codepoints represented by the compact ranges of codepoints.
 #[derive(Debug, Eq, PartialEq, PartialOrd, Hash)]
@@ -20,6 +64,55 @@ pub struct CharSet {
     pub(self) ranges: [CharRange],
 }
 
+/// Call `emit` with each maximal range of codepoints for which `f` holds, in order.
+#[cfg(any(feature = "owned-set", feature = "new-set"))]
+pub(crate) fn for_each_run(f: impl Fn(char) -> bool, mut emit: impl FnMut(CharRange)) {
+    let mut run: Option<CharRange> = None;
+    for c in CharRange::from(..) {
+        match (f(c), &mut run) {
+            (true, None) => run = Some(CharRange::singleton(c)),
+            (true, Some(r)) => r.high = c,
+            (false, Some(r)) => {
+                emit(*r);
+                run = None;
+            }
+            (false, None) => {}
+        }
+    }
+    if let Some(r) = run {
+        emit(r);
+    }
+}
+
+/// Generate a new set from a membership function.
+///
+/// This constructs Rust code that is legal in expression position that
+/// evaluates to a `&'static CharSet`. Requires that `CharSet` and `CharRange` are in scope.
+#[cfg(feature = "new-set")]
+pub fn generate(f: impl Fn(char) -> bool) -> proc_macro2::TokenStream {
+    let mut ranges = alloc::vec::Vec::new();
+    for_each_run(f, |r| ranges.push(r));
+    generate_from_ranges(ranges)
+}
+
+/// The code for a static set of sorted ranges, which neither overlap nor touch.
+#[cfg(feature = "new-set")]
+pub(crate) fn generate_from_ranges(
+    ranges: impl IntoIterator<Item = CharRange>,
+) -> proc_macro2::TokenStream {
+    use quote::quote;
+
+    let ranges = ranges.into_iter().map(|r| {
+        let (low, high) = (r.low, r.high);
+        quote!(CharRange::closed(#low, #high))
+    });
+    quote!({
+        const RANGES: &[CharRange] = &[#(#ranges),*];
+        CharSet::from_raw(RANGES)
+    })
+}
+
+#[allow(clippy::derive_ord_xor_partial_ord)]
 impl Ord for CharSet {
     fn cmp(&self, other: &Self) -> cmp::Ordering {
         self.partial_cmp(other)
@@ -27,12 +120,284 @@ impl Ord for CharSet {
     }
 }
 
+/// Compares membership, so a set equals a range with exactly the same codepoints.
+impl PartialEq<CharRange> for CharSet {
+    fn eq(&self, other: &CharRange) -> bool {
+        if other.is_empty() {
+            self.is_empty()
+        } else {
+            ops::same_members(&self.ranges, core::slice::from_ref(other))
+        }
+    }
+}
+
+/// Compares membership, so a set equals a slice of ranges with exactly the same codepoints.
+///
+/// The ranges of the slice must be sorted and must not overlap, but may touch.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::{set::CharSet, CharRange};
+/// let ranges = [CharRange::closed('0', '9'), CharRange::closed('a', 'f')];
+/// let set = CharSet::from_raw(&ranges);
+/// assert!(*set == ranges[..]);
+/// assert!(*set == [CharRange::closed('0', '5'), CharRange::closed('6', '9'), CharRange::closed('a', 'f')][..]);
+/// assert!(*set != [CharRange::closed('0', '9')][..]);
+/// ```
+impl PartialEq<[CharRange]> for CharSet {
+    fn eq(&self, other: &[CharRange]) -> bool {
+        ops::same_members(&self.ranges, other)
+    }
+}
+
+/// Render the set as a character class, such as `[a-z0-9\u{1F600}-\u{1F64F}]`.
+///
+/// Printable ASCII is written as is, except for the class syntax characters
+/// `[]^-\`, which are escaped with a backslash. Everything else is written as
+/// a `\u{...}` escape, so the output is unambiguous and all on one line.
+/// With the `owned-set` feature, the output can be parsed back into a `CharSetBuf`.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::{set::CharSet, CharRange};
+/// let ranges = [CharRange::closed('\n', '\n'), CharRange::closed('-', '-'), CharRange::closed('a', 'z')];
+/// let set = CharSet::from_raw(&ranges);
+/// assert_eq!(set.to_string(), r"[\u{A}\-a-z]");
+/// ```
+impl fmt::Display for CharSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_escaped(f: &mut fmt::Formatter<'_>, c: char) -> fmt::Result {
+            match c {
+                '[' | ']' | '^' | '-' | '\\' => write!(f, "\\{}", c),
+                ' '..='~' => f.write_char(c),
+                _ => write!(f, "\\u{{{:X}}}", c as u32),
+            }
+        }
+
+        f.write_char('[')?;
+        for r in self.ranges() {
+            write_escaped(f, r.low)?;
+            if r.high != r.low {
+                f.write_char('-')?;
+                write_escaped(f, r.high)?;
+            }
+        }
+        f.write_char(']')
+    }
+}
+
+impl CharSet {
+    /// Write the set as a Unicode Character Database range list.
+    ///
+    /// Each range is written on its own line as `0041..005A`, or as `005F` if
+    /// it holds a single codepoint, with at least four uppercase hex digits.
+    /// A range which spans the surrogates is split around them, as the UCD
+    /// counts surrogates as codepoints.
+    /// With the `owned-set` feature, `parse::parse_ucd` reads this format back.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let ranges = [CharRange::closed('A', 'Z'), CharRange::closed('_', '_'), CharRange::closed('\u{D7FF}', '\u{1F600}')];
+    /// let mut ucd = String::new();
+    /// CharSet::from_raw(&ranges).write_ucd(&mut ucd).unwrap();
+    /// assert_eq!(ucd, "0041..005A\n005F\nD7FF\nE000..1F600\n");
+    /// ```
+    pub fn write_ucd(&self, mut w: impl fmt::Write) -> fmt::Result {
+        for r in self.ranges() {
+            let parts = if r.low <= BEFORE_SURROGATE && AFTER_SURROGATE <= r.high {
+                [
+                    CharRange::closed(r.low, BEFORE_SURROGATE),
+                    CharRange::closed(AFTER_SURROGATE, r.high),
+                ]
+            } else {
+                [r, CharRange::empty()]
+            };
+            for r in parts.iter().filter(|r| !r.is_empty()) {
+                if r.low == r.high {
+                    writeln!(w, "{:04X}", r.low as u32)?;
+                } else {
+                    writeln!(w, "{:04X}..{:04X}", r.low as u32, r.high as u32)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "trie")]
+impl CharSet {
+    /// Does this set have exactly the same members as a trie?
+    ///
+    /// This compares the trie one 64-codepoint chunk at a time, so is much
+    /// faster than testing every codepoint.
+    pub fn eq_trie(&self, trie: &CharTrie<'_>) -> bool {
+        self.first_trie_mismatch(trie).is_none()
+    }
+
+    /// The lowest codepoint which is in exactly one of this set and a trie.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, trie::CharTrie, CharRange};
+    /// static ASCII: CharTrie<'static> = CharTrie::from_raw(
+    ///     &[!0, !0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    ///     &[0; 992],
+    ///     (&[0; 256], &[[0; 64]]),
+    ///     &[0],
+    /// );
+    /// let ascii = [CharRange::closed('\0', '\x7F')];
+    /// assert!(CharSet::from_raw(&ascii).eq_trie(&ASCII));
+    /// let latin1 = [CharRange::closed('\0', '\u{FF}')];
+    /// assert_eq!(CharSet::from_raw(&latin1).first_trie_mismatch(&ASCII), Some('\u{80}'));
+    /// ```
+    pub fn first_trie_mismatch(&self, trie: &CharTrie<'_>) -> Option<char> {
+        let bases = (0..=char::MAX as u32).step_by(64);
+        for (base, chunk) in bases.zip(self.chunks()) {
+            // the surrogates are never members of either
+            if (BEFORE_SURROGATE as u32..AFTER_SURROGATE as u32).contains(&base) {
+                continue;
+            }
+            let diff = chunk ^ trie.chunk(base);
+            if diff != 0 {
+                return char::from_u32(base + diff.trailing_zeros());
+            }
+        }
+        None
+    }
+
+    /// The membership bits of each 64-codepoint chunk of this set, in order,
+    /// as stored in the leaves of a `CharTrie`.
+    ///
+    /// The chunks of surrogates are always empty.
+    pub(crate) fn chunks(&self) -> impl Iterator<Item = u64> + '_ {
+        crate::trie::range_chunks(self.ranges.iter().copied())
+    }
+}
+
+/// An error from validating the raw ranges of a set.
+///
+/// Each variant holds the index of the first offending range.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum RangesError {
+    /// The range is empty.
+    Empty(usize),
+    /// The range starts before the previous range.
+    Unsorted(usize),
+    /// The range overlaps or touches the previous range.
+    Overlapping(usize),
+}
+
+impl fmt::Display for RangesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RangesError::Empty(i) => write!(f, "range {} is empty", i),
+            RangesError::Unsorted(i) => write!(f, "range {} is out of order", i),
+            RangesError::Overlapping(i) => write!(f, "range {} overlaps the previous range", i),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RangesError {}
+
 impl CharSet {
     /// Create a `CharSet` from a raw slice of ranges. Intended for use by code generation.
+    ///
+    /// The ranges must be nonempty, sorted, and neither overlap nor touch.
+    /// This is not checked; see `CharSet::try_from_raw` and
+    /// `CharSet::from_raw_checked` for checked versions.
     #[allow(unsafe_code)]
-    pub fn from_raw(slice: &[CharRange]) -> &CharSet {
+    pub const fn from_raw(slice: &[CharRange]) -> &CharSet {
         unsafe { &*(slice as *const [CharRange] as *const CharSet) }
     }
+
+    /// Create a `CharSet` from a raw slice of ranges, checking that it is valid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::{CharSet, RangesError}, CharRange};
+    /// let ok = [CharRange::closed('0', '9'), CharRange::closed('a', 'z')];
+    /// assert!(CharSet::try_from_raw(&ok).is_ok());
+    ///
+    /// let reversed = [CharRange::closed('a', 'z'), CharRange::closed('0', '9')];
+    /// assert_eq!(CharSet::try_from_raw(&reversed), Err(RangesError::Unsorted(1)));
+    /// ```
+    pub const fn try_from_raw(slice: &[CharRange]) -> Result<&CharSet, RangesError> {
+        let mut i = 0;
+        while i < slice.len() {
+            let r = slice[i];
+            if r.low as u32 > r.high as u32 {
+                return Err(RangesError::Empty(i));
+            }
+            if i > 0 {
+                let prev = slice[i - 1];
+                if (r.low as u32) < prev.low as u32 {
+                    return Err(RangesError::Unsorted(i));
+                }
+                if r.low as u32 <= prev.high as u32 + 1 {
+                    return Err(RangesError::Overlapping(i));
+                }
+            }
+            i += 1;
+        }
+        Ok(CharSet::from_raw(slice))
+    }
+
+    /// Panic if this set is not in canonical form.
+    ///
+    /// The ranges must be nonempty, sorted, and neither overlap nor touch,
+    /// as checked by `CharSet::try_from_raw`. This always holds for sets built
+    /// by this crate, but not for those made from raw ranges without checking.
+    /// With the `debug-validate` feature, every edit of a `CharSetBuf`
+    /// checks this, to catch a corrupted set where it is corrupted.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let touching = [CharRange::closed('a', 'c'), CharRange::closed('d', 'f')];
+    /// CharSet::from_raw(&touching).assert_valid();
+    /// ```
+    #[track_caller]
+    pub fn assert_valid(&self) {
+        if let Err(err) = CharSet::try_from_raw(&self.ranges) {
+            panic!("invalid CharSet: {}", err);
+        }
+    }
+
+    /// Create a `CharSet` from a raw slice of ranges, panicking if it is invalid.
+    ///
+    /// When used to initialize a `const` or `static`, the check is done at
+    /// compile time, so a malformed table is a compile error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// const RANGES: &[CharRange] = &[CharRange::closed('0', '9'), CharRange::closed('a', 'z')];
+    /// static DIGITS_AND_LOWER: &CharSet = CharSet::from_raw_checked(RANGES);
+    /// assert!(DIGITS_AND_LOWER.contains('7'));
+    /// ```
+    ///
+    /// ```compile_fail
+    /// # use mileage::{set::CharSet, CharRange};
+    /// const RANGES: &[CharRange] = &[CharRange::closed('a', 'z'), CharRange::closed('0', '9')];
+    /// static UNSORTED: &CharSet = CharSet::from_raw_checked(RANGES);
+    /// ```
+    pub const fn from_raw_checked(slice: &[CharRange]) -> &CharSet {
+        match CharSet::try_from_raw(slice) {
+            Ok(set) => set,
+            Err(RangesError::Empty(_)) => panic!("CharSet range is empty"),
+            Err(RangesError::Unsorted(_)) => panic!("CharSet range is out of order"),
+            Err(RangesError::Overlapping(_)) => panic!("CharSet range overlaps the previous range"),
+        }
+    }
 }
 
 impl CharSet {
@@ -46,16 +411,331 @@ impl CharSet {
         self.search(c).is_ok()
     }
 
+    /// Does this set include every codepoint of this string?
+    ///
+    /// This is trivially true for the empty string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let ranges = [CharRange::closed('0', '9'), CharRange::closed('_', '_'), CharRange::closed('a', 'z')];
+    /// let ident = CharSet::from_raw(&ranges);
+    /// assert!(ident.contains_all("snake_case_2"));
+    /// assert!(!ident.contains_all("kebab-case"));
+    /// ```
+    pub fn contains_all(&self, s: &str) -> bool {
+        // Text tends to stay within a range, so check the last one found first.
+        let mut last: Option<CharRange> = None;
+        s.chars().all(|c| match last {
+            Some(r) if r.contains(c) => true,
+            _ => match self.search(c) {
+                Ok(idx) => {
+                    last = Some(self.ranges[idx]);
+                    true
+                }
+                Err(_) => false,
+            },
+        })
+    }
+
+    /// Does this set include any codepoint of this string?
+    ///
+    /// This is trivially false for the empty string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let ranges = [CharRange::closed('0', '9')];
+    /// let digits = CharSet::from_raw(&ranges);
+    /// assert!(digits.contains_any("route 66"));
+    /// assert!(!digits.contains_any("route sixty-six"));
+    /// ```
+    pub fn contains_any(&self, s: &str) -> bool {
+        s.chars().any(|c| self.contains(c))
+    }
+
     /// How many codepoints are in this set?
     pub fn len(&self) -> usize {
         self.ranges().map(CharRange::len).sum()
     }
 
+    /// How many codepoints of this set are within a range?
+    ///
+    /// The range is found by binary search, so this only visits the ranges of
+    /// this set which overlap it, rather than every codepoint.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let ranges = [CharRange::closed('0', '9'), CharRange::closed('a', 'z')];
+    /// let set = CharSet::from_raw(&ranges);
+    /// assert_eq!(set.count_in_range(CharRange::from('5'..='c')), 8);
+    /// assert_eq!(set.count_in_range(CharRange::from('\0'..='\u{7F}')), 36);
+    /// ```
+    pub fn count_in_range(&self, r: CharRange) -> usize {
+        self.intersect_range(r).map(CharRange::len).sum()
+    }
+
     /// Is this set empty?
     pub fn is_empty(&self) -> bool {
         self.ranges.is_empty()
     }
 
+    /// How many compact ranges make up this set?
+    pub fn range_count(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// What fraction of all codepoints are in this set?
+    ///
+    /// This is between `0.0` for the empty set and `1.0` for the set of every `char`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let ranges = [CharRange::from(..)];
+    /// assert_eq!(CharSet::from_raw(&ranges).coverage(), 1.0);
+    /// assert_eq!(CharSet::empty().coverage(), 0.0);
+    /// ```
+    pub fn coverage(&self) -> f64 {
+        self.len() as f64 / CharRange::from(..).len() as f64
+    }
+
+    /// The number of bytes used by the ranges of this set.
+    ///
+    /// For a `CharSetBuf`, see `CharSetBuf::memory_footprint` instead,
+    /// which includes the spare capacity and the buffer itself.
+    pub fn memory_footprint(&self) -> usize {
+        mem::size_of_val(&self.ranges)
+    }
+
+    /// Is this set a single nonempty range of codepoints?
+    ///
+    /// This is exactly when `CharSet::as_single_range` is `Some`.
+    pub fn is_contiguous(&self) -> bool {
+        self.as_single_range().is_some()
+    }
+
+    /// The range of codepoints that makes up this set, if it is a single nonempty range.
+    ///
+    /// Ranges only separated by the surrogate gap count as a single range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let ranges = [CharRange::closed('\0', '\u{D7FF}'), CharRange::closed('\u{E000}', 'z')];
+    /// assert_eq!(CharSet::from_raw(&ranges).as_single_range(), Some(CharRange::from('\0'..='z')));
+    /// assert_eq!(CharSet::from_raw(&ranges[..1]).as_single_range(), Some(ranges[0]));
+    /// assert_eq!(CharSet::empty().as_single_range(), None);
+    /// ```
+    pub fn as_single_range(&self) -> Option<CharRange> {
+        match self.ranges {
+            [r] => Some(r),
+            [below, above] if below.high == BEFORE_SURROGATE && above.low == AFTER_SURROGATE => {
+                Some(CharRange::closed(below.low, above.high))
+            }
+            _ => None,
+        }
+    }
+
+    /// The lowest codepoint in this set.
+    pub fn first(&self) -> Option<char> {
+        self.ranges.first().map(|r| r.low)
+    }
+
+    /// The highest codepoint in this set.
+    pub fn last(&self) -> Option<char> {
+        self.ranges.last().map(|r| r.high)
+    }
+
+    /// How many codepoints of this set are lower than this codepoint, if it is in this set.
+    ///
+    /// This is the inverse of `nth`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let hex = [CharRange::closed('0', '9'), CharRange::closed('a', 'f')];
+    /// let hex = CharSet::from_raw(&hex);
+    /// assert_eq!(hex.position('0'), Some(0));
+    /// assert_eq!(hex.position('c'), Some(12));
+    /// assert_eq!(hex.position('x'), None);
+    /// ```
+    pub fn position(&self, c: char) -> Option<usize> {
+        let idx = self.search(c).ok()?;
+        let before: usize = self.ranges[..idx].iter().map(|r| r.len()).sum();
+        Some(before + CharRange::from(self.ranges[idx].low..c).len())
+    }
+
+    /// The `n`th lowest codepoint in this set, counting from zero.
+    ///
+    /// This is the inverse of `position`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let hex = [CharRange::closed('0', '9'), CharRange::closed('a', 'f')];
+    /// let hex = CharSet::from_raw(&hex);
+    /// assert_eq!(hex.nth(0), Some('0'));
+    /// assert_eq!(hex.nth(12), Some('c'));
+    /// assert_eq!(hex.nth(16), None);
+    /// ```
+    pub fn nth(&self, mut n: usize) -> Option<char> {
+        for r in self.ranges() {
+            let len = r.len();
+            if n < len {
+                return r.iter().nth(n);
+            }
+            n -= len;
+        }
+        None
+    }
+
+    /// The lowest codepoint of this set which is not below `c`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let ranges = [CharRange::closed('0', '9'), CharRange::closed('a', 'f')];
+    /// let hex = CharSet::from_raw(&ranges);
+    /// assert_eq!(hex.next_member('5'), Some('5'));
+    /// assert_eq!(hex.next_member('A'), Some('a'));
+    /// assert_eq!(hex.next_member('g'), None);
+    /// ```
+    pub fn next_member(&self, c: char) -> Option<char> {
+        match self.search(c) {
+            Ok(_) => Some(c),
+            Err(idx) => self.ranges.get(idx).map(|r| r.low),
+        }
+    }
+
+    /// The lowest codepoint not in this set which is not below `c`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let ranges = [CharRange::closed('0', '9'), CharRange::closed('a', 'f')];
+    /// let hex = CharSet::from_raw(&ranges);
+    /// assert_eq!(hex.next_gap('5'), Some(':'));
+    /// assert_eq!(hex.next_gap('A'), Some('A'));
+    /// assert_eq!(hex.next_gap('a'), Some('g'));
+    /// ```
+    pub fn next_gap(&self, c: char) -> Option<char> {
+        let mut idx = match self.search(c) {
+            Ok(idx) => idx,
+            Err(_) => return Some(c),
+        };
+        loop {
+            let after = CharRange::from((Bound::Excluded(self.ranges[idx].high), Bound::Unbounded));
+            if after.is_empty() {
+                return None;
+            }
+            // ranges are split at the surrogates, so the gap may not be a gap
+            match self.ranges.get(idx + 1) {
+                Some(r) if r.low == after.low => idx += 1,
+                _ => return Some(after.low),
+            }
+        }
+    }
+
+    /// Are all codepoints of this set also in `other`?
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let lower = [CharRange::closed('a', 'z')];
+    /// let alpha = [CharRange::closed('A', 'Z'), CharRange::closed('a', 'z')];
+    /// assert!( CharSet::from_raw(&lower).is_subset(CharSet::from_raw(&alpha)));
+    /// assert!(!CharSet::from_raw(&alpha).is_subset(CharSet::from_raw(&lower)));
+    /// ```
+    pub fn is_subset(&self, other: &CharSet) -> bool {
+        let mut others = other.ranges.iter();
+        let mut cover = others.next();
+        for &r in &self.ranges {
+            let mut rest = r;
+            while !rest.is_empty() {
+                match cover {
+                    Some(c) if c.high < rest.low => cover = others.next(),
+                    Some(c) if c.low <= rest.low => {
+                        rest =
+                            CharRange::from((Bound::Excluded(c.high), Bound::Included(rest.high)))
+                    }
+                    _ => return false,
+                }
+            }
+        }
+        true
+    }
+
+    /// Are all codepoints of `other` also in this set?
+    pub fn is_superset(&self, other: &CharSet) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Do this set and `other` have no codepoints in common?
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let lower = [CharRange::closed('a', 'z')];
+    /// let upper = [CharRange::closed('A', 'Z')];
+    /// let alpha = [CharRange::closed('A', 'Z'), CharRange::closed('a', 'z')];
+    /// assert!( CharSet::from_raw(&lower).is_disjoint(CharSet::from_raw(&upper)));
+    /// assert!(!CharSet::from_raw(&alpha).is_disjoint(CharSet::from_raw(&upper)));
+    /// ```
+    pub fn is_disjoint(&self, other: &CharSet) -> bool {
+        !self.overlaps(other)
+    }
+
+    /// Do this set and `other` have any codepoints in common?
+    ///
+    /// This stops at the first common codepoint, and only walks the ranges
+    /// of each set that are within the span of the other.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let ident = [CharRange::closed('0', '9'), CharRange::closed('_', '_'), CharRange::closed('a', 'z')];
+    /// let digit = [CharRange::closed('0', '9')];
+    /// let punct = [CharRange::closed('!', '/')];
+    /// assert!(CharSet::from_raw(&ident).overlaps(CharSet::from_raw(&digit)));
+    /// assert!(!CharSet::from_raw(&ident).overlaps(CharSet::from_raw(&punct)));
+    /// ```
+    pub fn overlaps(&self, other: &CharSet) -> bool {
+        let (lhs, rhs) = match (self.span(), other.span()) {
+            (Some(lhs), Some(rhs)) => (self.overlapping(rhs), other.overlapping(lhs)),
+            _ => return false,
+        };
+        let (mut lhs, mut rhs) = (lhs.iter().peekable(), rhs.iter().peekable());
+        while let (Some(l), Some(r)) = (lhs.peek(), rhs.peek()) {
+            if l.high < r.low {
+                lhs.next();
+            } else if r.high < l.low {
+                rhs.next();
+            } else {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// The range from the lowest to the highest codepoint of this set.
+    fn span(&self) -> Option<CharRange> {
+        Some(CharRange::closed(self.first()?, self.last()?))
+    }
+
     /// Binary search for where a codepoint should be in this set.
     ///
     /// If the value is found then `Ok` is returned, containing the index of
@@ -65,4 +745,337 @@ impl CharSet {
     fn search(&self, c: char) -> Result<usize, usize> {
         self.ranges.binary_search_by(|r| r.cmp_char(c))
     }
+
+    /// The ranges of this set which overlap with a nonempty range.
+    fn overlapping(&self, r: CharRange) -> &[CharRange] {
+        debug_assert!(!r.is_empty());
+        let low = self.search(r.low).unwrap_or_else(|idx| idx);
+        let high = self.search(r.high).map_or_else(|idx| idx, |idx| idx + 1);
+        &self.ranges[low..high]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, alloc::vec::Vec};
+
+    #[test]
+    #[rustfmt::skip]
+    fn display() {
+        use alloc::string::ToString;
+
+        let test_data: &[(&[CharRange], &str)] = &[
+            (&[],                                                     "[]"),
+            (&[CharRange::closed('a', 'z')],                          "[a-z]"),
+            (&[CharRange::closed('0', '9'), CharRange::closed('a', 'b')], "[0-9a-b]"),
+            (&[CharRange::closed('[', '^')],                          r"[\[-\^]"),
+            (&[CharRange::closed('\0', ' ')],                         r"[\u{0}- ]"),
+            (&[CharRange::closed('\u{7F}', '\u{7F}')],                r"[\u{7F}]"),
+            (&[CharRange::closed('\u{1F600}', '\u{1F64F}')],          r"[\u{1F600}-\u{1F64F}]"),
+        ];
+
+        for &(ranges, expected) in test_data {
+            assert_eq!(CharSet::from_raw(ranges).to_string(), expected);
+        }
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    #[cfg(feature = "new-set")]
+    fn generate_ascii_digits() {
+        use {alloc::string::ToString, quote::quote};
+
+        assert_eq!(
+            generate(|c| c.is_ascii_digit() || c == char::MAX).to_string(),
+            quote!({
+                const RANGES: &[CharRange] = &[
+                    CharRange::closed('0', '9'),
+                    CharRange::closed('\u{10ffff}', '\u{10ffff}')
+                ];
+                CharSet::from_raw(RANGES)
+            })
+            .to_string(),
+        );
+
+        // This is said set actually in memory
+        static SET: &CharSet = {
+            const RANGES: &[CharRange] = &[
+                CharRange::closed('0', '9'),
+                CharRange::closed('\u{10ffff}', '\u{10ffff}'),
+            ];
+            CharSet::from_raw(RANGES)
+        };
+        assert!(SET.contains('5'));
+        assert!(SET.contains(char::MAX));
+        assert!(!SET.contains('a'));
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn try_from_raw() {
+        let r = CharRange::closed;
+        let test_data: &[(&[CharRange], Result<(), RangesError>)] = &[
+            (&[],                                             Ok(())),
+            (&[r('a', 'c'), r('e', 'f')],                     Ok(())),
+            (&[r('\0', '\u{D7FF}'), r('\u{E000}', char::MAX)], Ok(())),
+            (&[r('c', 'a')],                                  Err(RangesError::Empty(0))),
+            (&[r('e', 'f'), r('a', 'c')],                     Err(RangesError::Unsorted(1))),
+            (&[r('a', 'c'), r('c', 'f')],                     Err(RangesError::Overlapping(1))),
+            (&[r('a', 'c'), r('d', 'f')],                     Err(RangesError::Overlapping(1))),
+            (&[r('a', 'c'), r('e', 'f'), r('a', 'f')],        Err(RangesError::Unsorted(2))),
+        ];
+
+        for &(ranges, result) in test_data {
+            assert_eq!(CharSet::try_from_raw(ranges).map(|_| ()), result, "{:?}", ranges);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "trie")]
+    fn trie_mismatch() {
+        // ASCII, U+0800..=U+083F, U+10001, and U+10003
+        const LEVEL2: [u8; 992] = {
+            let mut level2 = [0; 992];
+            level2[0] = 1;
+            level2
+        };
+        const LEVEL3: [u8; 256] = {
+            let mut level3 = [0; 256];
+            level3[0] = 1;
+            level3
+        };
+        const CHONK: [u8; 64] = {
+            let mut chonk = [0; 64];
+            chonk[0] = 2;
+            chonk
+        };
+        static TRIE: CharTrie<'static> = CharTrie::from_raw(
+            &[
+                !0, !0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0,
+            ],
+            &LEVEL2,
+            (&LEVEL3, &[[0; 64], CHONK]),
+            &[0, !0, 0b1010],
+        );
+
+        let r = CharRange::closed;
+        #[rustfmt::skip]
+        let test_data: &[(&[CharRange], Option<char>)] = &[
+            (&[r('\0', '\x7F'), r('\u{800}', '\u{83F}'), r('\u{10001}', '\u{10001}'), r('\u{10003}', '\u{10003}')], None),
+            (&[r('\0', '\x7F'), r('\u{800}', '\u{83F}'), r('\u{10001}', '\u{10001}')], Some('\u{10003}')),
+            (&[r('\0', '\x7F'), r('\u{800}', '\u{840}'), r('\u{10001}', '\u{10001}'), r('\u{10003}', '\u{10003}')], Some('\u{840}')),
+            (&[r('\0', '\x7E'), r('\u{800}', '\u{83F}'), r('\u{10001}', '\u{10001}'), r('\u{10003}', '\u{10003}')], Some('\x7F')),
+            (&[r('\0', '\u{83F}')], Some('\u{80}')),
+            (&[], Some('\0')),
+            (&[r('\0', '\x7F'), r('\u{800}', '\u{83F}'), r('\u{D7FF}', '\u{E000}')], Some('\u{D7FF}')),
+            (&[r('\0', '\x7F'), r('\u{800}', '\u{83F}'), r('\u{10001}', '\u{10001}'), r('\u{10003}', char::MAX)], Some('\u{10004}')),
+        ];
+
+        for &(ranges, result) in test_data {
+            let set = CharSet::from_raw(ranges);
+            assert_eq!(set.first_trie_mismatch(&TRIE), result, "{:?}", ranges);
+            assert_eq!(set.eq_trie(&TRIE), result.is_none());
+            let brute = CharRange::from(..)
+                .iter()
+                .find(|&c| set.contains(c) != TRIE.contains(c));
+            assert_eq!(brute, result);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "overlaps")]
+    fn from_raw_checked() {
+        let ranges = [CharRange::closed('a', 'c'), CharRange::closed('d', 'f')];
+        CharSet::from_raw_checked(&ranges);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn contains_str() {
+        let ranges = [CharRange::closed('a', 'c'), CharRange::closed('x', 'z'), CharRange::closed('\u{1F600}', '\u{1F64F}')];
+        let set = CharSet::from_raw(&ranges);
+        let test_data = [
+            // (string, contains_all, contains_any)
+            ("",                    true,  false),
+            ("abc",                 true,  true),
+            ("cabbyxz",             true,  true),
+            ("\u{1F600}ax",         true,  true),
+            ("abd",                 false, true),
+            ("dab",                 false, true),
+            ("def",                 false, false),
+            ("\u{1F650}",           false, false),
+        ];
+
+        for &(s, all, any) in &test_data {
+            assert_eq!(set.contains_all(s), all, "{:?}", s);
+            assert_eq!(set.contains_any(s), any, "{:?}", s);
+            assert_eq!(CharSet::empty().contains_all(s), s.is_empty());
+            assert!(!CharSet::empty().contains_any(s));
+        }
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn eq_range() {
+        let r = CharRange::closed;
+        let test_data: &[(&[CharRange], CharRange, bool)] = &[
+            (&[],                                               r('b', 'a'),            true),
+            (&[],                                               r('a', 'a'),            false),
+            (&[r('a', 'z')],                                    r('a', 'z'),            true),
+            (&[r('a', 'z')],                                    r('a', 'y'),            false),
+            (&[r('a', 'z')],                                    r('z', 'a'),            false),
+            (&[r('a', 'c'), r('e', 'z')],                       r('a', 'z'),            false),
+            (&[r('\0', '\u{D7FF}'), r('\u{E000}', char::MAX)],  CharRange::from(..),    true),
+            (&[r('\u{D7FE}', '\u{D7FF}'), r('\u{E000}', '\u{E001}')], r('\u{D7FE}', '\u{E001}'), true),
+        ];
+
+        for &(ranges, range, eq) in test_data {
+            assert_eq!(*CharSet::from_raw(ranges) == range, eq, "{:?} == {:?}", ranges, range);
+        }
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn eq_slice() {
+        let r = CharRange::closed;
+        let test_data: &[(&[CharRange], &[CharRange], bool)] = &[
+            (&[],                                   &[],                                               true),
+            (&[r('a', 'z')],                        &[r('a', 'z')],                                    true),
+            (&[r('a', 'z')],                        &[r('a', 'm'), r('n', 'z')],                       true),
+            (&[r('a', 'z')],                        &[r('a', 'c'), r('d', 'f'), r('g', 'z')],          true),
+            (&[r('a', 'z')],                        &[r('a', 'm'), r('o', 'z')],                       false),
+            (&[r('a', 'c'), r('x', 'z')],           &[r('a', 'c')],                                    false),
+            (&[r('\0', char::MAX)],                 &[r('\0', '\u{D7FF}'), r('\u{E000}', char::MAX)],  true),
+        ];
+
+        for &(ranges, slice, eq) in test_data {
+            assert_eq!(*CharSet::from_raw(ranges) == *slice, eq, "{:?} == {:?}", ranges, slice);
+        }
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn as_single_range() {
+        let r = CharRange::closed;
+        let test_data: &[(&[CharRange], Option<CharRange>)] = &[
+            (&[],                                       None),
+            (&[r('a', 'z')],                            Some(r('a', 'z'))),
+            (&[r('a', 'c'), r('e', 'z')],               None),
+            (&[r('a', '\u{D7FF}'), r('\u{E000}', '\u{E001}')], Some(r('a', '\u{E001}'))),
+            (&[r('a', '\u{D7FE}'), r('\u{E000}', '\u{E001}')], None),
+            (&[r('a', '\u{D7FF}'), r('\u{E000}', '\u{E001}'), r('\u{E003}', '\u{E003}')], None),
+        ];
+
+        for &(ranges, range) in test_data {
+            let set = CharSet::from_raw(ranges);
+            assert_eq!(set.as_single_range(), range, "{:?}", ranges);
+            assert_eq!(set.is_contiguous(), range.is_some(), "{:?}", ranges);
+        }
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn next_member_and_gap() {
+        let ranges = [
+            CharRange::closed('b', 'c'),
+            CharRange::closed('\u{D7FE}', '\u{D7FF}'),
+            CharRange::closed('\u{E000}', '\u{E001}'),
+            CharRange::closed('\u{10FFFE}', char::MAX),
+        ];
+        let set = CharSet::from_raw(&ranges);
+        let test_data = [
+            // (c, next_member, next_gap)
+            ('\0',          Some('b'),          Some('\0')),
+            ('b',           Some('b'),          Some('d')),
+            ('c',           Some('c'),          Some('d')),
+            ('d',           Some('\u{D7FE}'),   Some('d')),
+            ('\u{D7FE}',    Some('\u{D7FE}'),   Some('\u{E002}')),
+            ('\u{E001}',    Some('\u{E001}'),   Some('\u{E002}')),
+            ('\u{E002}',    Some('\u{10FFFE}'), Some('\u{E002}')),
+            (char::MAX,     Some(char::MAX),    None),
+        ];
+
+        for &(c, member, gap) in &test_data {
+            assert_eq!(set.next_member(c), member, "{:?}", c);
+            assert_eq!(set.next_gap(c), gap, "{:?}", c);
+        }
+        assert_eq!(CharSet::empty().next_member('\0'), None);
+        assert_eq!(CharSet::empty().next_gap(char::MAX), Some(char::MAX));
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn count_in_range() {
+        let ranges = [
+            CharRange::closed('b', 'd'),
+            CharRange::closed('\u{D7FE}', '\u{E001}'),
+            CharRange::closed(char::MAX, char::MAX),
+        ];
+        let set = CharSet::from_raw(&ranges);
+        let test_data = [
+            (CharRange::from(..),                               set.len()),
+            (CharRange::empty(),                                0),
+            (CharRange::closed('a', 'a'),                       0),
+            (CharRange::closed('a', 'c'),                       2),
+            (CharRange::closed('c', 'c'),                       1),
+            (CharRange::closed('c', '\u{D7FF}'),                4),
+            (CharRange::closed('\u{D7FF}', '\u{E000}'),         2),
+            (CharRange::closed('\u{E001}', char::MAX),          2),
+        ];
+
+        for &(range, count) in &test_data {
+            assert_eq!(set.count_in_range(range), count, "{:?}", range);
+            assert_eq!(set.count_in_range(range), set.chars().filter(|&c| range.contains(c)).count());
+        }
+    }
+
+    #[test]
+    fn statistics() {
+        let ranges = [
+            CharRange::closed('\0', '\u{D7FF}'),
+            CharRange::closed('\u{E000}', '\u{10FFFF}'),
+        ];
+        let all = CharSet::from_raw(&ranges);
+        assert_eq!(all.range_count(), 2);
+        assert_eq!(all.coverage(), 1.0);
+        assert_eq!(all.memory_footprint(), 2 * mem::size_of::<CharRange>());
+
+        let half = CharSet::from_raw(&ranges[1..]);
+        let expected = (0x11_0000 - 0xE000) as f64 / (0x11_0000 - 0x800) as f64;
+        assert!((half.coverage() - expected).abs() < 1e-12);
+        assert_eq!(CharSet::empty().memory_footprint(), 0);
+    }
+
+    #[test]
+    fn subset_and_disjoint() {
+        #[rustfmt::skip]
+        let test_data = [
+            // (lhs, rhs, lhs.is_subset(rhs), lhs.is_disjoint(rhs))
+            (&[][..], &[][..], true, true),
+            (&[][..], &['a'..='z'][..], true, true),
+            (&['a'..='z'][..], &[][..], false, true),
+            (&['a'..='z'][..], &['a'..='z'][..], true, false),
+            (&['b'..='y'][..], &['a'..='z'][..], true, false),
+            (&['a'..='z'][..], &['b'..='y'][..], false, false),
+            (&['a'..='c', 'x'..='z'][..], &['a'..='z'][..], true, false),
+            (&['a'..='c'][..], &['d'..='f'][..], false, true),
+            (&['a'..='c', 'g'..='i'][..], &['d'..='f'][..], false, true),
+            (&['a'..='d'][..], &['a'..='b', 'd'..='d'][..], false, false),
+            (&['\u{D7F0}'..='\u{E010}'][..], &['\0'..='\u{D7FF}', '\u{E000}'..=char::MAX][..], true, false),
+            (&['b'..='b', 'x'..='x'][..], &['a'..='a', 'c'..='w', 'y'..='z'][..], false, true),
+            (&['b'..='b', 'x'..='x'][..], &['a'..='a', 'c'..='x', 'z'..='z'][..], false, false),
+        ];
+
+        for &(lhs, rhs, subset, disjoint) in &test_data {
+            let lhs: Vec<CharRange> = lhs.iter().cloned().map(Into::into).collect();
+            let rhs: Vec<CharRange> = rhs.iter().cloned().map(Into::into).collect();
+            let (lhs, rhs) = (CharSet::from_raw(&lhs), CharSet::from_raw(&rhs));
+            assert_eq!(lhs.is_subset(rhs), subset, "{:?} <= {:?}", lhs, rhs);
+            assert_eq!(rhs.is_superset(lhs), subset, "{:?} >= {:?}", rhs, lhs);
+            assert_eq!(lhs.is_disjoint(rhs), disjoint, "{:?} & {:?}", lhs, rhs);
+            assert_eq!(rhs.is_disjoint(lhs), disjoint, "{:?} & {:?}", rhs, lhs);
+            assert_eq!(lhs.overlaps(rhs), !disjoint, "{:?} & {:?}", lhs, rhs);
+        }
+    }
 }
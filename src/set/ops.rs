@@ -0,0 +1,418 @@
+use {
+    crate::{set::CharSet, CharRange, AFTER_SURROGATE, BEFORE_SURROGATE},
+    core::{char, iter::FusedIterator},
+};
+
+/// One past the highest codepoint, used as the boundary of an unbounded range.
+const END: u32 = char::MAX as u32 + 1;
+
+/// A boolean operation over membership in two sets.
+pub(crate) type Op = fn(bool, bool) -> bool;
+
+#[cfg(feature = "owned-set")]
+pub(crate) fn complement(a: bool, _: bool) -> bool {
+    !a
+}
+
+pub(crate) fn union(a: bool, b: bool) -> bool {
+    a | b
+}
+
+pub(crate) fn intersection(a: bool, b: bool) -> bool {
+    a & b
+}
+
+pub(crate) fn difference(a: bool, b: bool) -> bool {
+    a & !b
+}
+
+pub(crate) fn symmetric_difference(a: bool, b: bool) -> bool {
+    a ^ b
+}
+
+/// A merge walk over the range boundaries of two sorted range lists.
+///
+/// The sweep does not hold onto the ranges it walks; they are passed in on
+/// every step instead. This allows the output to be written into the same
+/// buffer that is being walked, so long as the walked prefix is left intact.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Sweep {
+    /// Boundary index into the left list: `2 * i` is the low of range `i`,
+    /// and `2 * i + 1` is one past the high of range `i`.
+    a: usize,
+    /// Boundary index into the right list.
+    b: usize,
+    /// Whether the operation currently holds.
+    on: bool,
+    /// Where the operation started holding.
+    start: u32,
+    /// Whether the trailing range has already been yielded.
+    done: bool,
+}
+
+impl Sweep {
+    pub(crate) fn new(op: Op) -> Self {
+        Sweep {
+            a: 0,
+            b: 0,
+            on: op(false, false),
+            start: 0,
+            done: false,
+        }
+    }
+
+    /// Produce the next range for which `op` holds.
+    pub(crate) fn next(&mut self, a: &[CharRange], b: &[CharRange], op: Op) -> Option<CharRange> {
+        while !self.done {
+            let next_a = boundary(a, self.a);
+            let next_b = boundary(b, self.b);
+            let pos = next_a.min(next_b);
+
+            if pos == END {
+                self.done = true;
+                if self.on {
+                    if let Some(r) = range(self.start, END) {
+                        return Some(r);
+                    }
+                }
+                break;
+            }
+
+            if next_a == pos {
+                self.a += 1;
+            }
+            if next_b == pos {
+                self.b += 1;
+            }
+
+            let on = op(self.a & 1 == 1, self.b & 1 == 1);
+            if on != self.on {
+                self.on = on;
+                if on {
+                    self.start = pos;
+                } else if let Some(r) = range(self.start, pos) {
+                    return Some(r);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Do two sorted range lists contain the same codepoints?
+///
+/// Unlike comparing the lists directly, this treats ranges which only touch
+/// across the surrogate gap the same as a single range spanning it.
+pub(crate) fn same_members(a: &[CharRange], b: &[CharRange]) -> bool {
+    let op = symmetric_difference;
+    Sweep::new(op).next(a, b, op).is_none()
+}
+
+/// A lazy iterator over the compact ranges of the union of two sets.
+///
+/// Constructed via `CharSet::union_ranges`. See `CharSet` for more information.
+#[derive(Clone, Debug)]
+pub struct Union<'a> {
+    lhs: &'a [CharRange],
+    rhs: &'a [CharRange],
+    sweep: Sweep,
+}
+
+impl CharSet {
+    /// Iterate the compact ranges of the union of this set and `other`,
+    /// without allocating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let lower = [CharRange::closed('a', 'z')];
+    /// let digit = [CharRange::closed('0', '9')];
+    /// let union = CharSet::from_raw(&lower).union_ranges(CharSet::from_raw(&digit));
+    /// assert!(union.eq(vec![CharRange::from('0'..='9'), CharRange::from('a'..='z')]));
+    /// ```
+    pub fn union_ranges<'a>(&'a self, other: &'a CharSet) -> Union<'a> {
+        Union {
+            lhs: &self.ranges,
+            rhs: &other.ranges,
+            sweep: Sweep::new(union),
+        }
+    }
+}
+
+impl Iterator for Union<'_> {
+    type Item = CharRange;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.sweep.next(self.lhs, self.rhs, union)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.lhs.len() + self.rhs.len()))
+    }
+}
+
+impl FusedIterator for Union<'_> {}
+
+/// A lazy iterator over the compact ranges of the intersection of two sets.
+///
+/// Constructed via `CharSet::intersection_ranges`. See `CharSet` for more information.
+#[derive(Clone, Debug)]
+pub struct Intersection<'a> {
+    lhs: &'a [CharRange],
+    rhs: &'a [CharRange],
+    sweep: Sweep,
+}
+
+impl CharSet {
+    /// Iterate the compact ranges of the intersection of this set and `other`,
+    /// without allocating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let alpha = [CharRange::closed('A', 'Z'), CharRange::closed('a', 'z')];
+    /// let hex = [CharRange::closed('0', '9'), CharRange::closed('A', 'F'), CharRange::closed('a', 'f')];
+    /// let both = CharSet::from_raw(&alpha).intersection_ranges(CharSet::from_raw(&hex));
+    /// assert!(both.eq(vec![CharRange::from('A'..='F'), CharRange::from('a'..='f')]));
+    /// ```
+    pub fn intersection_ranges<'a>(&'a self, other: &'a CharSet) -> Intersection<'a> {
+        Intersection {
+            lhs: &self.ranges,
+            rhs: &other.ranges,
+            sweep: Sweep::new(intersection),
+        }
+    }
+}
+
+impl Iterator for Intersection<'_> {
+    type Item = CharRange;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.sweep.next(self.lhs, self.rhs, intersection)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.lhs.len() + self.rhs.len()))
+    }
+}
+
+impl FusedIterator for Intersection<'_> {}
+
+/// A lazy iterator over the compact ranges of the difference of two sets.
+///
+/// Constructed via `CharSet::difference_ranges`. See `CharSet` for more information.
+#[derive(Clone, Debug)]
+pub struct Difference<'a> {
+    lhs: &'a [CharRange],
+    rhs: &'a [CharRange],
+    sweep: Sweep,
+}
+
+impl CharSet {
+    /// Iterate the compact ranges of the codepoints in this set but not in `other`,
+    /// without allocating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let lower = [CharRange::closed('a', 'z')];
+    /// let vowels = [
+    ///     CharRange::singleton('a'),
+    ///     CharRange::singleton('e'),
+    ///     CharRange::singleton('i'),
+    ///     CharRange::singleton('o'),
+    ///     CharRange::singleton('u'),
+    /// ];
+    /// let consonants = CharSet::from_raw(&lower).difference_ranges(CharSet::from_raw(&vowels));
+    /// assert!(consonants.eq(vec![
+    ///     CharRange::from('b'..='d'),
+    ///     CharRange::from('f'..='h'),
+    ///     CharRange::from('j'..='n'),
+    ///     CharRange::from('p'..='t'),
+    ///     CharRange::from('v'..='z'),
+    /// ]));
+    /// ```
+    pub fn difference_ranges<'a>(&'a self, other: &'a CharSet) -> Difference<'a> {
+        Difference {
+            lhs: &self.ranges,
+            rhs: &other.ranges,
+            sweep: Sweep::new(difference),
+        }
+    }
+}
+
+impl Iterator for Difference<'_> {
+    type Item = CharRange;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.sweep.next(self.lhs, self.rhs, difference)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.lhs.len() + self.rhs.len()))
+    }
+}
+
+impl FusedIterator for Difference<'_> {}
+
+/// A lazy iterator over the ranges of codepoints missing from a set within a window.
+///
+/// Constructed via `CharSet::gaps`. See `CharSet` for more information.
+#[derive(Clone, Debug)]
+pub struct Gaps<'a> {
+    window: Option<CharRange>,
+    ranges: &'a [CharRange],
+    sweep: Sweep,
+}
+
+impl CharSet {
+    /// Iterate the maximal ranges of codepoints within `within` that are not in this set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let covered = [CharRange::closed('a', 'c'), CharRange::closed('x', 'z')];
+    /// let gaps = CharSet::from_raw(&covered).gaps(CharRange::from('a'..='z'));
+    /// assert!(gaps.eq(vec![CharRange::from('d'..='w')]));
+    /// ```
+    pub fn gaps(&self, within: CharRange) -> Gaps<'_> {
+        let (window, ranges) = if within.is_empty() {
+            (None, &[][..])
+        } else {
+            (Some(within), self.overlapping(within))
+        };
+        Gaps {
+            window,
+            ranges,
+            sweep: Sweep::new(difference),
+        }
+    }
+}
+
+impl Iterator for Gaps<'_> {
+    type Item = CharRange;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.sweep
+            .next(self.window.as_slice(), self.ranges, difference)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.ranges.len() + 1))
+    }
+}
+
+impl FusedIterator for Gaps<'_> {}
+
+/// The codepoint position of boundary `idx` in `ranges`.
+fn boundary(ranges: &[CharRange], idx: usize) -> u32 {
+    match ranges.get(idx / 2) {
+        None => END,
+        Some(r) if idx & 1 == 0 => r.low as u32,
+        Some(r) => r.high as u32 + 1,
+    }
+}
+
+/// The range of codepoints in `start..end`, or `None` if there are none.
+fn range(start: u32, end: u32) -> Option<CharRange> {
+    if start >= end {
+        return None;
+    }
+    let low = char::from_u32(start).unwrap_or(AFTER_SURROGATE);
+    let high = char::from_u32(end - 1).unwrap_or(BEFORE_SURROGATE);
+    Some(CharRange::closed(low, high)).filter(|r| !r.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, alloc::vec::Vec, core::ops::RangeInclusive};
+
+    fn set(ranges: &[RangeInclusive<char>]) -> Vec<CharRange> {
+        ranges.iter().cloned().map(Into::into).collect()
+    }
+
+    #[test]
+    fn union_ranges() {
+        #[rustfmt::skip]
+        let test_data = [
+            (&[][..], &[][..], &[][..]),
+            (&['a'..='c'][..], &[][..], &['a'..='c'][..]),
+            (&['a'..='c'][..], &['d'..='f'][..], &['a'..='f'][..]),
+            (&['a'..='c', 'x'..='z'][..], &['e'..='f'][..], &['a'..='c', 'e'..='f', 'x'..='z'][..]),
+            (&['a'..='c', 'x'..='z'][..], &['b'..='y'][..], &['a'..='z'][..]),
+            (&['\0'..='\u{D7FF}'][..], &['\u{E000}'..=char::MAX][..], &['\0'..='\u{D7FF}', '\u{E000}'..=char::MAX][..]),
+        ];
+
+        for &(lhs, rhs, result) in &test_data {
+            let (lhs, rhs, result) = (set(lhs), set(rhs), set(result));
+            let (lhs, rhs) = (CharSet::from_raw(&lhs), CharSet::from_raw(&rhs));
+            assert_eq!(lhs.union_ranges(rhs).collect::<Vec<_>>(), result);
+            assert_eq!(rhs.union_ranges(lhs).collect::<Vec<_>>(), result);
+        }
+    }
+
+    #[test]
+    fn intersection_and_difference_ranges() {
+        #[rustfmt::skip]
+        let test_data = [
+            // (lhs, rhs, lhs & rhs, lhs - rhs)
+            (&[][..], &[][..], &[][..], &[][..]),
+            (&['a'..='c'][..], &[][..], &[][..], &['a'..='c'][..]),
+            (&[][..], &['a'..='c'][..], &[][..], &[][..]),
+            (&['a'..='c'][..], &['d'..='f'][..], &[][..], &['a'..='c'][..]),
+            (&['a'..='m'][..], &['g'..='z'][..], &['g'..='m'][..], &['a'..='f'][..]),
+            (&['a'..='z'][..], &['g'..='m'][..], &['g'..='m'][..], &['a'..='f', 'n'..='z'][..]),
+            (&['a'..='c', 'x'..='z'][..], &['b'..='y'][..], &['b'..='c', 'x'..='y'][..], &['a'..='a', 'z'..='z'][..]),
+            (&['\0'..=char::MAX][..], &['\0'..='\u{D7FF}'][..], &['\0'..='\u{D7FF}'][..], &['\u{E000}'..=char::MAX][..]),
+        ];
+
+        for &(lhs, rhs, intersection, difference) in &test_data {
+            let (lhs, rhs) = (set(lhs), set(rhs));
+            let (lhs, rhs) = (CharSet::from_raw(&lhs), CharSet::from_raw(&rhs));
+            assert_eq!(
+                lhs.intersection_ranges(rhs).collect::<Vec<_>>(),
+                set(intersection)
+            );
+            assert_eq!(
+                rhs.intersection_ranges(lhs).collect::<Vec<_>>(),
+                set(intersection)
+            );
+            assert_eq!(
+                lhs.difference_ranges(rhs).collect::<Vec<_>>(),
+                set(difference)
+            );
+        }
+    }
+
+    #[test]
+    fn gaps() {
+        #[rustfmt::skip]
+        let test_data = [
+            (&[][..], 'a'..='z', &['a'..='z'][..]),
+            (&['a'..='c'][..], 'z'..='a', &[][..]),
+            (&['a'..='z'][..], 'b'..='y', &[][..]),
+            (&['a'..='c', 'x'..='z'][..], 'a'..='z', &['d'..='w'][..]),
+            (&['a'..='c', 'x'..='z'][..], '\0'..=char::MAX, &['\0'..='`', 'd'..='w', '{'..=char::MAX][..]),
+            (&['a'..='c', 'g'..='i', 'x'..='z'][..], 'b'..='y', &['d'..='f', 'j'..='w'][..]),
+            (&['\0'..='\u{D7FF}'][..], 'a'..=char::MAX, &['\u{E000}'..=char::MAX][..]),
+        ];
+
+        for &(ranges, ref within, result) in &test_data {
+            let ranges = set(ranges);
+            let gaps = CharSet::from_raw(&ranges).gaps(within.clone().into());
+            assert_eq!(gaps.collect::<Vec<_>>(), set(result));
+        }
+    }
+}
@@ -1,31 +1,74 @@
+#[cfg(feature = "trie")]
+use crate::trie::CharTrie;
 use {
-    crate::{range::CharRange, set::CharSet},
-    alloc::{vec, vec::Vec},
+    crate::{
+        parse::{self, ClassError},
+        range::CharRange,
+        set::{ops, CharSet},
+        AFTER_SURROGATE, BEFORE_SURROGATE,
+    },
+    alloc::{borrow::ToOwned, boxed::Box, collections::BinaryHeap, vec, vec::Vec},
     core::{
-        char, cmp,
-        iter::FromIterator,
-        ops::{Bound, Deref},
+        borrow::Borrow,
+        char, cmp, fmt,
+        iter::{self, FromIterator, FusedIterator},
+        mem,
+        ops::{
+            Bound, Deref, Not, Range, RangeFrom, RangeFull, RangeInclusive, RangeTo,
+            RangeToInclusive,
+        },
+        str::FromStr,
     },
 };
 
+/// How many ranges a `CharSetBuf` stores inline with the `inline-set` feature.
+#[cfg(feature = "inline-set")]
+pub const INLINE_RANGES: usize = 4;
+
+/// The storage of the ranges of a `CharSetBuf`.
+#[cfg(not(feature = "inline-set"))]
+type Ranges = Vec<CharRange>;
+#[cfg(feature = "inline-set")]
+type Ranges = smallvec::SmallVec<[CharRange; INLINE_RANGES]>;
+
 /// A mutable set of codepoints represented by the compact ranges of codepoints.
+///
+/// With the `inline-set` feature, up to `INLINE_RANGES` ranges are stored
+/// inline rather than on the heap.
 #[derive(Clone, Debug, Default, Eq, PartialEq, PartialOrd, Hash)]
 pub struct CharSetBuf {
     /// # Correctness
     ///
     /// - Must remain sorted
     /// - Ranges must not overlap or touch
-    pub(self) ranges: Vec<CharRange>,
+    pub(super) ranges: Ranges,
 }
 
 impl Deref for CharSetBuf {
     type Target = CharSet;
 
     fn deref(&self) -> &Self::Target {
-        CharSet::from_raw(&*self.ranges)
+        CharSet::from_raw(&self.ranges)
+    }
+}
+
+impl Borrow<CharSet> for CharSetBuf {
+    fn borrow(&self) -> &CharSet {
+        self
+    }
+}
+
+impl ToOwned for CharSet {
+    type Owned = CharSetBuf;
+
+    fn to_owned(&self) -> CharSetBuf {
+        CharSetBuf {
+            ranges: Ranges::from(&self.ranges[..]),
+        }
     }
 }
 
+#[allow(clippy::derive_ord_xor_partial_ord)]
 impl Ord for CharSetBuf {
     fn cmp(&self, other: &Self) -> cmp::Ordering {
         self.partial_cmp(other)
@@ -33,81 +76,340 @@ impl Ord for CharSetBuf {
     }
 }
 
-// sorry for the inference issues this causes I guess ¯\_(ツ)_/¯
-impl<R: Into<CharRange>> From<R> for CharSetBuf {
-    fn from(range: R) -> Self {
-        Self {
-            ranges: vec![range.into()],
+// one impl per range type rather than a blanket `impl<R: Into<CharRange>>`,
+// so that slices of ranges can be converted as well
+macro_rules! from_range {
+    ($($ty:ty),* $(,)?) => {$(
+        impl From<$ty> for CharSetBuf {
+            fn from(range: $ty) -> Self {
+                Self {
+                    ranges: iter::once(CharRange::from(range))
+                        .filter(|r| !r.is_empty())
+                        .collect(),
+                }
+            }
         }
-    }
+    )*};
+}
+
+from_range! {
+    CharRange,
+    RangeFull,
+    Range<char>,
+    RangeFrom<char>,
+    RangeTo<char>,
+    RangeInclusive<char>,
+    RangeToInclusive<char>,
+    (Bound<char>, Bound<char>),
 }
 
 impl CharSetBuf {
     /// An empty set.
     pub fn new() -> Self {
-        Self { ranges: Vec::new() }
+        Self {
+            ranges: Ranges::new(),
+        }
+    }
+
+    /// Adopt a vector of ranges that is already in canonical form.
+    ///
+    /// The ranges must be nonempty, sorted, and neither overlap nor touch,
+    /// as checked by `CharSet::try_from_raw`. This is not checked unless the
+    /// `debug-validate` feature is enabled; violating it will not cause
+    /// undefined behavior, but will cause incorrect results.
+    #[track_caller]
+    pub fn from_sorted_ranges_unchecked(ranges: Vec<CharRange>) -> Self {
+        #[cfg(feature = "inline-set")]
+        let ranges = Ranges::from_vec(ranges);
+        let set = CharSetBuf { ranges };
+        set.validate();
+        set
+    }
+
+    /// The set of every codepoint for which the predicate holds.
+    ///
+    /// This tests every codepoint once, building the ranges from runs of
+    /// members directly, so is much faster than collecting the members.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::set::CharSetBuf;
+    /// let digits = CharSetBuf::from_fn(|c| c.is_ascii_digit());
+    /// assert_eq!(digits, CharSetBuf::from('0'..='9'));
+    /// ```
+    pub fn from_fn(f: impl Fn(char) -> bool) -> Self {
+        let mut set = Self::new();
+        super::for_each_run(f, |r| set.ranges.push(r));
+        set
     }
 
     /// Create a set with the specified capacity for compact ranges
     pub fn with_capacity(capacity: usize) -> Self {
         CharSetBuf {
-            ranges: Vec::with_capacity(capacity),
+            ranges: Ranges::with_capacity(capacity),
         }
     }
 }
 
+impl CharSetBuf {
+    /// Take the compact ranges of this set.
+    pub fn into_ranges(self) -> Vec<CharRange> {
+        #[cfg(feature = "inline-set")]
+        return self.ranges.into_vec();
+        #[cfg(not(feature = "inline-set"))]
+        return self.ranges;
+    }
+
+    /// Convert this set into a boxed set slice, dropping any excess capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use {mileage::set::{CharSet, CharSetBuf}, std::sync::Arc};
+    /// let mut set = CharSetBuf::with_capacity(16);
+    /// set.extend("hello".chars());
+    /// let shared: Arc<CharSet> = set.into_boxed_set().into();
+    /// assert!(shared.contains_all("hole"));
+    /// ```
+    #[allow(unsafe_code)]
+    pub fn into_boxed_set(self) -> Box<CharSet> {
+        let ranges = Box::into_raw(self.into_ranges().into_boxed_slice());
+        // SAFETY: `CharSet` is a `repr(transparent)` wrapper of `[CharRange]`
+        unsafe { Box::from_raw(ranges as *mut CharSet) }
+    }
+}
+
+impl From<Box<CharSet>> for CharSetBuf {
+    #[allow(unsafe_code)]
+    fn from(set: Box<CharSet>) -> Self {
+        // SAFETY: `CharSet` is a `repr(transparent)` wrapper of `[CharRange]`
+        let ranges = unsafe { Box::from_raw(Box::into_raw(set) as *mut [CharRange]) };
+        CharSetBuf::from_sorted_ranges_unchecked(ranges.into_vec())
+    }
+}
+
+/// Normalizes the ranges, which may be in any order and may overlap.
+///
+/// # Examples
+///
+/// ```
+/// # use {core::ops::RangeInclusive, mileage::{set::CharSetBuf, CharRange}};
+/// static HEX: &[RangeInclusive<char>] = &['a'..='f', '0'..='9', 'A'..='F', 'a'..='c'];
+/// let set = CharSetBuf::from(HEX);
+/// assert_eq!(set.range_count(), 3);
+/// assert_eq!(set.len(), 22);
+/// ```
+impl From<&[RangeInclusive<char>]> for CharSetBuf {
+    fn from(ranges: &[RangeInclusive<char>]) -> Self {
+        ranges.iter().cloned().collect()
+    }
+}
+
+/// Collects every codepoint of the string, as `CharSetBuf::insert_str`.
+impl From<&str> for CharSetBuf {
+    fn from(s: &str) -> Self {
+        let mut set = CharSetBuf::new();
+        set.insert_str(s);
+        set
+    }
+}
+
+/// Normalizes the ranges, which may be in any order and may overlap.
+impl<const N: usize> From<&[RangeInclusive<char>; N]> for CharSetBuf {
+    fn from(ranges: &[RangeInclusive<char>; N]) -> Self {
+        CharSetBuf::from(&ranges[..])
+    }
+}
+
+/// Reads the compact ranges out of the trie, as `CharTrie::ranges`.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::{set::CharSetBuf, trie::CharTrie};
+/// static ASCII: CharTrie<'static> = CharTrie::from_raw(
+///     &[!0, !0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+///     &[0; 992],
+///     (&[0; 256], &[[0; 64]]),
+///     &[0],
+/// );
+/// let mut set = CharSetBuf::from(ASCII);
+/// set.remove('a');
+/// assert_eq!(set, CharSetBuf::from(&['\0'..='`', 'b'..='\x7F']));
+/// ```
+#[cfg(feature = "trie")]
+impl From<CharTrie<'_>> for CharSetBuf {
+    fn from(trie: CharTrie<'_>) -> Self {
+        CharSetBuf::from_sorted_ranges_unchecked(trie.ranges().collect())
+    }
+}
+
+impl CharSetBuf {
+    /// How many compact ranges this set can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.ranges.capacity()
+    }
+
+    /// Reserve capacity for at least `additional` more compact ranges.
+    pub fn reserve(&mut self, additional: usize) {
+        self.ranges.reserve(additional)
+    }
+
+    /// Shrink the capacity of this set to fit its compact ranges.
+    pub fn shrink_to_fit(&mut self) {
+        self.ranges.shrink_to_fit()
+    }
+
+    /// The number of bytes used by this set, including both the
+    /// `CharSetBuf` itself and its heap allocation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::set::CharSetBuf;
+    /// let mut set = CharSetBuf::with_capacity(16);
+    /// set.extend("acegikm".chars());
+    /// let before = set.memory_footprint();
+    /// set.shrink_to_fit();
+    /// assert_eq!(set.capacity(), set.range_count());
+    /// assert!(set.memory_footprint() < before);
+    /// ```
+    pub fn memory_footprint(&self) -> usize {
+        #[cfg(feature = "inline-set")]
+        let heap = if self.ranges.spilled() {
+            self.capacity()
+        } else {
+            0
+        };
+        #[cfg(not(feature = "inline-set"))]
+        let heap = self.capacity();
+        mem::size_of::<Self>() + heap * mem::size_of::<CharRange>()
+    }
+}
+
 impl CharSetBuf {
     /// Clear this set such that it is empty again.
     pub fn clear(&mut self) {
         self.ranges.clear()
     }
 
+    /// Remove all ranges from this set, yielding them in order.
+    ///
+    /// The set is empty after this call, even if the iterator is not fully consumed.
+    pub fn drain(&mut self) -> Drain<'_> {
+        Drain {
+            raw: self.ranges.drain(..),
+        }
+    }
+
+    /// Remove a range of codepoints from this set, yielding the removed portions in order.
+    ///
+    /// The codepoints are removed even if the iterator is not fully consumed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use {core::iter::FromIterator, mileage::{set::CharSetBuf, CharRange}};
+    /// let mut set = CharSetBuf::from_iter(vec![
+    ///     CharRange::from('a'..='c'),
+    ///     CharRange::from('x'..='z'),
+    /// ]);
+    /// let removed: CharSetBuf = set.drain_range(CharRange::from('b'..='y')).collect();
+    /// assert_eq!(removed, CharSetBuf::from_iter(vec![
+    ///     CharRange::from('b'..='c'),
+    ///     CharRange::from('x'..='y'),
+    /// ]));
+    /// assert_eq!(set, CharSetBuf::from_iter(vec!['a', 'z']));
+    /// ```
+    pub fn drain_range(&mut self, r: CharRange) -> Drain<'_> {
+        if r.is_empty() {
+            return Drain {
+                raw: self.ranges.drain(0..0),
+            };
+        }
+
+        // inclusive index of lowest edited range
+        let mut low = self.search(r.low).unwrap_or_else(|it| it);
+        // exclusive index of highest edited range
+        let mut high = match self.search(r.high) {
+            Ok(idx) => idx + 1,
+            Err(idx) => idx,
+        };
+
+        if low < high {
+            // split off the parts of the edited ranges that stick out of the drained range
+            let below = CharRange::from((
+                Bound::Included(self.ranges[low].low),
+                Bound::Excluded(r.low),
+            ));
+            let above = CharRange::from((
+                Bound::Excluded(r.high),
+                Bound::Included(self.ranges[high - 1].high),
+            ));
+            self.ranges[low].low = cmp::max(self.ranges[low].low, r.low);
+            self.ranges[high - 1].high = cmp::min(self.ranges[high - 1].high, r.high);
+            if !above.is_empty() {
+                self.ranges.insert(high, above);
+            }
+            if !below.is_empty() {
+                self.ranges.insert(low, below);
+                low += 1;
+                high += 1;
+            }
+        }
+
+        Drain {
+            raw: self.ranges.drain(low..high),
+        }
+    }
+
     /// Insert a single codepoint to this set.
     ///
+    /// Returns whether the codepoint was newly inserted.
+    ///
     /// # Examples
     ///
     /// ```
     /// # use mileage::set::CharSetBuf;
     /// let mut set = CharSetBuf::from('a'..='b');
-    /// set.insert('d');
-    /// set.insert('c');
+    /// assert!(set.insert('d'));
+    /// assert!(set.insert('c'));
+    /// assert!(!set.insert('a'));
     /// assert_eq!(set, CharSetBuf::from('a'..='d'));
     /// ```
-    pub fn insert(&mut self, c: char) {
-        if let Err(idx) = self.search(c) {
-            if idx == self.ranges.len() {
-                self.ranges.push(CharRange::singleton(c));
-                return;
-            }
-
-            let above = &mut self.ranges[idx];
-            debug_assert!(above.low > c);
-            let high = above.high;
-
-            if above.low as u32 - c as u32 == 1 {
-                above.low = c;
-            } else {
-                self.ranges.insert(idx, CharRange::singleton(c));
-            }
+    pub fn insert(&mut self, c: char) -> bool {
+        let idx = match self.search(c) {
+            Ok(_) => return false,
+            Err(idx) => idx,
+        };
 
-            if idx > 0 {
-                let below = &mut self.ranges[idx - 1];
-                if c as u32 - below.high as u32 <= 1 {
-                    below.high = high;
-                    self.ranges.remove(idx);
-                }
+        let joins_below = idx > 0 && c as u32 - self.ranges[idx - 1].high as u32 == 1;
+        let joins_above = idx < self.ranges.len() && self.ranges[idx].low as u32 - c as u32 == 1;
+        match (joins_below, joins_above) {
+            (true, true) => {
+                self.ranges[idx - 1].high = self.ranges[idx].high;
+                self.ranges.remove(idx);
             }
+            (true, false) => self.ranges[idx - 1].high = c,
+            (false, true) => self.ranges[idx].low = c,
+            (false, false) => self.ranges.insert(idx, CharRange::singleton(c)),
         }
+        self.validate();
+        true
     }
 
     /// Insert a range of codepoints into this set.
     ///
     /// Functionally equivalent to inserting each character separately, but done
     /// with a constant amount of work roughly equivalent to inserting a single codepoint.
-    pub fn insert_range(&mut self, r: CharRange) {
+    ///
+    /// Returns how many codepoints were newly inserted.
+    pub fn insert_range(&mut self, r: CharRange) -> usize {
         if r.is_empty() {
-            return;
+            return 0;
         }
+        let inserted = r.len() - self.count_in_range(r);
 
         // low_idx: inclusive index of lowest replaced range
         // low_char: lowest char of the new inserted range
@@ -145,19 +447,78 @@ impl CharSetBuf {
             // fix the remaining range to cover entire new range
             self.ranges[low_idx] = CharRange::from(low_char..=high_char);
         }
+        self.validate();
+        inserted
+    }
+
+    /// Insert many codepoints in any order.
+    ///
+    /// This collects and sorts the codepoints, then merges them into the set
+    /// all at once. This is much faster than inserting them one by one when
+    /// there are many, as each `insert` may need to shift the ranges after it.
+    /// `FromIterator<char>` always takes this path, and `Extend<char>`
+    /// takes it when the iterator reports that it is long.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::set::CharSetBuf;
+    /// let mut set = CharSetBuf::new();
+    /// set.extend_unsorted("the quick brown fox jumps over the lazy dog".chars());
+    /// assert_eq!(set.range_count(), 2); // ' ' and 'a'..='z'
+    /// assert_eq!(set.len(), 27);
+    /// ```
+    pub fn extend_unsorted(&mut self, chars: impl IntoIterator<Item = char>) {
+        let mut chars: Vec<char> = chars.into_iter().collect();
+        chars.sort_unstable();
+        let mut ranges = Ranges::new();
+        for c in chars {
+            match ranges.last_mut() {
+                Some(r) if c as u32 <= r.high as u32 + 1 => r.high = c,
+                _ => ranges.push(CharRange::singleton(c)),
+            }
+        }
+        if self.ranges.is_empty() {
+            self.ranges = ranges;
+            self.validate();
+        } else {
+            self.union_with(CharSet::from_raw(&ranges));
+        }
+    }
+
+    /// Insert every codepoint of a string.
+    ///
+    /// This is `extend_unsorted` over the characters of the string, so the
+    /// codepoints are batched into ranges and merged into the set at once.
+    /// This is also available as `From<&str>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::set::CharSetBuf;
+    /// let mut alphabet = CharSetBuf::from("hello");
+    /// alphabet.insert_str("world");
+    /// assert_eq!(alphabet, CharSetBuf::from("dehlorw"));
+    /// assert_eq!(alphabet.range_count(), 6);
+    /// ```
+    pub fn insert_str(&mut self, s: &str) {
+        self.extend_unsorted(s.chars())
     }
 
     /// Remove a single codepoint from this set.
     ///
+    /// Returns whether the codepoint was present in the set.
+    ///
     /// # Examples
     ///
     /// ```
     /// # use {core::iter::FromIterator, mileage::{set::CharSetBuf, CharRange}};
     /// let mut set = CharSetBuf::from('a'..='c');
-    /// set.remove('b');
+    /// assert!(set.remove('b'));
+    /// assert!(!set.remove('b'));
     /// assert_eq!(set, CharSetBuf::from_iter(vec!['a', 'c']));
     /// ```
-    pub fn remove(&mut self, c: char) {
+    pub fn remove(&mut self, c: char) -> bool {
         if let Ok(idx) = self.search(c) {
             let this = &mut self.ranges[idx];
             if this.len() == 1 {
@@ -165,7 +526,7 @@ impl CharSetBuf {
             } else if this.low == c {
                 *this = CharRange::from((Bound::Excluded(c), Bound::Included(this.high)));
             } else if this.high == c {
-                *this = CharRange::from(this.low..=c);
+                *this = CharRange::from(this.low..c);
             } else {
                 let low = this.low;
                 *this = CharRange::from((Bound::Excluded(c), Bound::Included(this.high)));
@@ -174,6 +535,10 @@ impl CharSetBuf {
                     CharRange::from((Bound::Included(low), Bound::Excluded(c))),
                 );
             }
+            self.validate();
+            true
+        } else {
+            false
         }
     }
 
@@ -181,9 +546,11 @@ impl CharSetBuf {
     ///
     /// Functionally equivalent to removing each character separately, but done
     /// with a constant amount of work roughly equivalent to removing a single codepoint.
-    pub fn remove_range(&mut self, r: CharRange) {
+    ///
+    /// Returns how many codepoints were removed.
+    pub fn remove_range(&mut self, r: CharRange) -> usize {
         if r.is_empty() {
-            return;
+            return 0;
         }
 
         // inclusive index of lowest edited range
@@ -198,110 +565,647 @@ impl CharSetBuf {
             // no change, range not included
             debug_assert!(!self.contains(r.low));
             debug_assert!(!self.contains(r.high));
-        } else if low + 1 == high {
-            // one range to split
-            let split = &mut self.ranges[low];
-            if split.low == r.low && split.high == r.high {
-                // remove entire range
-                self.ranges.remove(low);
-            } else if split.low == r.low {
-                // shrink to top
-                debug_assert!(split.high > r.high);
-                *split = CharRange::from((Bound::Excluded(r.high), Bound::Included(split.high)));
-            } else if split.high == r.high {
-                // shrink to bottom
-                debug_assert!(r.low > split.low);
-                *split = CharRange::from((Bound::Included(split.low), Bound::Excluded(r.low)));
-            } else {
-                // split
-                debug_assert!(split.high > r.high);
-                debug_assert!(r.low > split.low);
-                let high_char = split.high;
-                *split = CharRange::from((Bound::Included(split.low), Bound::Excluded(r.low)));
-                self.ranges.insert(
-                    high, // insert after `split`
-                    CharRange::from((Bound::Excluded(r.high), Bound::Included(high_char))),
-                );
-            }
-        } else {
-            let left = &mut self.ranges[low];
-            *left = CharRange::from((Bound::Included(left.low), Bound::Excluded(r.low)));
-            let high = high - 1; // inclusive
-            let right = &mut self.ranges[high];
-            *right = CharRange::from((Bound::Excluded(r.high), Bound::Included(right.high)));
-            self.ranges
-                .drain((Bound::Excluded(low), Bound::Excluded(high)));
+            return 0;
         }
+
+        let removed = self.count_in_range(r);
+        // the parts of the edited ranges that stick out of the removed range
+        let below = CharRange::from((
+            Bound::Included(self.ranges[low].low),
+            Bound::Excluded(r.low),
+        ));
+        let above = CharRange::from((
+            Bound::Excluded(r.high),
+            Bound::Included(self.ranges[high - 1].high),
+        ));
+        self.splice(
+            low..high,
+            [below, above].iter().copied().filter(|r| !r.is_empty()),
+        );
+        self.validate();
+        removed
     }
-}
 
-impl Extend<CharRange> for CharSetBuf {
-    fn extend<T: IntoIterator<Item = CharRange>>(&mut self, iter: T) {
-        iter.into_iter().for_each(|r| self.insert_range(r));
+    /// Replace the ranges at `idx` with `with`, as `Vec::splice`.
+    fn splice(&mut self, idx: Range<usize>, with: impl Iterator<Item = CharRange>) {
+        #[cfg(not(feature = "inline-set"))]
+        self.ranges.splice(idx, with);
+        #[cfg(feature = "inline-set")]
+        {
+            self.ranges.drain(idx.clone());
+            self.ranges.insert_many(idx.start, with);
+        }
     }
-}
 
-impl Extend<char> for CharSetBuf {
-    fn extend<T: IntoIterator<Item = char>>(&mut self, iter: T) {
-        iter.into_iter().for_each(|c| self.insert(c));
+    /// Check that this set is still in canonical form after an edit,
+    /// if the `debug-validate` feature is enabled.
+    #[inline]
+    #[track_caller]
+    fn validate(&self) {
+        #[cfg(feature = "debug-validate")]
+        self.assert_valid();
     }
 }
 
-impl FromIterator<CharRange> for CharSetBuf {
-    fn from_iter<T: IntoIterator<Item = CharRange>>(iter: T) -> Self {
-        let iter = iter.into_iter();
-        let mut set = Self::with_capacity(iter.size_hint().0);
-        iter.for_each(|r| set.insert_range(r));
-        set
+impl CharSetBuf {
+    /// Keep only the compact ranges for which the predicate holds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSetBuf, CharRange};
+    /// let mut set: CharSetBuf = "abcxyz\u{E9}".chars().collect();
+    /// set.retain_ranges(|r| r.high.is_ascii());
+    /// assert_eq!(set.ranges().collect::<Vec<_>>(), [CharRange::from('a'..='c'), CharRange::from('x'..='z')]);
+    /// ```
+    pub fn retain_ranges(&mut self, mut f: impl FnMut(CharRange) -> bool) {
+        self.remove_where(|r| if f(r) { Some(r) } else { None })
     }
-}
 
-impl FromIterator<char> for CharSetBuf {
-    fn from_iter<T: IntoIterator<Item = char>>(iter: T) -> Self {
-        let mut set = Self::new();
-        iter.into_iter().for_each(|c| set.insert(c));
-        set
+    /// Trim or remove each compact range.
+    ///
+    /// The function is called with each range, and returns the part of it to keep,
+    /// or `None` to remove it entirely. The kept part is clamped to the original range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSetBuf, CharRange};
+    /// // Remove everything outside of the Latin-1 Supplement block
+    /// let mut set: CharSetBuf = vec!['a'..='z', '\u{C0}'..='\u{17F}', '\u{1F600}'..='\u{1F64F}'].into_iter().collect();
+    /// set.remove_where(|_| Some(CharRange::from('\u{80}'..='\u{FF}')));
+    /// assert_eq!(set, CharSetBuf::from('\u{C0}'..='\u{FF}'));
+    /// ```
+    pub fn remove_where(&mut self, mut f: impl FnMut(CharRange) -> Option<CharRange>) {
+        let mut kept = 0;
+        for idx in 0..self.ranges.len() {
+            let r = self.ranges[idx];
+            if let Some(keep) = f(r) {
+                let keep =
+                    CharRange::closed(cmp::max(r.low, keep.low), cmp::min(r.high, keep.high));
+                if !keep.is_empty() {
+                    self.ranges[kept] = keep;
+                    kept += 1;
+                }
+            }
+        }
+        self.ranges.truncate(kept);
+        self.validate();
+    }
+
+    /// Remove and return the lowest codepoint in this set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::set::CharSetBuf;
+    /// let mut set = CharSetBuf::from('a'..='b');
+    /// assert_eq!(set.pop_first(), Some('a'));
+    /// assert_eq!(set.pop_first(), Some('b'));
+    /// assert_eq!(set.pop_first(), None);
+    /// ```
+    pub fn pop_first(&mut self) -> Option<char> {
+        let first = self.ranges.first_mut()?;
+        let c = first.low;
+        if first.len() == 1 {
+            self.ranges.remove(0);
+        } else {
+            *first = CharRange::from((Bound::Excluded(c), Bound::Included(first.high)));
+        }
+        self.validate();
+        Some(c)
+    }
+
+    /// Remove and return the highest codepoint in this set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::set::CharSetBuf;
+    /// let mut set = CharSetBuf::from('a'..='b');
+    /// assert_eq!(set.pop_last(), Some('b'));
+    /// assert_eq!(set.pop_last(), Some('a'));
+    /// assert_eq!(set.pop_last(), None);
+    /// ```
+    pub fn pop_last(&mut self) -> Option<char> {
+        let last = self.ranges.last_mut()?;
+        let c = last.high;
+        if last.len() == 1 {
+            self.ranges.pop();
+        } else {
+            *last = CharRange::from((Bound::Included(last.low), Bound::Excluded(c)));
+        }
+        self.validate();
+        Some(c)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// A single edit to a `CharSetBuf`, for use with `CharSetBuf::apply`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum SetOp {
+    /// Insert a range of codepoints.
+    Insert(CharRange),
+    /// Remove a range of codepoints.
+    Remove(CharRange),
+}
 
-    #[test]
-    fn insert_range() {
-        #[rustfmt::skip]
-        let test_data = vec![
-            (vec!['a'..='c'], 'z'..='a' /* empty */, vec!['a'..='c']),
-            (vec![], 'm'..='m', vec!['m'..='m']),
-            (vec!['m'..='m'], 'l'..='l', vec!['l'..='m']),
-            (vec!['m'..='m'], 'n'..='n', vec!['m'..='n']),
-            (vec!['m'..='m'], 'k'..='k', vec!['k'..='k', 'm'..='m']),
-            (vec!['m'..='m'], 'o'..='o', vec!['m'..='m', 'o'..='o']),
-            (vec!['l'..='l', 'n'..='n'], 'm'..='m', vec!['l'..='n']),
-            (vec!['l'..='n'], 'm'..='m', vec!['l'..='n']),
-            (vec!['a'..='c', 'e'..='g'], 'd'..='d', vec!['a'..='g']),
-            (vec!['a'..='c', 'e'..='g'], 'b'..='f', vec!['a'..='g']),
-            (vec!['c'..='e', 'g'..='i'], 'a'..='f', vec!['a'..='i']),
-            (vec!['c'..='e', 'g'..='i'], 'f'..='k', vec!['c'..='k']),
-            (vec!['a'..='b', 'h'..='j'], 'd'..='f', vec!['a'..='b', 'd'..='f', 'h'..='j']),
-        ];
+impl CharSetBuf {
+    /// The union of many sets.
+    ///
+    /// This gathers the ranges of every set and merges them all at once,
+    /// rather than allocating and merging an intermediate set for each.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSetBuf, CharRange};
+    /// let lower = CharSetBuf::from('a'..='z');
+    /// let upper = CharSetBuf::from('A'..='Z');
+    /// let digit = CharSetBuf::from('0'..='9');
+    /// let alnum = CharSetBuf::union_all(vec![&*lower, &*upper, &*digit]);
+    /// assert_eq!(alnum.range_count(), 3);
+    /// assert!(alnum.contains_all("R2D2"));
+    /// ```
+    pub fn union_all<'a>(sets: impl IntoIterator<Item = &'a CharSet>) -> CharSetBuf {
+        let mut ranges: Ranges = sets.into_iter().flat_map(CharSet::ranges).collect();
+        ranges.sort_unstable_by_key(|r| r.low);
+        ranges.dedup_by(|next, prev| {
+            let touching = next.low as u32 <= prev.high as u32 + 1;
+            if touching {
+                prev.high = cmp::max(prev.high, next.high);
+            }
+            touching
+        });
+        CharSetBuf { ranges }
+    }
 
-        for (set, diff, result) in test_data {
-            let mut set = CharSetBuf {
-                ranges: set.into_iter().map(Into::into).collect(),
-            };
-            set.insert_range(CharRange::from(diff));
-            let result = CharSetBuf {
-                ranges: result.into_iter().map(Into::into).collect(),
+    /// Apply many edits in order, as if by `insert_range` and `remove_range`.
+    ///
+    /// Rather than editing the set once per op, this works out which op is
+    /// the last to touch each codepoint, then merges the result into the set
+    /// in one pass. This takes `O(n log n)` time for `n` ops, plus time linear
+    /// in the size of the set, rather than `O(n)` time per op.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::{CharSetBuf, SetOp}, CharRange};
+    /// let mut set = CharSetBuf::from('a'..='z');
+    /// set.apply(vec![
+    ///     SetOp::Remove(CharRange::from('d'..='w')),
+    ///     SetOp::Insert(CharRange::from('0'..='9')),
+    ///     SetOp::Insert(CharRange::from('m'..='n')),
+    ///     SetOp::Remove(CharRange::from('n'..='n')),
+    /// ]);
+    /// assert_eq!(set, CharSetBuf::from(&['0'..='9', 'a'..='c', 'm'..='m', 'x'..='z']));
+    /// ```
+    pub fn apply(&mut self, ops: impl IntoIterator<Item = SetOp>) {
+        // (position, op index, whether the op starts here), in `u32` space
+        let mut bounds = Vec::new();
+        let mut inserts = Vec::new();
+        for (i, op) in ops.into_iter().enumerate() {
+            let (r, insert) = match op {
+                SetOp::Insert(r) => (r, true),
+                SetOp::Remove(r) => (r, false),
             };
-            assert_eq!(set, result);
+            inserts.push(insert);
+            if !r.is_empty() {
+                bounds.push((r.low as u32, i, true));
+                bounds.push((r.high as u32 + 1, i, false));
+            }
         }
-    }
+        bounds.sort_unstable_by_key(|&(pos, ..)| pos);
 
-    #[test]
-    fn remove_range() {
-        #[rustfmt::skip]
+        // the codepoints touched by any op, and those where the last op is an insert
+        let mut touched = CharSetBuf::new();
+        let mut inserted = CharSetBuf::new();
+        let mut active = BinaryHeap::new();
+        let mut ended = vec![false; inserts.len()];
+        let mut idx = 0;
+        while idx < bounds.len() {
+            let low = bounds[idx].0;
+            while let Some(&(pos, i, start)) = bounds.get(idx) {
+                if pos != low {
+                    break;
+                }
+                if start {
+                    active.push(i);
+                } else {
+                    ended[i] = true;
+                }
+                idx += 1;
+            }
+            while active.peek().is_some_and(|&i| ended[i]) {
+                active.pop();
+            }
+            // an op is still active, so its end is still to come
+            if let Some(&i) = active.peek() {
+                let r = segment(low, bounds[idx].0);
+                touched.push_touching(r);
+                if inserts[i] {
+                    inserted.push_touching(r);
+                }
+            }
+        }
+
+        self.difference_with(&touched);
+        self.union_with(&inserted);
+
+        /// The codepoints of `low..high` in `u32` space, which may be empty.
+        fn segment(low: u32, high: u32) -> CharRange {
+            // the bounds can fall inside the surrogates
+            CharRange::closed(
+                char::from_u32(low).unwrap_or(AFTER_SURROGATE),
+                char::from_u32(high - 1).unwrap_or(BEFORE_SURROGATE),
+            )
+        }
+    }
+
+    /// Push a range after every existing range, merging it into the last
+    /// range if they touch.
+    fn push_touching(&mut self, r: CharRange) {
+        if r.is_empty() {
+            return;
+        }
+        match self.ranges.last_mut() {
+            Some(last) if r.low as u32 <= last.high as u32 + 1 => last.high = r.high,
+            _ => self.ranges.push(r),
+        }
+    }
+
+    /// Add all codepoints of `other` to this set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use {core::iter::FromIterator, mileage::{set::CharSetBuf, CharRange}};
+    /// let mut set = CharSetBuf::from('a'..='c');
+    /// set.union_with(&CharSetBuf::from_iter(vec!['d', 'z']));
+    /// assert_eq!(set, CharSetBuf::from_iter(vec![
+    ///     CharRange::from('a'..='d'),
+    ///     CharRange::from('z'..='z'),
+    /// ]));
+    /// ```
+    pub fn union_with(&mut self, other: &CharSet) {
+        self.combine_with(other, ops::union)
+    }
+
+    /// Remove all codepoints not in `other` from this set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::set::CharSetBuf;
+    /// let mut set = CharSetBuf::from('a'..='m');
+    /// set.intersect_with(&CharSetBuf::from('g'..='z'));
+    /// assert_eq!(set, CharSetBuf::from('g'..='m'));
+    /// ```
+    pub fn intersect_with(&mut self, other: &CharSet) {
+        self.combine_with(other, ops::intersection)
+    }
+
+    /// Remove all codepoints in `other` from this set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::set::CharSetBuf;
+    /// let mut set = CharSetBuf::from('a'..='m');
+    /// set.difference_with(&CharSetBuf::from('g'..='z'));
+    /// assert_eq!(set, CharSetBuf::from('a'..='f'));
+    /// ```
+    pub fn difference_with(&mut self, other: &CharSet) {
+        self.combine_with(other, ops::difference)
+    }
+
+    /// Remove all codepoints in `other` from this set.
+    ///
+    /// This is `difference_with`, but returns how many codepoints were removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::set::CharSetBuf;
+    /// let mut set = CharSetBuf::from('a'..='m');
+    /// assert_eq!(set.remove_set(&CharSetBuf::from('g'..='z')), 7);
+    /// assert_eq!(set.remove_set(&CharSetBuf::from('g'..='z')), 0);
+    /// ```
+    pub fn remove_set(&mut self, other: &CharSet) -> usize {
+        let before = self.len();
+        self.difference_with(other);
+        before - self.len()
+    }
+
+    /// Keep only the codepoints in exactly one of this set and `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use {core::iter::FromIterator, mileage::{set::CharSetBuf, CharRange}};
+    /// let mut set = CharSetBuf::from('a'..='m');
+    /// set.symmetric_difference_with(&CharSetBuf::from('g'..='z'));
+    /// assert_eq!(set, CharSetBuf::from_iter(vec![
+    ///     CharRange::from('a'..='f'),
+    ///     CharRange::from('n'..='z'),
+    /// ]));
+    /// ```
+    pub fn symmetric_difference_with(&mut self, other: &CharSet) {
+        self.combine_with(other, ops::symmetric_difference)
+    }
+
+    /// Replace this set with its complement.
+    ///
+    /// Each range is overwritten by the gap before it, so this needs room for
+    /// at most one more range and otherwise reuses the existing allocation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use {core::iter::FromIterator, mileage::{set::CharSetBuf, CharRange}};
+    /// let mut set = CharSetBuf::from('b'..='y');
+    /// set.invert();
+    /// assert_eq!(set, CharSetBuf::from_iter(vec![
+    ///     CharRange::from(..='a'),
+    ///     CharRange::from('z'..),
+    /// ]));
+    /// set.invert();
+    /// assert_eq!(set, CharSetBuf::from('b'..='y'));
+    /// ```
+    pub fn invert(&mut self) {
+        let mut low = Bound::Unbounded;
+        for r in self.ranges.iter_mut() {
+            let high = r.high;
+            *r = CharRange::from((low, Bound::Excluded(r.low)));
+            low = Bound::Excluded(high);
+        }
+        self.ranges.push(CharRange::from((low, Bound::Unbounded)));
+        // only the first and last gap can be empty, or one straddling the surrogates
+        self.ranges.retain(|r| !r.is_empty());
+        self.validate();
+    }
+
+    /// Replace this set with the result of `op` applied to it and `other`.
+    ///
+    /// The result is written after the existing ranges, which are then drained,
+    /// so the existing allocation is reused when it has room.
+    fn combine_with(&mut self, other: &CharSet, op: ops::Op) {
+        let len = self.ranges.len();
+        let mut sweep = ops::Sweep::new(op);
+        while let Some(r) = sweep.next(&self.ranges[..len], &other.ranges, op) {
+            self.ranges.push(r);
+        }
+        self.ranges.drain(..len);
+        self.validate();
+    }
+}
+
+impl CharSet {
+    /// The set of all codepoints not in this set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use {core::iter::FromIterator, mileage::{set::CharSetBuf, CharRange}};
+    /// let set = CharSetBuf::from('b'..='y');
+    /// assert_eq!(set.complement(), CharSetBuf::from_iter(vec![
+    ///     CharRange::from(..='a'),
+    ///     CharRange::from('z'..),
+    /// ]));
+    /// assert_eq!(!set.complement(), set);
+    /// ```
+    pub fn complement(&self) -> CharSetBuf {
+        let mut ranges = Ranges::with_capacity(self.ranges.len() + 1);
+        let mut sweep = ops::Sweep::new(ops::complement);
+        while let Some(r) = sweep.next(&self.ranges, &[], ops::complement) {
+            ranges.push(r);
+        }
+        CharSetBuf { ranges }
+    }
+}
+
+impl Not for &CharSet {
+    type Output = CharSetBuf;
+
+    fn not(self) -> CharSetBuf {
+        self.complement()
+    }
+}
+
+impl Not for &CharSetBuf {
+    type Output = CharSetBuf;
+
+    fn not(self) -> CharSetBuf {
+        self.complement()
+    }
+}
+
+impl Not for CharSetBuf {
+    type Output = CharSetBuf;
+
+    fn not(mut self) -> CharSetBuf {
+        self.invert();
+        self
+    }
+}
+
+/// A draining iterator over ranges of `char`.
+///
+/// Constructed via `CharSetBuf::drain` and `CharSetBuf::drain_range`.
+/// See `CharSetBuf` for more information.
+#[derive(Debug)]
+pub struct Drain<'a> {
+    #[cfg(not(feature = "inline-set"))]
+    raw: alloc::vec::Drain<'a, CharRange>,
+    #[cfg(feature = "inline-set")]
+    raw: smallvec::Drain<'a, [CharRange; INLINE_RANGES]>,
+}
+
+impl Iterator for Drain<'_> {
+    type Item = CharRange;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.raw.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.raw.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Drain<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.raw.next_back()
+    }
+}
+
+impl ExactSizeIterator for Drain<'_> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.raw.len()
+    }
+}
+
+impl FusedIterator for Drain<'_> {}
+
+impl Extend<CharRange> for CharSetBuf {
+    fn extend<T: IntoIterator<Item = CharRange>>(&mut self, iter: T) {
+        iter.into_iter().for_each(|r| {
+            self.insert_range(r);
+        });
+    }
+}
+
+/// The size hint above which extending with chars sorts them all at once
+/// rather than inserting them one by one.
+const BULK_EXTEND_THRESHOLD: usize = 64;
+
+impl Extend<char> for CharSetBuf {
+    fn extend<T: IntoIterator<Item = char>>(&mut self, iter: T) {
+        let iter = iter.into_iter();
+        if iter.size_hint().0 >= BULK_EXTEND_THRESHOLD {
+            self.extend_unsorted(iter);
+        } else {
+            iter.for_each(|c| {
+                self.insert(c);
+            });
+        }
+    }
+}
+
+/// Compares membership, so the sets are equal if they have exactly the same codepoints.
+impl PartialEq<CharSet> for CharSetBuf {
+    fn eq(&self, other: &CharSet) -> bool {
+        ops::same_members(&self.ranges, &other.ranges)
+    }
+}
+
+/// Compares membership, so the sets are equal if they have exactly the same codepoints.
+impl PartialEq<CharSetBuf> for CharSet {
+    fn eq(&self, other: &CharSetBuf) -> bool {
+        ops::same_members(&self.ranges, &other.ranges)
+    }
+}
+
+/// Compares membership, so a set equals a range with exactly the same codepoints.
+impl PartialEq<CharRange> for CharSetBuf {
+    fn eq(&self, other: &CharRange) -> bool {
+        **self == *other
+    }
+}
+
+impl<'a> Extend<&'a CharRange> for CharSetBuf {
+    fn extend<T: IntoIterator<Item = &'a CharRange>>(&mut self, iter: T) {
+        self.extend(iter.into_iter().copied())
+    }
+}
+
+impl<'a> Extend<&'a char> for CharSetBuf {
+    fn extend<T: IntoIterator<Item = &'a char>>(&mut self, iter: T) {
+        self.extend(iter.into_iter().copied())
+    }
+}
+
+impl fmt::Display for CharSetBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl FromStr for CharSetBuf {
+    type Err = ClassError;
+
+    /// Parse a character class, such as `[a-zA-Z0-9_]`.
+    ///
+    /// See `parse::parse_class` for the accepted syntax.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse::parse_class(s)
+    }
+}
+
+impl FromIterator<CharRange> for CharSetBuf {
+    fn from_iter<T: IntoIterator<Item = CharRange>>(iter: T) -> Self {
+        let iter = iter.into_iter();
+        let mut set = Self::with_capacity(iter.size_hint().0);
+        iter.for_each(|r| {
+            set.insert_range(r);
+        });
+        set
+    }
+}
+
+impl FromIterator<char> for CharSetBuf {
+    fn from_iter<T: IntoIterator<Item = char>>(iter: T) -> Self {
+        let mut set = Self::new();
+        set.extend_unsorted(iter);
+        set
+    }
+}
+
+impl<'a> FromIterator<&'a CharRange> for CharSetBuf {
+    fn from_iter<T: IntoIterator<Item = &'a CharRange>>(iter: T) -> Self {
+        iter.into_iter().copied().collect()
+    }
+}
+
+impl FromIterator<RangeInclusive<char>> for CharSetBuf {
+    fn from_iter<T: IntoIterator<Item = RangeInclusive<char>>>(iter: T) -> Self {
+        iter.into_iter().map(CharRange::from).collect()
+    }
+}
+
+impl<'a> FromIterator<&'a char> for CharSetBuf {
+    fn from_iter<T: IntoIterator<Item = &'a char>>(iter: T) -> Self {
+        iter.into_iter().copied().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        alloc::string::{String, ToString},
+        alloc::vec,
+    };
+
+    #[test]
+    fn insert_range() {
+        #[rustfmt::skip]
+        let test_data = vec![
+            (vec!['a'..='c'], 'z'..='a' /* empty */, vec!['a'..='c']),
+            (vec![], 'm'..='m', vec!['m'..='m']),
+            (vec!['m'..='m'], 'l'..='l', vec!['l'..='m']),
+            (vec!['m'..='m'], 'n'..='n', vec!['m'..='n']),
+            (vec!['m'..='m'], 'k'..='k', vec!['k'..='k', 'm'..='m']),
+            (vec!['m'..='m'], 'o'..='o', vec!['m'..='m', 'o'..='o']),
+            (vec!['l'..='l', 'n'..='n'], 'm'..='m', vec!['l'..='n']),
+            (vec!['l'..='n'], 'm'..='m', vec!['l'..='n']),
+            (vec!['a'..='c', 'e'..='g'], 'd'..='d', vec!['a'..='g']),
+            (vec!['a'..='c', 'e'..='g'], 'b'..='f', vec!['a'..='g']),
+            (vec!['c'..='e', 'g'..='i'], 'a'..='f', vec!['a'..='i']),
+            (vec!['c'..='e', 'g'..='i'], 'f'..='k', vec!['c'..='k']),
+            (vec!['a'..='b', 'h'..='j'], 'd'..='f', vec!['a'..='b', 'd'..='f', 'h'..='j']),
+        ];
+
+        for (set, diff, result) in test_data {
+            let mut set = CharSetBuf {
+                ranges: set.into_iter().map(Into::into).collect(),
+            };
+            let before = set.len();
+            let inserted = set.insert_range(CharRange::from(diff));
+            let result = CharSetBuf {
+                ranges: result.into_iter().map(Into::into).collect(),
+            };
+            assert_eq!(set, result);
+            assert_eq!(inserted, result.len() - before);
+        }
+    }
+
+    #[test]
+    fn remove_range() {
+        #[rustfmt::skip]
         let test_data = vec![
             (vec!['a'..='c'], 'z'..='a' /* empty */, vec!['a'..='c']),
             (vec![], 'a'..='a', vec![]),
@@ -310,17 +1214,473 @@ mod tests {
             (vec!['a'..='c'], 'b'..='b', vec!['a'..='a', 'c'..='c']),
             (vec!['a'..='c'], 'c'..='c', vec!['a'..='b']),
             (vec!['a'..='b', 'd'..='e'], 'b'..='d', vec!['a'..='a', 'e'..='e']),
+            (vec!['b'..='d'], 'a'..='c', vec!['d'..='d']),
+            (vec!['b'..='d'], 'c'..='e', vec!['b'..='b']),
+            (vec!['b'..='d'], 'a'..='e', vec![]),
+            (vec!['b'..='d', 'f'..='h'], 'a'..='g', vec!['h'..='h']),
+            (vec!['b'..='d', 'f'..='h'], 'a'..='z', vec![]),
+            (vec!['b'..='d', 'f'..='h', 'j'..='l'], 'e'..='i', vec!['b'..='d', 'j'..='l']),
         ];
 
         for (set, diff, result) in test_data {
             let mut set = CharSetBuf {
                 ranges: set.into_iter().map(Into::into).collect(),
             };
-            set.remove_range(CharRange::from(diff));
+            let mut bulk = set.clone();
+            let before = set.len();
+            let removed = set.remove_range(CharRange::from(diff.clone()));
+            let result = CharSetBuf {
+                ranges: result.into_iter().map(Into::into).collect(),
+            };
+            assert_eq!(set, result);
+            assert_eq!(removed, before - result.len());
+            assert_eq!(bulk.remove_set(&CharSetBuf::from(diff)), removed);
+            assert_eq!(bulk, result);
+        }
+    }
+
+    #[test]
+    fn set_operations() {
+        #[rustfmt::skip]
+        let test_data = vec![
+            (vec![], vec![], [vec![], vec![], vec![], vec![]]),
+            (vec!['a'..='c'], vec![], [vec!['a'..='c'], vec![], vec!['a'..='c'], vec!['a'..='c']]),
+            (vec![], vec!['a'..='c'], [vec!['a'..='c'], vec![], vec![], vec!['a'..='c']]),
+            (vec!['a'..='c'], vec!['d'..='f'], [vec!['a'..='f'], vec![], vec!['a'..='c'], vec!['a'..='f']]),
+            (vec!['a'..='m'], vec!['g'..='z'], [vec!['a'..='z'], vec!['g'..='m'], vec!['a'..='f'], vec!['a'..='f', 'n'..='z']]),
+            (vec!['a'..='z'], vec!['g'..='m'], [vec!['a'..='z'], vec!['g'..='m'], vec!['a'..='f', 'n'..='z'], vec!['a'..='f', 'n'..='z']]),
+            (vec!['a'..='b', 'y'..='z'], vec!['b'..='y'], [vec!['a'..='z'], vec!['b'..='b', 'y'..='y'], vec!['a'..='a', 'z'..='z'], vec!['a'..='a', 'c'..='x', 'z'..='z']]),
+            (vec!['\0'..=char::MAX], vec!['\0'..='\u{D7FF}'], [vec!['\0'..=char::MAX], vec!['\0'..='\u{D7FF}'], vec!['\u{E000}'..=char::MAX], vec!['\u{E000}'..=char::MAX]]),
+        ];
+
+        for (lhs, rhs, [union, intersection, difference, symmetric_difference]) in test_data {
+            let lhs = CharSetBuf {
+                ranges: lhs.into_iter().map(Into::into).collect(),
+            };
+            let rhs = CharSetBuf {
+                ranges: rhs.into_iter().map(Into::into).collect(),
+            };
+            let expected = |ranges: Vec<RangeInclusive<char>>| CharSetBuf {
+                ranges: ranges.into_iter().map(Into::into).collect(),
+            };
+
+            let mut set = lhs.clone();
+            set.union_with(&rhs);
+            assert_eq!(set, expected(union));
+            let mut set = lhs.clone();
+            set.intersect_with(&rhs);
+            assert_eq!(set, expected(intersection));
+            let mut set = lhs.clone();
+            set.difference_with(&rhs);
+            assert_eq!(set, expected(difference));
+            let mut set = lhs.clone();
+            set.symmetric_difference_with(&rhs);
+            assert_eq!(set, expected(symmetric_difference));
+        }
+    }
+
+    #[test]
+    fn insert() {
+        #[rustfmt::skip]
+        let test_data = vec![
+            (vec![], 'm', vec!['m'..='m']),
+            (vec!['m'..='m'], 'm', vec!['m'..='m']),
+            (vec!['m'..='m'], 'l', vec!['l'..='m']),
+            (vec!['m'..='m'], 'n', vec!['m'..='n']),
+            (vec!['m'..='m'], 'k', vec!['k'..='k', 'm'..='m']),
+            (vec!['m'..='m'], 'o', vec!['m'..='m', 'o'..='o']),
+            (vec!['l'..='l', 'n'..='n'], 'm', vec!['l'..='n']),
+            (vec!['k'..='k', 'n'..='n'], 'm', vec!['k'..='k', 'm'..='n']),
+            (vec!['l'..='l', 'o'..='o'], 'm', vec!['l'..='m', 'o'..='o']),
+        ];
+
+        for (set, c, result) in test_data {
+            let mut set = CharSetBuf {
+                ranges: set.into_iter().map(Into::into).collect(),
+            };
+            let inserted = !set.contains(c);
+            assert_eq!(set.insert(c), inserted);
             let result = CharSetBuf {
                 ranges: result.into_iter().map(Into::into).collect(),
             };
             assert_eq!(set, result);
         }
     }
+
+    #[test]
+    fn drain_range() {
+        #[rustfmt::skip]
+        let test_data = vec![
+            (vec!['a'..='c'], 'z'..='a' /* empty */, vec![], vec!['a'..='c']),
+            (vec![], 'a'..='z', vec![], vec![]),
+            (vec!['a'..='c'], 'a'..='c', vec!['a'..='c'], vec![]),
+            (vec!['a'..='c'], 'b'..='b', vec!['b'..='b'], vec!['a'..='a', 'c'..='c']),
+            (vec!['b'..='d', 'f'..='h'], 'a'..='g', vec!['b'..='d', 'f'..='g'], vec!['h'..='h']),
+            (vec!['b'..='d', 'f'..='h'], 'c'..='z', vec!['c'..='d', 'f'..='h'], vec!['b'..='b']),
+            (vec!['b'..='d', 'j'..='l'], 'e'..='i', vec![], vec!['b'..='d', 'j'..='l']),
+        ];
+
+        for (set, diff, drained, result) in test_data {
+            let mut set = CharSetBuf {
+                ranges: set.into_iter().map(Into::into).collect(),
+            };
+            let drained: Vec<CharRange> = drained.into_iter().map(Into::into).collect();
+            assert_eq!(
+                set.drain_range(CharRange::from(diff)).collect::<Vec<_>>(),
+                drained
+            );
+            let result = CharSetBuf {
+                ranges: result.into_iter().map(Into::into).collect(),
+            };
+            assert_eq!(set, result);
+        }
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut set = CharSetBuf::from('a'..='c');
+        assert!(set.remove('c'));
+        assert!(!set.contains('c'));
+        assert!(!set.remove('c'));
+        assert!(set.insert('c'));
+        assert!(!set.insert('c'));
+        assert_eq!(set, CharSetBuf::from('a'..='c'));
+    }
+
+    #[test]
+    fn extend_unsorted() {
+        // A deterministic scattering of codepoints, with plenty of repeats and neighbors
+        let mut state = 1u32;
+        let chars: Vec<char> = (0..4096)
+            .filter_map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                char::from_u32((state >> 8) % 0x1_0000)
+            })
+            .collect();
+
+        let mut expected = CharSetBuf::from('\u{D000}'..='\u{E100}');
+        for &c in &chars {
+            expected.insert(c);
+        }
+
+        let mut set = CharSetBuf::from('\u{D000}'..='\u{E100}');
+        set.extend_unsorted(chars.iter().copied());
+        assert_eq!(set, expected);
+
+        let mut set = CharSetBuf::from('\u{D000}'..='\u{E100}');
+        set.extend(chars.iter().copied());
+        assert_eq!(set, expected);
+
+        let mut expected = CharSetBuf::new();
+        for &c in &chars {
+            expected.insert(c);
+        }
+        assert_eq!(chars.iter().copied().collect::<CharSetBuf>(), expected);
+        assert_eq!(
+            CharSetBuf::from(&*chars.iter().collect::<String>()),
+            expected
+        );
+    }
+
+    #[test]
+    fn borrowed_collect() {
+        let chars = ['c', 'a', 'b', 'x'];
+        let ranges = [CharRange::from('a'..='c'), CharRange::from('x'..='x')];
+        let expected = CharSetBuf {
+            ranges: Ranges::from(&ranges[..]),
+        };
+
+        assert_eq!(chars.iter().collect::<CharSetBuf>(), expected);
+        assert_eq!(ranges.iter().collect::<CharSetBuf>(), expected);
+        assert_eq!(
+            vec!['x'..='x', 'a'..='c']
+                .into_iter()
+                .collect::<CharSetBuf>(),
+            expected,
+        );
+
+        let mut set = CharSetBuf::new();
+        set.extend(&chars[..2]);
+        set.extend(&ranges);
+        assert_eq!(set, expected);
+    }
+
+    #[test]
+    fn cross_type_eq() {
+        let ranges = [CharRange::from('a'..='c'), CharRange::from('x'..='z')];
+        let set = CharSet::from_raw(&ranges);
+        let mut buf = CharSetBuf::from('a'..='c');
+        assert!(buf != *set);
+        assert!(*set != buf);
+        buf.insert_range(CharRange::from('x'..='z'));
+        assert!(buf == *set);
+        assert!(*set == buf);
+
+        let all = CharRange::from(..);
+        let mut buf = CharSetBuf::from('\0'..='\u{D7FF}');
+        assert!(buf != all);
+        buf.insert_range(CharRange::from('\u{E000}'..=char::MAX));
+        assert!(buf == all);
+        assert!(buf == *CharSetBuf::from(all));
+    }
+
+    #[test]
+    fn from_std_ranges() {
+        let table = [
+            'x'..='z',
+            '\u{E000}'..='\u{E00F}',
+            'a'..='c',
+            'b'..='d',
+            '\0'..='\u{D7FF}',
+        ];
+        let set = CharSetBuf::from(&table);
+        assert_eq!(set, CharSetBuf::from(&table[..]));
+        assert!(
+            *set == [
+                CharRange::closed('\0', '\u{D7FF}'),
+                CharRange::closed('\u{E000}', '\u{E00F}')
+            ][..]
+        );
+
+        assert!(CharSetBuf::from('b'..'b').is_empty());
+        assert!(CharSetBuf::from(CharRange::empty()).is_empty());
+        assert_eq!(CharSetBuf::from(&[][..]), CharSetBuf::new());
+    }
+
+    #[test]
+    fn cow() {
+        use alloc::borrow::Cow;
+
+        fn with_digits(set: &CharSet) -> Cow<'_, CharSet> {
+            if set.contains_all("0123456789") {
+                Cow::Borrowed(set)
+            } else {
+                let mut set = set.to_owned();
+                set.insert_range(CharRange::from('0'..='9'));
+                Cow::Owned(set)
+            }
+        }
+
+        let ranges = [CharRange::from('0'..='9')];
+        let digits = CharSet::from_raw(&ranges);
+        assert!(matches!(with_digits(digits), Cow::Borrowed(_)));
+        let with = with_digits(CharSet::empty());
+        assert!(matches!(with, Cow::Owned(_)));
+        assert_eq!(&*with, digits);
+        assert_eq!(with.into_owned(), CharSetBuf::from('0'..='9'));
+    }
+
+    #[test]
+    fn union_all() {
+        #[rustfmt::skip]
+        let test_data = vec![
+            vec![],
+            vec![vec!['a'..='c']],
+            vec![vec!['a'..='c'], vec![]],
+            vec![vec!['a'..='c', 'x'..='z'], vec!['d'..='f'], vec!['b'..='y']],
+            vec![vec!['a'..='a', 'c'..='c'], vec!['e'..='e'], vec!['b'..='b', 'g'..='g']],
+            vec![vec!['\0'..='\u{D7FF}'], vec!['\u{E000}'..=char::MAX], vec!['a'..='z']],
+        ];
+
+        for sets in test_data {
+            let sets: Vec<CharSetBuf> = sets.into_iter().map(CharSetBuf::from_iter).collect();
+            let mut expected = CharSetBuf::new();
+            for set in &sets {
+                expected.union_with(set);
+            }
+            let set = CharSetBuf::union_all(sets.iter().map(|set| &**set));
+            assert_eq!(set, expected);
+        }
+    }
+
+    #[test]
+    fn apply() {
+        use SetOp::{Insert, Remove};
+
+        fn check(initial: &CharSetBuf, ops: &[SetOp]) {
+            let mut expected = initial.clone();
+            for &op in ops {
+                match op {
+                    Insert(r) => expected.insert_range(r),
+                    Remove(r) => expected.remove_range(r),
+                };
+            }
+            let mut set = initial.clone();
+            set.apply(ops.iter().copied());
+            assert!(set == *expected, "{:?} {:?}", initial, ops);
+        }
+
+        let r = |low, high| CharRange::closed(low, high);
+        #[rustfmt::skip]
+        let test_data = vec![
+            (vec![], vec![]),
+            (vec!['a'..='z'], vec![]),
+            (vec!['a'..='z'], vec![Remove(r('z', 'a'))]),
+            (vec![], vec![Insert(r('a', 'c')), Insert(r('b', 'd'))]),
+            (vec!['a'..='z'], vec![Remove(r('c', 'x')), Insert(r('m', 'm'))]),
+            (vec!['a'..='z'], vec![Insert(r('m', 'm')), Remove(r('c', 'x'))]),
+            (vec!['a'..='c', 'x'..='z'], vec![Remove(r('b', 'y')), Insert(r('a', 'a')), Insert(r('d', 'w'))]),
+            (vec![], vec![Insert(r('\u{D7F0}', '\u{D7FF}')), Insert(r('\u{E000}', '\u{E00F}')), Remove(r('\u{D7FF}', '\u{E000}'))]),
+            (vec!['\0'..=char::MAX], vec![Remove(r('\u{D7FF}', '\u{E000}')), Insert(r('\u{E000}', char::MAX))]),
+        ];
+        for (initial, ops) in test_data {
+            check(&initial.into_iter().collect(), &ops);
+        }
+
+        // a long pseudorandom script, with a simple LCG to avoid a dependency
+        let mut state = 0x2545_F491_u32;
+        let mut next = |n: u32| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 8) % n
+        };
+        let alphabet = [
+            'a',
+            'f',
+            'k',
+            'p',
+            'u',
+            'z',
+            '\u{D7FF}',
+            '\u{E000}',
+            char::MAX,
+        ];
+        let ops: Vec<SetOp> = (0..500)
+            .map(|_| {
+                let (a, b) = (alphabet[next(9) as usize], alphabet[next(9) as usize]);
+                let range = CharRange::closed(cmp::min(a, b), cmp::max(a, b));
+                if next(2) == 0 {
+                    Insert(range)
+                } else {
+                    Remove(range)
+                }
+            })
+            .collect();
+        for n in [1, 2, 10, 100, 500] {
+            check(&CharSetBuf::from('c'..='x'), &ops[..n]);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "debug-validate")]
+    #[should_panic(expected = "overlaps")]
+    fn validate_unchecked() {
+        CharSetBuf::from_sorted_ranges_unchecked(vec![
+            CharRange::from('a'..='c'),
+            CharRange::from('d'..='f'),
+        ]);
+    }
+
+    #[test]
+    #[cfg(feature = "inline-set")]
+    fn inline_storage() {
+        let mut set = CharSetBuf::new();
+        for (i, c) in "acegikm".chars().enumerate() {
+            set.insert(c);
+            assert_eq!(set.ranges.spilled(), i >= INLINE_RANGES);
+        }
+        set.remove_range(CharRange::from('b'..='j'));
+        set.shrink_to_fit();
+        assert!(!set.ranges.spilled());
+        assert_eq!(set.memory_footprint(), mem::size_of::<CharSetBuf>());
+    }
+
+    #[test]
+    fn remove_where() {
+        #[rustfmt::skip]
+        let test_data = vec![
+            (vec![], None, vec![]),
+            (vec!['a'..='c', 'x'..='z'], None, vec![]),
+            (vec!['a'..='c', 'x'..='z'], Some('\0'..=char::MAX), vec!['a'..='c', 'x'..='z']),
+            (vec!['a'..='c', 'x'..='z'], Some('b'..='y'), vec!['b'..='c', 'x'..='y']),
+            (vec!['a'..='c', 'x'..='z'], Some('d'..='w'), vec![]),
+            (vec!['a'..='c', 'x'..='z'], Some('c'..='a'), vec![]),
+        ];
+
+        for (set, keep, result) in test_data {
+            let mut set: CharSetBuf = set.into_iter().collect();
+            set.remove_where(|_| keep.clone().map(CharRange::from));
+            assert_eq!(set, result.into_iter().collect::<CharSetBuf>());
+        }
+    }
+
+    #[test]
+    fn into_ranges_and_box() {
+        let ranges = vec![CharRange::from('a'..='c'), CharRange::from('x'..='z')];
+        let set = CharSetBuf::from_sorted_ranges_unchecked(ranges.clone());
+        assert_eq!(set.clone().into_ranges(), ranges);
+
+        let boxed = set.clone().into_boxed_set();
+        assert_eq!(boxed.ranges().collect::<Vec<_>>(), ranges);
+        assert_eq!(CharSetBuf::from(boxed), set);
+        assert_eq!(
+            CharSetBuf::from(CharSetBuf::new().into_boxed_set()),
+            CharSetBuf::new()
+        );
+    }
+
+    #[test]
+    fn complement() {
+        #[rustfmt::skip]
+        let test_data = vec![
+            (vec![], vec!['\0'..=char::MAX]),
+            (vec!['\0'..=char::MAX], vec![]),
+            (vec!['\0'..='a'], vec!['b'..=char::MAX]),
+            (vec!['a'..=char::MAX], vec!['\0'..='`']),
+            (vec!['\0'..='\u{D7FF}'], vec!['\u{E000}'..=char::MAX]),
+            (vec!['\0'..='\u{D7FF}', '\u{E000}'..=char::MAX], vec![]),
+            (vec!['b'..='c', 'e'..='f'], vec!['\0'..='a', 'd'..='d', 'g'..=char::MAX]),
+            (vec!['\u{D7FE}'..='\u{E001}'], vec!['\0'..='\u{D7FD}', '\u{E002}'..=char::MAX]),
+            (vec!['a'..='\u{D7FF}', '\u{E000}'..='z'], vec!['\0'..='`', '{'..=char::MAX]),
+        ];
+
+        for (set, result) in test_data {
+            let set = CharSetBuf {
+                ranges: set.into_iter().map(Into::into).collect(),
+            };
+            let result = CharSetBuf {
+                ranges: result.into_iter().map(Into::into).collect(),
+            };
+            assert_eq!(set.complement(), result);
+            let mut inverted = set.clone();
+            inverted.invert();
+            assert_eq!(inverted, result);
+        }
+    }
+
+    #[test]
+    fn from_fn() {
+        let predicates: [fn(char) -> bool; 4] = [
+            |_| false,
+            |c| c.is_ascii_digit() || c == char::MAX,
+            char::is_whitespace,
+            char::is_alphabetic,
+        ];
+
+        for f in &predicates {
+            let expected: CharSetBuf = CharRange::from(..).iter().filter(|&c| f(c)).collect();
+            assert_eq!(CharSetBuf::from_fn(f), expected);
+        }
+    }
+
+    #[test]
+    fn text_round_trip() {
+        #[rustfmt::skip]
+        let test_data = vec![
+            vec![],
+            vec!['\0'..=char::MAX],
+            vec!['-'..='-', '['..='^', 'a'..='z'],
+            vec!['\0'..='\u{1F}', '\u{7F}'..='\u{9F}'],
+            vec!['\u{D7FF}'..='\u{E000}', '\u{1F600}'..='\u{1F64F}'],
+        ];
+
+        for set in test_data {
+            let set = CharSetBuf {
+                ranges: set.into_iter().map(Into::into).collect(),
+            };
+            let mut ucd = String::new();
+            set.write_ucd(&mut ucd).unwrap();
+            assert!(parse::parse_ucd(&ucd).unwrap() == *set);
+            assert_eq!(set.to_string().parse(), Ok(set));
+        }
+    }
 }
@@ -1,15 +1,25 @@
+#[cfg(feature = "owned-set")]
+use crate::set::CharSetBuf;
 use {
-    crate::{set::CharSet, CharRange},
+    crate::{
+        set::{CharSet, Chars},
+        CharRange,
+    },
+    core::ops::Bound,
     rayon::{
-        iter::plumbing::{Consumer, UnindexedConsumer},
+        iter::plumbing::{bridge, Consumer, Producer, ProducerCallback, UnindexedConsumer},
         prelude::*,
     },
 };
 
 /// A parallel iterator over a set of unicode code points.
+///
+/// The codepoints are split evenly between threads, no matter how they are
+/// distributed between the compact ranges of the set.
 #[derive(Clone, Debug)]
 pub struct Iter<'a> {
-    raw: rayon::iter::Flatten<rayon::slice::Iter<'a, CharRange>>,
+    ranges: &'a [CharRange],
+    len: usize,
 }
 
 impl ParallelIterator for Iter<'_> {
@@ -19,10 +29,31 @@ impl ParallelIterator for Iter<'_> {
     where
         C: UnindexedConsumer<Self::Item>,
     {
-        self.raw.drive_unindexed(consumer)
+        bridge(self, consumer)
     }
 
-    // Flatten doesn't override any default provided methods
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+impl IndexedParallelIterator for Iter<'_> {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn drive<C: Consumer<Self::Item>>(self, consumer: C) -> C::Result {
+        bridge(self, consumer)
+    }
+
+    fn with_producer<CB: ProducerCallback<Self::Item>>(self, callback: CB) -> CB::Output {
+        callback.callback(CharsProducer {
+            head: CharRange::empty(),
+            body: self.ranges,
+            tail: CharRange::empty(),
+            len: self.len,
+        })
+    }
 }
 
 impl<'a> IntoParallelIterator for &'a CharSet {
@@ -31,7 +62,268 @@ impl<'a> IntoParallelIterator for &'a CharSet {
 
     fn into_par_iter(self) -> Iter<'a> {
         Iter {
-            raw: self.ranges.par_iter().flatten(),
+            ranges: &self.ranges,
+            len: self.len(),
+        }
+    }
+}
+
+/// Produces the codepoints of `head`, then `body`, then `tail`.
+struct CharsProducer<'a> {
+    head: CharRange,
+    body: &'a [CharRange],
+    tail: CharRange,
+    len: usize,
+}
+
+/// Split a range into its first `n` codepoints and the rest.
+fn split_range(r: CharRange, n: usize) -> (CharRange, CharRange) {
+    match r.iter().nth(n) {
+        Some(mid) => (
+            CharRange::from((Bound::Included(r.low), Bound::Excluded(mid))),
+            CharRange::from(mid..=r.high),
+        ),
+        None => (r, CharRange::empty()),
+    }
+}
+
+impl<'a> Producer for CharsProducer<'a> {
+    type Item = char;
+    type IntoIter = Chars<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Chars::from_parts(self.head, self.body, self.tail, self.len)
+    }
+
+    fn split_at(self, index: usize) -> (Self, Self) {
+        debug_assert!(index <= self.len);
+        let (left_len, right_len) = (index, self.len - index);
+
+        let mut n = index;
+        if n <= self.head.len() {
+            let (left, right) = split_range(self.head, n);
+            return (
+                CharsProducer {
+                    head: left,
+                    body: &[],
+                    tail: CharRange::empty(),
+                    len: left_len,
+                },
+                CharsProducer {
+                    head: right,
+                    body: self.body,
+                    tail: self.tail,
+                    len: right_len,
+                },
+            );
+        }
+        n -= self.head.len();
+
+        for (i, &r) in self.body.iter().enumerate() {
+            if n <= r.len() {
+                let (left, right) = split_range(r, n);
+                return (
+                    CharsProducer {
+                        head: self.head,
+                        body: &self.body[..i],
+                        tail: left,
+                        len: left_len,
+                    },
+                    CharsProducer {
+                        head: right,
+                        body: &self.body[i + 1..],
+                        tail: self.tail,
+                        len: right_len,
+                    },
+                );
+            }
+            n -= r.len();
+        }
+
+        let (left, right) = split_range(self.tail, n);
+        (
+            CharsProducer {
+                head: self.head,
+                body: self.body,
+                tail: left,
+                len: left_len,
+            },
+            CharsProducer {
+                head: right,
+                body: &[],
+                tail: CharRange::empty(),
+                len: right_len,
+            },
+        )
+    }
+}
+
+#[cfg(feature = "owned-set")]
+impl<'a> IntoParallelIterator for &'a CharSetBuf {
+    type Iter = Iter<'a>;
+    type Item = char;
+
+    fn into_par_iter(self) -> Iter<'a> {
+        (**self).into_par_iter()
+    }
+}
+
+/// A parallel iterator over an owned set of unicode code points.
+#[cfg(feature = "owned-set")]
+#[derive(Clone, Debug)]
+pub struct IntoIter {
+    raw: rayon::iter::Flatten<rayon::vec::IntoIter<CharRange>>,
+}
+
+#[cfg(feature = "owned-set")]
+impl ParallelIterator for IntoIter {
+    type Item = char;
+
+    fn drive_unindexed<C>(self, consumer: C) -> <C as Consumer<Self::Item>>::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        self.raw.drive_unindexed(consumer)
+    }
+}
+
+#[cfg(feature = "owned-set")]
+impl IntoParallelIterator for CharSetBuf {
+    type Iter = IntoIter;
+    type Item = char;
+
+    fn into_par_iter(self) -> IntoIter {
+        IntoIter {
+            raw: self.into_ranges().into_par_iter().flatten(),
+        }
+    }
+}
+
+/// A parallel iterator over the compact ranges of a set.
+///
+/// Constructed via `CharSet::par_ranges`. See `CharSet` for more information.
+#[derive(Clone, Debug)]
+pub struct RangeIter<'a> {
+    raw: rayon::iter::Copied<rayon::slice::Iter<'a, CharRange>>,
+}
+
+impl CharSet {
+    /// Iterate the compact ranges of this set in parallel.
+    ///
+    /// Unlike `par_iter`, this keeps the ranges whole, and is indexed,
+    /// so the ranges can be processed in parallel and collected in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use {mileage::{set::CharSet, CharRange}, rayon::prelude::*};
+    /// let ranges = [CharRange::closed('0', '9'), CharRange::closed('a', 'f')];
+    /// let set = CharSet::from_raw(&ranges);
+    /// let table: Vec<String> = set
+    ///     .par_ranges()
+    ///     .map(|r| format!("{:?}..={:?}", r.low, r.high))
+    ///     .collect();
+    /// assert_eq!(table, ["'0'..='9'", "'a'..='f'"]);
+    /// ```
+    pub fn par_ranges(&self) -> RangeIter<'_> {
+        RangeIter {
+            raw: self.ranges.par_iter().copied(),
+        }
+    }
+}
+
+impl ParallelIterator for RangeIter<'_> {
+    type Item = CharRange;
+
+    fn drive_unindexed<C>(self, consumer: C) -> <C as Consumer<Self::Item>>::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        self.raw.drive_unindexed(consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        self.raw.opt_len()
+    }
+}
+
+impl IndexedParallelIterator for RangeIter<'_> {
+    fn len(&self) -> usize {
+        self.raw.len()
+    }
+
+    fn drive<C: Consumer<Self::Item>>(self, consumer: C) -> C::Result {
+        self.raw.drive(consumer)
+    }
+
+    fn with_producer<CB: ProducerCallback<Self::Item>>(self, callback: CB) -> CB::Output {
+        self.raw.with_producer(callback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, alloc::vec::Vec};
+
+    #[test]
+    fn ranges_agree() {
+        let ranges = [
+            CharRange::closed('a', 'c'),
+            CharRange::closed('x', 'z'),
+            CharRange::closed('\u{D7FE}', '\u{E001}'),
+        ];
+        let set = CharSet::from_raw(&ranges);
+        assert_eq!(set.par_ranges().len(), ranges.len());
+        assert_eq!(set.par_ranges().collect::<Vec<_>>(), ranges);
+        assert_eq!(
+            set.par_iter().collect::<Vec<_>>(),
+            set.chars().collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn indexed_agrees() {
+        let ranges = [
+            CharRange::closed('a', 'c'),
+            CharRange::closed('x', 'z'),
+            CharRange::closed('\u{D7FE}', '\u{E001}'),
+            CharRange::closed('\u{10000}', '\u{1FFFF}'),
+        ];
+        let set = CharSet::from_raw(&ranges);
+        let chars = set.chars().collect::<Vec<_>>();
+
+        assert_eq!(set.par_iter().len(), chars.len());
+        let mut collected = Vec::new();
+        set.par_iter().collect_into_vec(&mut collected);
+        assert_eq!(collected, chars);
+        assert_eq!(
+            set.par_iter().rev().collect::<Vec<_>>(),
+            chars.iter().rev().copied().collect::<Vec<_>>()
+        );
+        for &min_len in &[1, 2, 3, 5, 7, 1000] {
+            assert_eq!(
+                set.par_iter().with_min_len(min_len).collect::<Vec<_>>(),
+                chars
+            );
+            assert_eq!(
+                set.par_iter().with_max_len(min_len).collect::<Vec<_>>(),
+                chars
+            );
         }
+        assert!(set
+            .par_iter()
+            .zip(chars.par_iter())
+            .all(|(c, &expected)| c == expected));
+    }
+
+    #[test]
+    #[cfg(feature = "owned-set")]
+    fn owned_agrees() {
+        let set: CharSetBuf = "the quick brown fox jumps over the lazy dog"
+            .chars()
+            .collect();
+        let chars = set.chars().collect::<Vec<_>>();
+        assert_eq!((&set).into_par_iter().collect::<Vec<_>>(), chars);
+        assert_eq!(set.into_par_iter().collect::<Vec<_>>(), chars);
     }
 }
@@ -0,0 +1,100 @@
+use {
+    crate::set::CharSet,
+    rand::{distributions::Distribution, Rng},
+};
+
+impl CharSet {
+    /// Choose a codepoint of this set uniformly at random.
+    ///
+    /// Returns `None` if the set is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use {mileage::{set::CharSet, CharRange}, rand::rngs::mock::StepRng};
+    /// let ranges = [CharRange::closed('0', '9'), CharRange::closed('a', 'f')];
+    /// let hex = CharSet::from_raw(&ranges);
+    /// let mut rng = StepRng::new(0, 1 << 60);
+    /// let c = hex.choose(&mut rng).unwrap();
+    /// assert!(c.is_ascii_hexdigit());
+    /// ```
+    pub fn choose<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<char> {
+        let len = self.len();
+        if len == 0 {
+            None
+        } else {
+            self.nth(rng.gen_range(0..len))
+        }
+    }
+
+    /// A distribution over the codepoints of this set, each equally likely.
+    ///
+    /// Returns `None` if the set is empty.
+    pub fn uniform(&self) -> Option<UniformChar<'_>> {
+        let len = self.len();
+        if len == 0 {
+            None
+        } else {
+            Some(UniformChar { set: self, len })
+        }
+    }
+}
+
+/// A uniform distribution over the codepoints of a nonempty set.
+///
+/// Constructed via `CharSet::uniform`. See `CharSet` for more information.
+///
+/// # Examples
+///
+/// ```
+/// # use {mileage::{set::CharSet, CharRange}, rand::{distributions::Distribution, rngs::mock::StepRng}};
+/// let ranges = [CharRange::closed('a', 'z')];
+/// let lower = CharSet::from_raw(&ranges).uniform().unwrap();
+/// let word: String = lower.sample_iter(StepRng::new(0, 1 << 59)).take(8).collect();
+/// assert!(word.chars().all(|c| c.is_ascii_lowercase()));
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct UniformChar<'a> {
+    set: &'a CharSet,
+    len: usize,
+}
+
+impl Distribution<char> for UniformChar<'_> {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> char {
+        self.set
+            .nth(rng.gen_range(0..self.len))
+            .unwrap_or_else(|| unreachable!("index is within the set"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::CharRange, rand::rngs::mock::StepRng};
+
+    #[test]
+    fn choose() {
+        let mut rng = StepRng::new(0, 0x0123_4567_89AB_CDEF);
+        assert_eq!(CharSet::empty().choose(&mut rng), None);
+        assert!(CharSet::empty().uniform().is_none());
+
+        let ranges = [CharRange::closed('a', 'a')];
+        let set = CharSet::from_raw(&ranges);
+        assert_eq!(set.choose(&mut rng), Some('a'));
+
+        let ranges = [
+            CharRange::closed('a', 'c'),
+            CharRange::closed('\u{D7FF}', '\u{E000}'),
+            CharRange::closed(char::MAX, char::MAX),
+        ];
+        let set = CharSet::from_raw(&ranges);
+        let uniform = set.uniform().unwrap();
+        let mut seen = [false; 6];
+        for _ in 0..256 {
+            let c = set.choose(&mut rng).unwrap();
+            assert!(set.contains(c), "{:?}", c);
+            seen[set.position(c).unwrap()] = true;
+            assert!(set.contains(uniform.sample(&mut rng)));
+        }
+        assert!(seen.iter().all(|&seen| seen));
+    }
+}
@@ -0,0 +1,126 @@
+use {
+    crate::{
+        set::{CharSet, CharSetBuf},
+        AFTER_SURROGATE, BEFORE_SURROGATE,
+    },
+    core::char,
+    rangemap::{RangeInclusiveSet, StepFns},
+};
+
+/// Steps between codepoints for `rangemap`, skipping over the surrogates.
+///
+/// `char` does not implement `rangemap::StepLite`, so a `RangeInclusiveSet<char>`
+/// needs explicit step functions. With these, ranges either side of the
+/// surrogates are treated as touching, so `rangemap` never sees a gap
+/// which contains no codepoints.
+///
+/// Stepping saturates at `'\0'` and `char::MAX`.
+///
+/// # Examples
+///
+/// ```
+/// # use {mileage::set::{CharSetBuf, CharStep}, rangemap::RangeInclusiveSet};
+/// let mut ranges = RangeInclusiveSet::<char, CharStep>::new_with_step_fns();
+/// ranges.insert('a'..='f');
+/// ranges.insert('0'..='9');
+/// let set = CharSetBuf::from(&ranges);
+/// assert!(set.contains('7'));
+/// assert!(RangeInclusiveSet::from(&*set) == ranges);
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct CharStep;
+
+impl StepFns<char> for CharStep {
+    fn add_one(c: &char) -> char {
+        match *c {
+            BEFORE_SURROGATE => AFTER_SURROGATE,
+            c => char::from_u32(c as u32 + 1).unwrap_or(char::MAX),
+        }
+    }
+
+    fn sub_one(c: &char) -> char {
+        match *c {
+            AFTER_SURROGATE => BEFORE_SURROGATE,
+            '\0' => '\0',
+            c => char::from_u32(c as u32 - 1).unwrap_or('\0'),
+        }
+    }
+}
+
+impl<S: StepFns<char>> From<&RangeInclusiveSet<char, S>> for CharSetBuf {
+    fn from(ranges: &RangeInclusiveSet<char, S>) -> Self {
+        ranges.iter().cloned().collect()
+    }
+}
+
+impl<S: StepFns<char>> From<RangeInclusiveSet<char, S>> for CharSetBuf {
+    fn from(ranges: RangeInclusiveSet<char, S>) -> Self {
+        CharSetBuf::from(&ranges)
+    }
+}
+
+impl From<&CharSet> for RangeInclusiveSet<char, CharStep> {
+    fn from(set: &CharSet) -> Self {
+        let mut ranges = RangeInclusiveSet::new_with_step_fns();
+        for r in set.ranges() {
+            ranges.insert(r.low..=r.high);
+        }
+        ranges
+    }
+}
+
+impl From<&CharSetBuf> for RangeInclusiveSet<char, CharStep> {
+    fn from(set: &CharSetBuf) -> Self {
+        RangeInclusiveSet::from(&**set)
+    }
+}
+
+impl From<CharSetBuf> for RangeInclusiveSet<char, CharStep> {
+    fn from(set: CharSetBuf) -> Self {
+        RangeInclusiveSet::from(&*set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, alloc::vec::Vec};
+
+    #[test]
+    fn steps() {
+        assert_eq!(CharStep::add_one(&'a'), 'b');
+        assert_eq!(CharStep::add_one(&BEFORE_SURROGATE), AFTER_SURROGATE);
+        assert_eq!(CharStep::add_one(&char::MAX), char::MAX);
+        assert_eq!(CharStep::sub_one(&'b'), 'a');
+        assert_eq!(CharStep::sub_one(&AFTER_SURROGATE), BEFORE_SURROGATE);
+        assert_eq!(CharStep::sub_one(&'\0'), '\0');
+    }
+
+    #[test]
+    fn round_trip() {
+        let set = CharSetBuf::from(&[
+            '\0'..='\u{D7FF}',
+            '\u{E000}'..='\u{E00F}',
+            '\u{10FFFE}'..=char::MAX,
+        ]);
+        let ranges = RangeInclusiveSet::from(&set);
+        assert_eq!(
+            ranges.iter().cloned().collect::<Vec<_>>(),
+            ['\0'..='\u{E00F}', '\u{10FFFE}'..=char::MAX],
+        );
+        assert!(CharSetBuf::from(&ranges) == *set);
+
+        let mut ranges = ranges;
+        ranges.remove('a'..='\u{E000}');
+        assert_eq!(
+            CharSetBuf::from(&ranges),
+            CharSetBuf::from(&[
+                '\0'..='`',
+                '\u{E001}'..='\u{E00F}',
+                '\u{10FFFE}'..=char::MAX,
+            ]),
+        );
+
+        let set = CharSetBuf::from(ranges.clone());
+        assert!(RangeInclusiveSet::from(set) == ranges);
+    }
+}
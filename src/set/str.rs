@@ -0,0 +1,279 @@
+use {
+    crate::set::CharSet,
+    core::{iter::FusedIterator, str::CharIndices},
+};
+
+impl CharSet {
+    /// Iterate the codepoints of a string which are in this set,
+    /// along with their byte offsets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let ranges = [CharRange::closed('0', '9')];
+    /// let digits = CharSet::from_raw(&ranges);
+    /// let matches: Vec<_> = digits.matches("a1\u{E9}2").collect();
+    /// assert_eq!(matches, [(1, '1'), (4, '2')]);
+    /// ```
+    pub fn matches<'a>(&'a self, s: &'a str) -> Matches<'a> {
+        Matches {
+            set: self,
+            raw: s.char_indices(),
+        }
+    }
+
+    /// The first codepoint of a string which is in this set, along with its byte offset.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let ranges = [CharRange::closed('0', '9')];
+    /// let digits = CharSet::from_raw(&ranges);
+    /// assert_eq!(digits.find_first("route 66"), Some((6, '6')));
+    /// assert_eq!(digits.find_first("route sixty-six"), None);
+    /// ```
+    pub fn find_first(&self, s: &str) -> Option<(usize, char)> {
+        self.matches(s).next()
+    }
+
+    /// Split a string on the codepoints in this set.
+    ///
+    /// Like `str::split`, adjacent delimiters produce empty pieces.
+    /// See `CharSet::fields` to skip those, and `CharSet::split_inclusive`
+    /// to keep the delimiters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let ranges = [CharRange::closed(',', ',')];
+    /// let comma = CharSet::from_raw(&ranges);
+    /// let pieces: Vec<_> = comma.split("a,b,,c").collect();
+    /// assert_eq!(pieces, ["a", "b", "", "c"]);
+    /// ```
+    pub fn split<'a>(&'a self, s: &'a str) -> Split<'a> {
+        Split {
+            set: self,
+            rest: Some(s),
+        }
+    }
+
+    /// Split a string on the codepoints in this set, keeping each delimiter
+    /// at the end of the piece it terminates.
+    ///
+    /// Like `str::split_inclusive`, there is no empty piece after a final delimiter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let ranges = [CharRange::closed('.', '.'), CharRange::closed('?', '?')];
+    /// let ends = CharSet::from_raw(&ranges);
+    /// let sentences: Vec<_> = ends.split_inclusive("Hi. Why? Bye.").collect();
+    /// assert_eq!(sentences, ["Hi.", " Why?", " Bye."]);
+    /// ```
+    pub fn split_inclusive<'a>(&'a self, s: &'a str) -> SplitInclusive<'a> {
+        SplitInclusive { set: self, rest: s }
+    }
+
+    /// Split a string on runs of codepoints in this set, skipping empty pieces.
+    ///
+    /// This is like `str::split_whitespace`, but for an arbitrary set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{set::CharSet, CharRange};
+    /// let ranges = [CharRange::closed(' ', ' '), CharRange::closed(',', ',')];
+    /// let separators = CharSet::from_raw(&ranges);
+    /// let fields: Vec<_> = separators.fields(" a, b,,c ").collect();
+    /// assert_eq!(fields, ["a", "b", "c"]);
+    /// ```
+    pub fn fields<'a>(&'a self, s: &'a str) -> Fields<'a> {
+        Fields { raw: self.split(s) }
+    }
+
+    /// Find the first codepoint of a string which is in this set,
+    /// returning the byte offsets of its start and end.
+    fn find_delimiter(&self, s: &str) -> Option<(usize, usize)> {
+        self.find_first(s).map(|(i, c)| (i, i + c.len_utf8()))
+    }
+}
+
+/// An iterator over the codepoints of a string which are in a set.
+///
+/// Constructed via `CharSet::matches`. See `CharSet` for more information.
+#[derive(Clone, Debug)]
+pub struct Matches<'a> {
+    set: &'a CharSet,
+    raw: CharIndices<'a>,
+}
+
+impl Iterator for Matches<'_> {
+    type Item = (usize, char);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let set = self.set;
+        self.raw.find(|&(_, c)| set.contains(c))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.raw.size_hint().1)
+    }
+}
+
+impl DoubleEndedIterator for Matches<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let set = self.set;
+        self.raw.rfind(|&(_, c)| set.contains(c))
+    }
+}
+
+impl FusedIterator for Matches<'_> {}
+
+/// An iterator over the pieces of a string between codepoints in a set.
+///
+/// Constructed via `CharSet::split`. See `CharSet` for more information.
+#[derive(Clone, Debug)]
+pub struct Split<'a> {
+    set: &'a CharSet,
+    rest: Option<&'a str>,
+}
+
+impl<'a> Iterator for Split<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.rest?;
+        match self.set.find_delimiter(rest) {
+            Some((start, end)) => {
+                self.rest = Some(&rest[end..]);
+                Some(&rest[..start])
+            }
+            None => {
+                self.rest = None;
+                Some(rest)
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.rest {
+            Some(rest) => (1, Some(rest.len() + 1)),
+            None => (0, Some(0)),
+        }
+    }
+}
+
+impl FusedIterator for Split<'_> {}
+
+/// An iterator over the pieces of a string ending in codepoints in a set.
+///
+/// Constructed via `CharSet::split_inclusive`. See `CharSet` for more information.
+#[derive(Clone, Debug)]
+pub struct SplitInclusive<'a> {
+    set: &'a CharSet,
+    rest: &'a str,
+}
+
+impl<'a> Iterator for SplitInclusive<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let end = self
+            .set
+            .find_delimiter(self.rest)
+            .map_or(self.rest.len(), |(_, end)| end);
+        let (piece, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Some(piece)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let min = if self.rest.is_empty() { 0 } else { 1 };
+        (min, Some(self.rest.len()))
+    }
+}
+
+impl FusedIterator for SplitInclusive<'_> {}
+
+/// An iterator over the nonempty pieces of a string between codepoints in a set.
+///
+/// Constructed via `CharSet::fields`. See `CharSet` for more information.
+#[derive(Clone, Debug)]
+pub struct Fields<'a> {
+    raw: Split<'a>,
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.raw.find(|piece| !piece.is_empty())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.raw.size_hint().1)
+    }
+}
+
+impl FusedIterator for Fields<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::CharRange, alloc::vec::Vec};
+
+    #[test]
+    #[rustfmt::skip]
+    fn matches() {
+        let ranges = [CharRange::closed('a', 'c'), CharRange::closed('\u{1F600}', '\u{1F64F}')];
+        let set = CharSet::from_raw(&ranges);
+        let test_data: &[(&str, &[(usize, char)])] = &[
+            ("",                    &[]),
+            ("xyz",                 &[]),
+            ("abc",                 &[(0, 'a'), (1, 'b'), (2, 'c')]),
+            ("xaxb",                &[(1, 'a'), (3, 'b')]),
+            ("\u{E9}\u{1F600}c",    &[(2, '\u{1F600}'), (6, 'c')]),
+        ];
+
+        for &(s, expected) in test_data {
+            assert_eq!(set.matches(s).collect::<Vec<_>>(), expected, "{:?}", s);
+            let mut rev = set.matches(s).rev().collect::<Vec<_>>();
+            rev.reverse();
+            assert_eq!(rev, expected, "{:?}", s);
+            assert_eq!(set.find_first(s), expected.first().copied(), "{:?}", s);
+        }
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn split() {
+        let ranges = [CharRange::closed(',', ','), CharRange::closed('\u{3001}', '\u{3001}')];
+        let set = CharSet::from_raw(&ranges);
+        type Pieces = &'static [&'static str];
+        let test_data: &[(&str, Pieces, Pieces, Pieces)] = &[
+            // (string, split, split_inclusive, fields)
+            ("",                &[""],                  &[],                    &[]),
+            (",",               &["", ""],              &[","],                 &[]),
+            ("a",               &["a"],                 &["a"],                 &["a"]),
+            ("a,b",             &["a", "b"],            &["a,", "b"],           &["a", "b"]),
+            ("a,b,",            &["a", "b", ""],        &["a,", "b,"],          &["a", "b"]),
+            (",a,,b",           &["", "a", "", "b"],    &[",", "a,", ",", "b"], &["a", "b"]),
+            ("a\u{3001}b,c",    &["a", "b", "c"],       &["a\u{3001}", "b,", "c"], &["a", "b", "c"]),
+        ];
+
+        for &(s, split, split_inclusive, fields) in test_data {
+            assert_eq!(set.split(s).collect::<Vec<_>>(), split, "{:?}", s);
+            assert_eq!(set.split_inclusive(s).collect::<Vec<_>>(), split_inclusive, "{:?}", s);
+            assert_eq!(set.fields(s).collect::<Vec<_>>(), fields, "{:?}", s);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Support for property testing and fuzzing with sets.
+//!
+//! - With the `proptest` feature, this module provides `proptest` strategies.
+//! - With the `arbitrary` feature, `CharSetBuf` implements `arbitrary::Arbitrary`.
+//!
+//! Either way, the generated sets are always valid, normalized sets.
+
+use crate::{set::CharSetBuf, CharRange};
+#[cfg(feature = "proptest")]
+use {crate::set::CharSet, proptest::prelude::*};
+
+/// The range between two codepoints, in whichever order they are given.
+fn range_between(a: char, b: char) -> CharRange {
+    if a <= b {
+        CharRange::closed(a, b)
+    } else {
+        CharRange::closed(b, a)
+    }
+}
+
+/// A strategy producing any set, made of up to 16 arbitrary ranges.
+///
+/// # Examples
+///
+/// ```
+/// # use {mileage::set::testing::any_char_set, proptest::prelude::*};
+/// proptest!(|(set in any_char_set())| {
+///     prop_assert!(set.ranges().count() <= 16);
+/// });
+/// ```
+#[cfg(feature = "proptest")]
+pub fn any_char_set() -> impl Strategy<Value = CharSetBuf> {
+    proptest::collection::vec((any::<char>(), any::<char>()), 0..16).prop_map(|bounds| {
+        bounds
+            .into_iter()
+            .map(|(a, b)| range_between(a, b))
+            .collect()
+    })
+}
+
+/// A strategy producing the codepoints of a set, which shrink towards its lowest codepoint.
+///
+/// # Panics
+///
+/// Panics if the set is empty, as there is nothing to produce.
+///
+/// # Examples
+///
+/// ```
+/// # use {mileage::{set::{testing::char_in, CharSet}, CharRange}, proptest::prelude::*};
+/// let ranges = [CharRange::closed('0', '9'), CharRange::closed('a', 'f')];
+/// let hex = CharSet::from_raw(&ranges);
+/// proptest!(|(c in char_in(hex))| {
+///     prop_assert!(c.is_ascii_hexdigit());
+/// });
+/// ```
+#[cfg(feature = "proptest")]
+pub fn char_in(set: &CharSet) -> impl Strategy<Value = char> + '_ {
+    assert!(
+        !set.is_empty(),
+        "cannot produce a codepoint of an empty set"
+    );
+    (0..set.len()).prop_map(move |n| {
+        set.nth(n)
+            .unwrap_or_else(|| unreachable!("index is within the set"))
+    })
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for CharSetBuf {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut set = CharSetBuf::new();
+        for bounds in u.arbitrary_iter::<(char, char)>()? {
+            let (a, b) = bounds?;
+            set.insert_range(range_between(a, b));
+        }
+        Ok(set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "proptest")]
+    proptest! {
+        #[test]
+        fn any_char_set_is_valid(set in any_char_set()) {
+            prop_assert!(CharSet::try_from_raw(&set.ranges).is_ok());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "proptest")]
+    fn char_in_is_member() {
+        let ranges = [
+            CharRange::closed('a', 'c'),
+            CharRange::closed('\u{D7FF}', '\u{E000}'),
+            CharRange::closed(char::MAX, char::MAX),
+        ];
+        let set = CharSet::from_raw(&ranges);
+        proptest!(|(c in char_in(set))| {
+            prop_assert!(set.contains(c));
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "arbitrary")]
+    fn arbitrary_is_valid() {
+        use {alloc::vec::Vec, arbitrary::Arbitrary};
+
+        let mut state = 7u32;
+        for len in 0..64 {
+            let data: Vec<u8> = (0..len * 8)
+                .map(|_| {
+                    state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                    (state >> 16) as u8
+                })
+                .collect();
+            let set = CharSetBuf::arbitrary(&mut arbitrary::Unstructured::new(&data)).unwrap();
+            assert!(crate::set::CharSet::try_from_raw(&set.ranges).is_ok());
+        }
+    }
+}
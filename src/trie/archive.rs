@@ -0,0 +1,144 @@
+#[cfg(feature = "owned-trie")]
+use crate::trie::CharTrieBuf;
+use {
+    crate::trie::{BytesError, CharTrie},
+    core::fmt,
+    rkyv::{
+        munge::munge,
+        rancor::Fallible,
+        ser::{Writer, WriterExt},
+        vec::{ArchivedVec, VecResolver},
+        Archive, Place, Portable, Serialize,
+    },
+};
+
+/// An archived `CharTrie`, which can be used in place, without deserializing.
+///
+/// The archive holds the binary serialization of the trie, as `CharTrie::to_bytes`,
+/// aligned to 8 bytes, so `as_trie` borrows the tables straight from the archive.
+/// The archived bytes are checked by `as_trie`, not by `rkyv::access`.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::trie::{ArchivedCharTrie, CharTrie};
+/// static LEVEL1: [u64; 32] = {
+///     let mut level1 = [0; 32];
+///     level1[0] = 0x03FF << 0x30;
+///     level1
+/// };
+/// static DIGITS: CharTrie<'static> =
+///     CharTrie::from_raw(&LEVEL1, &[0; 992], (&[0; 256], &[[0; 64]]), &[0]);
+///
+/// let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&DIGITS).unwrap();
+/// let archived = rkyv::access::<ArchivedCharTrie, rkyv::rancor::Error>(&bytes).unwrap();
+/// let digits = archived.as_trie().unwrap();
+/// assert!(digits.contains('7'));
+/// assert!(!digits.contains('a'));
+/// ```
+#[derive(Portable, rkyv::bytecheck::CheckBytes)]
+#[bytecheck(crate = rkyv::bytecheck)]
+#[repr(transparent)]
+pub struct ArchivedCharTrie {
+    bytes: ArchivedVec<u8>,
+}
+
+impl ArchivedCharTrie {
+    /// Borrow the archived trie, checking that it is valid.
+    pub fn as_trie(&self) -> Result<CharTrie<'_>, BytesError> {
+        CharTrie::from_bytes(self.bytes.as_slice())
+    }
+}
+
+impl fmt::Debug for ArchivedCharTrie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ArchivedCharTrie")
+            .field(&self.as_trie())
+            .finish()
+    }
+}
+
+impl Archive for CharTrie<'_> {
+    type Archived = ArchivedCharTrie;
+    type Resolver = VecResolver;
+
+    fn resolve(&self, resolver: VecResolver, out: Place<ArchivedCharTrie>) {
+        // the header and layout, then every table
+        let len = CharTrie::BYTES_HEADER.len() + 16 + self.memory_footprint();
+        munge!(let ArchivedCharTrie { bytes } = out);
+        ArchivedVec::resolve_from_len(len, resolver, bytes);
+    }
+}
+
+impl<S: Fallible + Writer + ?Sized> Serialize<S> for CharTrie<'_> {
+    fn serialize(&self, serializer: &mut S) -> Result<VecResolver, S::Error> {
+        let pos = serializer.align(8)?;
+        serializer.write(&self.to_bytes())?;
+        Ok(VecResolver::from_pos(pos))
+    }
+}
+
+#[cfg(feature = "owned-trie")]
+impl Archive for CharTrieBuf {
+    type Archived = ArchivedCharTrie;
+    type Resolver = VecResolver;
+
+    fn resolve(&self, resolver: VecResolver, out: Place<ArchivedCharTrie>) {
+        self.as_trie().resolve(resolver, out);
+    }
+}
+
+#[cfg(feature = "owned-trie")]
+impl<S: Fallible + Writer + ?Sized> Serialize<S> for CharTrieBuf {
+    fn serialize(&self, serializer: &mut S) -> Result<VecResolver, S::Error> {
+        self.as_trie().serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, rkyv::rancor::Error};
+
+    #[test]
+    fn round_trip() {
+        const LEVEL2: [u16; 992] = {
+            let mut level2 = [0; 992];
+            level2[0] = 1;
+            level2
+        };
+        const LEVEL3: [u8; 256] = {
+            let mut level3 = [0; 256];
+            level3[0] = 1;
+            level3
+        };
+        const CHONK: [u16; 64] = {
+            let mut chonk = [0; 64];
+            chonk[0] = 2;
+            chonk
+        };
+        // U+0800..=U+083F and U+10000
+        static TRIE: CharTrie<'static> =
+            CharTrie::from_raw_wide(&[0; 32], &LEVEL2, (&LEVEL3, &[[0; 64], CHONK]), &[0, !0, 1]);
+
+        // the trie follows a single byte, so it needs padding
+        let bytes = rkyv::to_bytes::<Error>(&(1u8, TRIE)).unwrap();
+        let archived =
+            rkyv::access::<rkyv::tuple::ArchivedTuple2<u8, ArchivedCharTrie>, Error>(&bytes)
+                .unwrap();
+        let trie = archived.1.as_trie().unwrap();
+        assert!(trie.ranges().eq(TRIE.ranges()));
+        assert_eq!(trie.to_bytes(), TRIE.to_bytes());
+    }
+
+    #[test]
+    #[cfg(feature = "owned-trie")]
+    fn owned() {
+        use crate::CharRange;
+
+        let mut buf = CharTrieBuf::new();
+        buf.insert_range(CharRange::closed('a', 'z')).unwrap();
+        let bytes = rkyv::to_bytes::<Error>(&buf).unwrap();
+        let archived = rkyv::access::<ArchivedCharTrie, Error>(&bytes).unwrap();
+        assert_eq!(archived.as_trie().unwrap(), CharRange::closed('a', 'z'));
+    }
+}
@@ -0,0 +1,226 @@
+use {
+    crate::trie::{check_narrow, check_wide, Shape, TrieError},
+    core::fmt,
+};
+
+/// A compressed trie-like set for codepoints of the Basic Multilingual Plane.
+///
+/// This is a `CharTrie` without level3, so it cannot contain any codepoint
+/// past U+FFFF, but saves the 256 byte first table of level3 and its blocks.
+/// Many sets, such as those of legacy encodings, never leave the BMP.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::trie::CharTrieBmp;
+/// static LEVEL1: [u64; 32] = {
+///     let mut level1 = [0; 32];
+///     level1[0] = 0x03FF << 0x30;
+///     level1
+/// };
+/// static DIGITS: CharTrieBmp = CharTrieBmp::from_raw(&LEVEL1, &[0; 992], &[0]);
+/// assert!(DIGITS.contains('7'));
+/// assert!(!DIGITS.contains('\u{1D7D8}'));
+/// ```
+#[derive(Copy, Clone)]
+pub struct CharTrieBmp {
+    level1: &'static [u64; 32],
+    level2: Level2,
+    leaves: &'static [u64],
+}
+
+#[derive(Copy, Clone)]
+enum Level2 {
+    Narrow(&'static [u8; 992]),
+    Wide(&'static [u16; 992]),
+}
+
+impl fmt::Debug for CharTrieBmp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let index = match self.level2 {
+            Level2::Narrow(_) => "u8",
+            Level2::Wide(_) => "u16",
+        };
+        f.debug_struct("CharTrieBmp")
+            .field("level1", &Shape(format_args!("&[u64; 32]")))
+            .field("level2", &Shape(format_args!("&[{}; 992]", index)))
+            .field(
+                "level4",
+                &Shape(format_args!("&[u64; dyn {}]", self.leaves.len())),
+            )
+            .finish()
+    }
+}
+
+impl CharTrieBmp {
+    /// Does this set contain this codepoint?
+    #[inline]
+    pub fn contains(&self, c: char) -> bool {
+        let c = c as u32;
+        let chunk = if c < 0x800 {
+            self.level1[(c >> 6) as usize]
+        } else if c < 0x10000 {
+            let chunk_index = (c - 0x800) >> 6;
+            match self.level2 {
+                Level2::Narrow(level2) => self.leaves[usize::from(level2[chunk_index as usize])],
+                Level2::Wide(level2) => self.leaves[usize::from(level2[chunk_index as usize])],
+            }
+        } else {
+            return false;
+        };
+        (chunk >> (c & 0b_111_111)) & 1 == 1
+    }
+
+    /// Create a BMP trie from the components as described by `CharTrie::from_raw`.
+    ///
+    /// The indices are not checked; looking up a codepoint through an index
+    /// which is out of bounds panics. Use `validate` to check them.
+    pub const fn from_raw(
+        level1: &'static [u64; 32],
+        level2: &'static [u8; 992],
+        leaves: &'static [u64],
+    ) -> Self {
+        CharTrieBmp {
+            level1,
+            level2: Level2::Narrow(level2),
+            leaves,
+        }
+    }
+
+    /// Create a BMP trie with 16 bit indices into the leaves,
+    /// for sets with more than 256 distinct leaves.
+    pub const fn from_raw_wide(
+        level1: &'static [u64; 32],
+        level2: &'static [u16; 992],
+        leaves: &'static [u64],
+    ) -> Self {
+        CharTrieBmp {
+            level1,
+            level2: Level2::Wide(level2),
+            leaves,
+        }
+    }
+
+    /// Check that every index of this trie is in bounds,
+    /// such that looking up any codepoint will not panic.
+    pub fn validate(&self) -> Result<(), TrieError> {
+        // an empty level3 has no indices which could be out of bounds
+        // unless level2 is already, as it needs a leaf at index 0
+        match self.level2 {
+            Level2::Narrow(level2) => {
+                check_narrow(level2, (&[0; 256], &[[0; 64]]), self.leaves.len())
+            }
+            Level2::Wide(level2) => check_wide(level2, (&[0; 256], &[[0; 64]]), self.leaves.len()),
+        }
+    }
+}
+
+/// Generate a new BMP trie from a membership function.
+///
+/// This constructs Rust code that is legal in expression position that
+/// evaluates to a `CharTrieBmp`. Requires that `CharTrieBmp` is in scope.
+///
+/// Returns `None` if the set contains any codepoint past U+FFFF,
+/// as then it needs a full `CharTrie` from `generate`.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::trie::generate_bmp;
+/// assert!(generate_bmp(|c| c.is_ascii_digit()).is_some());
+/// assert!(generate_bmp(|c| c.is_numeric()).is_none());
+/// ```
+#[cfg(feature = "new-trie")]
+pub fn generate_bmp(f: impl Fn(char) -> bool) -> Option<proc_macro2::TokenStream> {
+    use {
+        alloc::{collections::BTreeMap, vec::Vec},
+        core::char,
+    };
+
+    if (0x10000..=char::MAX as u32)
+        .filter_map(char::from_u32)
+        .any(&f)
+    {
+        return None;
+    }
+
+    let mut chunks = (0..0x10000)
+        .step_by(64)
+        .map(|base| crate::trie::chunk_of(base, &f));
+    let level1: Vec<u64> = chunks.by_ref().take(32).collect();
+    let mut leaves = Vec::new();
+    let mut leaf_indices = BTreeMap::new();
+    let level2: Vec<usize> = chunks
+        .map(|chunk| {
+            *leaf_indices.entry(chunk).or_insert_with(|| {
+                leaves.push(chunk);
+                leaves.len() - 1
+            })
+        })
+        .collect();
+
+    let (constructor, level2) = if leaves.len() <= 256 {
+        let level2 = level2.iter().map(|&idx| idx as u8);
+        (quote::quote!(from_raw), quote::quote!(&[#(#level2),*]))
+    } else {
+        let level2 = level2.iter().map(|&idx| idx as u16);
+        (quote::quote!(from_raw_wide), quote::quote!(&[#(#level2),*]))
+    };
+    Some(quote::quote! {
+        CharTrieBmp::#constructor(&[#(#level1),*], #level2, &[#(#leaves),*])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CharRange;
+
+    #[test]
+    fn contains() {
+        static LEVEL2: [u16; 992] = {
+            let mut level2 = [0; 992];
+            level2[1] = 1;
+            level2
+        };
+        static LEVEL1: [u64; 32] = {
+            let mut level1 = [0; 32];
+            level1[0] = !0;
+            level1
+        };
+        // U+0000..=U+003F and U+0840..=U+087F
+        let trie = CharTrieBmp::from_raw_wide(&LEVEL1, &LEVEL2, &[0, !0]);
+        assert_eq!(trie.validate(), Ok(()));
+        for c in CharRange::from(..) {
+            let expected = matches!(c, '\0'..='\x3F' | '\u{840}'..='\u{87F}');
+            assert_eq!(trie.contains(c), expected, "{:?}", c);
+        }
+
+        let trie = CharTrieBmp::from_raw(&[0; 32], &[1; 992], &[0]);
+        assert_eq!(trie.validate(), Err(TrieError::Level2(0)));
+        let trie = CharTrieBmp::from_raw(&[0; 32], &[0; 992], &[]);
+        assert_eq!(trie.validate(), Err(TrieError::Level2(0)));
+    }
+
+    #[test]
+    #[cfg(feature = "new-trie")]
+    fn generate() {
+        use {alloc::string::ToString, quote::quote};
+
+        let trie = generate_bmp(|c| c == '\u{800}').unwrap();
+        let level1 = [0u64; 32];
+        let level2: [u8; 992] = {
+            let mut level2 = [1u8; 992];
+            level2[0] = 0;
+            level2
+        };
+        assert_eq!(
+            trie.to_string(),
+            quote! {
+                CharTrieBmp::from_raw(&[#(#level1),*], &[#(#level2),*], &[1u64, 0u64])
+            }
+            .to_string(),
+        );
+        assert!(generate_bmp(|c| c == '\u{10000}').is_none());
+    }
+}
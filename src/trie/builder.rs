@@ -0,0 +1,127 @@
+use {
+    crate::{
+        trie::{chunk_mask, CharTrieBuf, GenerateError},
+        CharRange,
+    },
+    alloc::collections::BTreeMap,
+    core::char,
+};
+
+/// An incremental builder of a `CharTrieBuf`, from codepoints as they arrive.
+///
+/// Only the chunks of 64 codepoints which have been touched are stored,
+/// so a trie can be assembled from streaming data, such as while parsing
+/// a font's cmap, without first collecting a full set or writing a predicate.
+/// The distinct leaves are only counted by `build`.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::{trie::CharTrieBuilder, CharRange};
+/// let mut builder = CharTrieBuilder::new();
+/// for c in "hello, world".chars() {
+///     builder.insert(c);
+/// }
+/// builder.insert_range(CharRange::from('0'..='9'));
+/// let trie = builder.build().unwrap();
+/// assert!(trie.contains('w'));
+/// assert!(trie.contains('7'));
+/// assert!(!trie.contains('x'));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CharTrieBuilder {
+    /// The membership bits of each touched chunk, by the chunk's index.
+    chunks: BTreeMap<u32, u64>,
+}
+
+impl CharTrieBuilder {
+    /// A builder for an empty set.
+    pub fn new() -> Self {
+        CharTrieBuilder::default()
+    }
+
+    /// Insert a single codepoint.
+    pub fn insert(&mut self, c: char) {
+        let c = c as u32;
+        *self.chunks.entry(c >> 6).or_insert(0) |= 1 << (c & 0b_111_111);
+    }
+
+    /// Insert a range of codepoints.
+    pub fn insert_range(&mut self, r: CharRange) {
+        if r.is_empty() {
+            return;
+        }
+        let (low, high) = (r.low as u32, r.high as u32);
+        for base in (low & !0b_111_111..=high).step_by(64) {
+            let mask = chunk_mask(base, low, high);
+            if mask != 0 {
+                *self.chunks.entry(base >> 6).or_insert(0) |= mask;
+            }
+        }
+    }
+
+    /// Finish building the trie.
+    ///
+    /// This fails as `CharTrieBuf::from_fn` does,
+    /// if the set needs more than 256 distinct leaves or level3 blocks.
+    pub fn build(self) -> Result<CharTrieBuf, GenerateError> {
+        let chunks = &self.chunks;
+        CharTrieBuf::from_chunks(
+            (0..=char::MAX as u32 >> 6).map(|idx| chunks.get(&idx).copied().unwrap_or(0)),
+        )
+    }
+}
+
+impl Extend<char> for CharTrieBuilder {
+    fn extend<T: IntoIterator<Item = char>>(&mut self, iter: T) {
+        iter.into_iter().for_each(|c| self.insert(c))
+    }
+}
+
+impl Extend<CharRange> for CharTrieBuilder {
+    fn extend<T: IntoIterator<Item = CharRange>>(&mut self, iter: T) {
+        iter.into_iter().for_each(|r| self.insert_range(r))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build() {
+        let f = |c: char| {
+            c.is_ascii_alphanumeric() || ('\u{D000}'..='\u{F000}').contains(&c) || c == char::MAX
+        };
+
+        let mut builder = CharTrieBuilder::new();
+        builder.insert_range(CharRange::from('\u{D000}'..='\u{F000}'));
+        builder.extend("abcdefghijklmnopqrstuvwxyz".chars());
+        builder.extend(
+            [CharRange::from('A'..='Z'), CharRange::from('0'..='9')]
+                .iter()
+                .copied(),
+        );
+        builder.insert(char::MAX);
+        builder.insert('a');
+        builder.insert_range(CharRange::closed('b', 'a'));
+        let trie = builder.build().unwrap();
+        assert_eq!(trie.to_bytes(), CharTrieBuf::from_fn(f).unwrap().to_bytes());
+
+        assert_eq!(
+            CharTrieBuilder::new().build().unwrap().to_bytes(),
+            CharTrieBuf::new().to_bytes()
+        );
+
+        // every 64-codepoint chunk below U+10000 is different
+        let mut builder = CharTrieBuilder::new();
+        for chunk in 0..0x400 {
+            for bit in 0..16 {
+                if chunk >> bit & 1 == 1 {
+                    builder.extend(char::from_u32(chunk << 6 | bit));
+                }
+            }
+        }
+        assert!(builder.build().is_err());
+    }
+}
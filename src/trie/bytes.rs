@@ -0,0 +1,331 @@
+use {
+    crate::trie::{CharTrie, TrieError},
+    core::{convert::TryInto, fmt, mem, slice},
+};
+
+/// Identifies serialized `CharTrie` bytes.
+const MAGIC: [u8; 4] = *b"mlgt";
+
+/// Distinguishes native from swapped endianness in serialized `CharTrie` bytes.
+const ENDIANNESS_TAG: u32 = 0xFEFF;
+
+/// An error from loading a `CharTrie` from bytes.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum BytesError {
+    /// The bytes do not start with `CharTrie::BYTES_HEADER` and a known layout.
+    BadHeader,
+    /// The bytes were written by a platform with the opposite endianness.
+    WrongEndianness,
+    /// The bytes are not aligned for `u64`.
+    Misaligned,
+    /// The bytes are not as long as the layout in the header.
+    BadLength,
+    /// The tables are not a valid trie.
+    InvalidTrie(TrieError),
+}
+
+impl fmt::Display for BytesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BytesError::BadHeader => f.write_str("missing char trie header"),
+            BytesError::WrongEndianness => f.write_str("char trie has the wrong endianness"),
+            BytesError::Misaligned => f.write_str("char trie tables are misaligned"),
+            BytesError::BadLength => f.write_str("char trie tables have the wrong length"),
+            BytesError::InvalidTrie(err) => err.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BytesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BytesError::InvalidTrie(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// A table item which can be viewed as and loaded from native endian bytes.
+///
+/// Every implementor has no padding and is valid for any bit pattern.
+trait Raw: Copy {}
+impl Raw for u8 {}
+impl Raw for u16 {}
+impl Raw for u32 {}
+impl Raw for u64 {}
+impl<T: Raw> Raw for [T; 64] {}
+
+/// View a table as bytes.
+#[cfg(feature = "alloc")]
+#[allow(unsafe_code)]
+fn raw_bytes<T: Raw>(items: &[T]) -> &[u8] {
+    let len = mem::size_of_val(items);
+    // SAFETY: `Raw` types have no padding bytes.
+    unsafe { slice::from_raw_parts(items.as_ptr() as *const u8, len) }
+}
+
+/// Split a table of `len` items off the front of `bytes`.
+#[allow(unsafe_code)]
+fn take<'a, T: Raw>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [T], BytesError> {
+    let size = len
+        .checked_mul(mem::size_of::<T>())
+        .filter(|&size| size <= bytes.len())
+        .ok_or(BytesError::BadLength)?;
+    if bytes.as_ptr().align_offset(mem::align_of::<T>()) != 0 {
+        return Err(BytesError::Misaligned);
+    }
+    let (table, rest) = bytes.split_at(size);
+    *bytes = rest;
+    // SAFETY: the bytes are aligned and sized for `len` items, as checked above,
+    // and `Raw` types are valid for any bit pattern.
+    Ok(unsafe { slice::from_raw_parts(table.as_ptr() as *const T, len) })
+}
+
+/// Serialize the tables of a trie, including the header.
+#[cfg(feature = "alloc")]
+fn write<I: Raw>(
+    level1: &[u64; 32],
+    level2: &[I; 992],
+    level3: (&[u8; 256], &[[I; 64]]),
+    leaves: &[u64],
+) -> alloc::vec::Vec<u8> {
+    let layout = [
+        mem::size_of::<I>() as u32,
+        level3.1.len() as u32,
+        leaves.len() as u32,
+        0,
+    ];
+    let mut bytes = alloc::vec::Vec::new();
+    bytes.extend_from_slice(&CharTrie::BYTES_HEADER);
+    bytes.extend_from_slice(raw_bytes(&layout));
+    bytes.extend_from_slice(raw_bytes(level1));
+    bytes.extend_from_slice(raw_bytes(leaves));
+    bytes.extend_from_slice(level3.0);
+    bytes.extend_from_slice(raw_bytes(level2));
+    bytes.extend_from_slice(raw_bytes(level3.1));
+    bytes
+}
+
+/// # Binary serialization
+///
+/// A trie is serialized as `CharTrie::BYTES_HEADER`, then the width of its
+/// indices, the number of level3 blocks, and the number of leaves as
+/// native endian `u32`s followed by a zero `u32`, and then its tables:
+/// level1, the leaves, the first table of level3, level2, and the blocks of level3.
+/// Loading it back with `CharTrie::from_bytes` borrows the tables in place,
+/// from static data or from a buffer loaded at runtime, so the bytes must be
+/// aligned to 8 bytes, as `include_bytes!` does not guarantee.
+///
+/// Provenance is not serialized.
+///
+/// ```
+/// # use mileage::trie::CharTrie;
+/// #[repr(C, align(8))]
+/// struct Aligned<T: ?Sized>(T);
+///
+/// // 8 bit indices, one level3 block, and one leaf, all zero
+/// let mut buf = Aligned([0u8; 1600]);
+/// buf.0[..8].copy_from_slice(&CharTrie::BYTES_HEADER);
+/// buf.0[8..12].copy_from_slice(&1u32.to_ne_bytes());
+/// buf.0[12..16].copy_from_slice(&1u32.to_ne_bytes());
+/// buf.0[16..20].copy_from_slice(&1u32.to_ne_bytes());
+/// // the first chunk of level1 holds U+0030..=U+0039
+/// buf.0[24..32].copy_from_slice(&(0x03FF_u64 << 0x30).to_ne_bytes());
+///
+/// let digits = CharTrie::from_bytes(&buf.0).unwrap();
+/// assert!(digits.contains('7'));
+/// assert!(!digits.contains('a'));
+/// ```
+impl<'a> CharTrie<'a> {
+    /// The header which starts a serialized trie.
+    pub const BYTES_HEADER: [u8; 8] = {
+        let tag = ENDIANNESS_TAG.to_ne_bytes();
+        [
+            MAGIC[0], MAGIC[1], MAGIC[2], MAGIC[3], tag[0], tag[1], tag[2], tag[3],
+        ]
+    };
+
+    /// Serialize this trie, including the header.
+    #[cfg(feature = "alloc")]
+    pub fn to_bytes(&self) -> alloc::vec::Vec<u8> {
+        use crate::trie::Levels;
+
+        match self.levels {
+            Levels::Narrow(level2, level3) => write(self.level1, level2, level3, self.leaves),
+            Levels::Wide(level2, level3) => write(self.level1, level2, level3, self.leaves),
+        }
+    }
+
+    /// Borrow a serialized trie, checking that it is valid.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<CharTrie<'a>, BytesError> {
+        if bytes.len() < 8 || bytes[..4] != MAGIC {
+            return Err(BytesError::BadHeader);
+        }
+        let (header, mut body) = bytes.split_at(8);
+        if header != CharTrie::BYTES_HEADER {
+            let swapped = ENDIANNESS_TAG.swap_bytes().to_ne_bytes();
+            return Err(if header[4..] == swapped {
+                BytesError::WrongEndianness
+            } else {
+                BytesError::BadHeader
+            });
+        }
+        if bytes.as_ptr().align_offset(mem::align_of::<u64>()) != 0 {
+            return Err(BytesError::Misaligned);
+        }
+
+        let layout = take::<u8>(&mut body, 16).map_err(|_| BytesError::BadHeader)?;
+        let layout = |i: usize| u32::from_ne_bytes(layout[4 * i..][..4].try_into().unwrap());
+        let (width, chonks, leaves) = (layout(0), layout(1) as usize, layout(2) as usize);
+        if layout(3) != 0 {
+            return Err(BytesError::BadHeader);
+        }
+
+        let level1 = take::<u64>(&mut body, 32)?.try_into().unwrap();
+        let leaves = take::<u64>(&mut body, leaves)?;
+        let level3_0 = take::<u8>(&mut body, 256)?.try_into().unwrap();
+        let trie = match width {
+            1 => {
+                let level2 = take::<u8>(&mut body, 992)?.try_into().unwrap();
+                let level3 = (level3_0, take::<[u8; 64]>(&mut body, chonks)?);
+                CharTrie::try_from_raw(level1, level2, level3, leaves)
+                    .map_err(BytesError::InvalidTrie)?
+            }
+            2 => {
+                let level2 = take::<u16>(&mut body, 992)?.try_into().unwrap();
+                let level3 = (level3_0, take::<[u16; 64]>(&mut body, chonks)?);
+                CharTrie::try_from_raw_wide(level1, level2, level3, leaves)
+                    .map_err(BytesError::InvalidTrie)?
+            }
+            _ => return Err(BytesError::BadHeader),
+        };
+        if !body.is_empty() {
+            return Err(BytesError::BadLength);
+        }
+        Ok(trie)
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+
+    #[repr(C, align(8))]
+    struct Aligned<T: ?Sized>(T);
+
+    /// Copy bytes into a buffer aligned to 8 bytes, after `offset` bytes, and load them.
+    fn load(bytes: &[u8], offset: usize) -> Result<(), BytesError> {
+        let mut buf = Aligned([0u8; 4096]);
+        let buf = &mut buf.0[offset..][..bytes.len()];
+        buf.copy_from_slice(bytes);
+        CharTrie::from_bytes(buf).map(drop)
+    }
+
+    /// The serialized trie of U+0030..=U+0039, with 8 bit indices,
+    /// one level3 block, and one leaf, all zero.
+    static DIGITS: Aligned<[u8; 1600]> = {
+        let mut bytes = [0; 1600];
+        let mut i = 0;
+        while i < 8 {
+            bytes[i] = CharTrie::BYTES_HEADER[i];
+            i += 1;
+        }
+        let (one, digits) = (1u32.to_ne_bytes(), (0x03FF_u64 << 0x30).to_ne_bytes());
+        let mut i = 0;
+        while i < 4 {
+            bytes[8 + i] = one[i];
+            bytes[12 + i] = one[i];
+            bytes[16 + i] = one[i];
+            i += 1;
+        }
+        let mut i = 0;
+        while i < 8 {
+            bytes[24 + i] = digits[i];
+            i += 1;
+        }
+        Aligned(bytes)
+    };
+
+    const LEVEL2: [u8; 992] = {
+        let mut level2 = [0; 992];
+        level2[0] = 1;
+        level2
+    };
+    const WIDE_LEVEL2: [u16; 992] = {
+        let mut level2 = [0; 992];
+        level2[0] = 1;
+        level2
+    };
+    const LEVEL3: [u8; 256] = {
+        let mut level3 = [0; 256];
+        level3[255] = 1;
+        level3
+    };
+    static NARROW: CharTrie<'static> = CharTrie::from_raw(
+        &[
+            !0, !0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0,
+        ],
+        &LEVEL2,
+        (&LEVEL3, &[[0; 64], [2; 64]]),
+        &[0, !0, 0b1010],
+    );
+    static WIDE: CharTrie<'static> = CharTrie::from_raw_wide(
+        &[0; 32],
+        &WIDE_LEVEL2,
+        (&LEVEL3, &[[0; 64], [2; 64]]),
+        &[0, !0, 0b1010],
+    );
+
+    #[test]
+    fn round_trip() {
+        for &trie in &[NARROW, WIDE] {
+            let bytes = trie.to_bytes();
+            let mut buf = Aligned([0u8; 4096]);
+            buf.0[..bytes.len()].copy_from_slice(&bytes);
+            let loaded = CharTrie::from_bytes(&buf.0[..bytes.len()]).unwrap();
+            assert!(loaded.ranges().eq(trie.ranges()));
+            assert_eq!(loaded.to_bytes(), bytes);
+        }
+    }
+
+    #[test]
+    fn borrowed() {
+        let digits: CharTrie<'static> = CharTrie::from_bytes(&DIGITS.0).unwrap();
+        assert!(digits.ranges().eq(Some(crate::CharRange::closed('0', '9'))));
+        assert_eq!(digits.to_bytes(), DIGITS.0);
+    }
+
+    #[test]
+    fn errors() {
+        let bytes = NARROW.to_bytes();
+        let len = bytes.len();
+        assert_eq!(load(&bytes[..4], 0).unwrap_err(), BytesError::BadHeader);
+        assert_eq!(
+            load(&bytes[..len - 1], 0).unwrap_err(),
+            BytesError::BadLength,
+        );
+        assert_eq!(
+            load(&[&bytes[..], &[0; 8]].concat(), 0).unwrap_err(),
+            BytesError::BadLength,
+        );
+        assert_eq!(load(&bytes, 1).unwrap_err(), BytesError::Misaligned);
+
+        let mut corrupt = bytes.clone();
+        corrupt[4..8].copy_from_slice(&ENDIANNESS_TAG.swap_bytes().to_ne_bytes());
+        assert_eq!(load(&corrupt, 0).unwrap_err(), BytesError::WrongEndianness);
+
+        let mut corrupt = bytes.clone();
+        corrupt[8..12].copy_from_slice(&3u32.to_ne_bytes());
+        assert_eq!(load(&corrupt, 0).unwrap_err(), BytesError::BadHeader);
+
+        // the first index of level2, after the layout, level1, 3 leaves, and level3
+        let mut corrupt = bytes;
+        corrupt[8 + 16 + 256 + 24 + 256] = 3;
+        assert_eq!(
+            load(&corrupt, 0).unwrap_err(),
+            BytesError::InvalidTrie(TrieError::Level2(0)),
+        );
+    }
+}
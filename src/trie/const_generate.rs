@@ -0,0 +1,280 @@
+use crate::{
+    trie::{chunk_mask, CharTrie},
+    CharRange,
+};
+
+/// The tables of a trie, computed during const evaluation from sorted ranges.
+///
+/// This is the machinery behind `generate_const!`, which first computes the
+/// tables with room for the most leaves and level3 blocks, then shrinks them
+/// to the number actually used. Only 8 bit indices are supported, so sets with
+/// more than 256 distinct leaves or level3 blocks fail to compile, and still
+/// need `trie::generate`.
+#[derive(Copy, Clone, Debug)]
+pub struct ConstTables<const LEAVES: usize = 256, const BLOCKS: usize = 256> {
+    level1: [u64; 32],
+    level2: [u8; 992],
+    level3: [u8; 256],
+    blocks: [[u8; 64]; BLOCKS],
+    leaves: [u64; LEAVES],
+    leaf_count: usize,
+    block_count: usize,
+}
+
+impl ConstTables {
+    /// Compute the tables of a trie holding every codepoint of some ranges.
+    ///
+    /// The ranges must be sorted and must not overlap, as for `CharSet::from_raw`;
+    /// empty ranges are ignored. Panics if they are not sorted, or if the set
+    /// needs more than 256 distinct leaves or level3 blocks.
+    pub const fn from_ranges(ranges: &[CharRange]) -> Self {
+        let mut tables = ConstTables {
+            level1: [0; 32],
+            level2: [0; 992],
+            level3: [0; 256],
+            blocks: [[0; 64]; 256],
+            leaves: [0; 256],
+            leaf_count: 0,
+            block_count: 0,
+        };
+
+        let mut i = 0;
+        let mut prev: Option<u32> = None;
+        while i < ranges.len() {
+            if !ranges[i].is_empty() {
+                if let Some(prev) = prev {
+                    if prev >= ranges[i].low as u32 {
+                        panic!("ranges are not sorted");
+                    }
+                }
+                prev = Some(ranges[i].high as u32);
+            }
+            i += 1;
+        }
+
+        let mut cursor = 0;
+        let mut idx = 0;
+        while idx < 32 {
+            tables.level1[idx] = chunk(ranges, &mut cursor, idx as u32 * 64);
+            idx += 1;
+        }
+        let mut idx = 0;
+        while idx < 992 {
+            let chunk = chunk(ranges, &mut cursor, 0x800 + idx as u32 * 64);
+            tables.level2[idx] = tables.leaf_index(chunk);
+            idx += 1;
+        }
+        let mut block = 0;
+        while block < 256 {
+            let mut indices = [0; 64];
+            let mut idx = 0;
+            while idx < 64 {
+                let base = 0x10000 + block as u32 * 0x1000 + idx as u32 * 64;
+                indices[idx] = tables.leaf_index(chunk(ranges, &mut cursor, base));
+                idx += 1;
+            }
+            tables.level3[block] = tables.block_index(indices);
+            block += 1;
+        }
+        tables
+    }
+
+    /// The index of a leaf, adding it if it is new.
+    const fn leaf_index(&mut self, chunk: u64) -> u8 {
+        let mut idx = 0;
+        while idx < self.leaf_count {
+            if self.leaves[idx] == chunk {
+                return idx as u8;
+            }
+            idx += 1;
+        }
+        if self.leaf_count == 256 {
+            panic!("trie needs more than 256 distinct leaves");
+        }
+        self.leaves[idx] = chunk;
+        self.leaf_count += 1;
+        idx as u8
+    }
+
+    /// The index of a level3 block, adding it if it is new.
+    const fn block_index(&mut self, indices: [u8; 64]) -> u8 {
+        let mut idx = 0;
+        'blocks: while idx < self.block_count {
+            let mut i = 0;
+            while i < 64 {
+                if self.blocks[idx][i] != indices[i] {
+                    idx += 1;
+                    continue 'blocks;
+                }
+                i += 1;
+            }
+            return idx as u8;
+        }
+        // there are exactly 256 level3 blocks, so this never runs out
+        self.blocks[idx] = indices;
+        self.block_count += 1;
+        idx as u8
+    }
+}
+
+impl<const LEAVES: usize, const BLOCKS: usize> ConstTables<LEAVES, BLOCKS> {
+    /// The number of distinct leaves the trie needs.
+    pub const fn leaf_count(&self) -> usize {
+        self.leaf_count
+    }
+
+    /// The number of distinct level3 blocks the trie needs.
+    pub const fn block_count(&self) -> usize {
+        self.block_count
+    }
+
+    /// Copy these tables into tables sized for exactly the leaves and blocks in use.
+    ///
+    /// Panics if `L` and `B` are not `leaf_count` and `block_count`.
+    pub const fn shrink<const L: usize, const B: usize>(&self) -> ConstTables<L, B> {
+        assert!(L == self.leaf_count && B == self.block_count);
+        let mut tables = ConstTables {
+            level1: self.level1,
+            level2: self.level2,
+            level3: self.level3,
+            blocks: [[0; 64]; B],
+            leaves: [0; L],
+            leaf_count: L,
+            block_count: B,
+        };
+        let mut idx = 0;
+        while idx < L {
+            tables.leaves[idx] = self.leaves[idx];
+            idx += 1;
+        }
+        let mut idx = 0;
+        while idx < B {
+            tables.blocks[idx] = self.blocks[idx];
+            idx += 1;
+        }
+        tables
+    }
+
+    /// Borrow these tables as a trie.
+    pub const fn as_trie(&'static self) -> CharTrie<'static> {
+        CharTrie::from_raw(
+            &self.level1,
+            &self.level2,
+            (&self.level3, &self.blocks),
+            &self.leaves,
+        )
+    }
+}
+
+/// The membership bits of the chunk of 64 codepoints at `base`.
+///
+/// `cursor` is the first range which might overlap this or a later chunk,
+/// so walking the chunks in order visits each range a bounded number of times.
+const fn chunk(ranges: &[CharRange], cursor: &mut usize, base: u32) -> u64 {
+    while *cursor < ranges.len()
+        && (ranges[*cursor].is_empty() || (ranges[*cursor].high as u32) < base)
+    {
+        *cursor += 1;
+    }
+    let mut chunk = 0;
+    let mut i = *cursor;
+    while i < ranges.len() {
+        // an empty range's low is `char::MAX`, so would end the walk early
+        if ranges[i].is_empty() {
+            i += 1;
+            continue;
+        }
+        if ranges[i].low as u32 >= base + 64 {
+            break;
+        }
+        chunk |= chunk_mask(base, ranges[i].low as u32, ranges[i].high as u32);
+        i += 1;
+    }
+    chunk
+}
+
+/// Generate a static trie from sorted ranges of codepoints during const evaluation.
+///
+/// This evaluates to a `CharTrie<'static>`, and can initialize a `static`,
+/// without a build script or procedural macro. The ranges are a const expression
+/// of type `&[CharRange]`, sorted and not overlapping, as for `CharSet::from_raw`.
+/// Sets which need more than 256 distinct leaves fail to compile, and need
+/// `trie::generate` for its 16 bit indices.
+///
+/// # Examples
+///
+/// ```
+/// use mileage::{trie::CharTrie, CharRange};
+///
+/// static HEX: CharTrie<'static> = mileage::generate_const!(&[
+///     CharRange::closed('0', '9'),
+///     CharRange::closed('A', 'F'),
+///     CharRange::closed('a', 'f'),
+/// ]);
+/// assert!(HEX.contains('7'));
+/// assert!(HEX.contains('c'));
+/// assert!(!HEX.contains('g'));
+/// ```
+#[macro_export]
+macro_rules! generate_const {
+    ($ranges:expr $(,)?) => {{
+        const FULL: $crate::trie::ConstTables = $crate::trie::ConstTables::from_ranges($ranges);
+        static TABLES: $crate::trie::ConstTables<{ FULL.leaf_count() }, { FULL.block_count() }> =
+            FULL.shrink();
+        TABLES.as_trie()
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_ranges() {
+        #[rustfmt::skip]
+        static RANGES: [CharRange; 6] = [
+            CharRange::closed('0', '9'),
+            CharRange::closed('\u{7C0}', '\u{840}'),
+            CharRange::closed('\u{D000}', '\u{D7FF}'),
+            CharRange::closed('\u{E000}', '\u{E03F}'),
+            CharRange::closed('\u{1F600}', '\u{1F64F}'),
+            CharRange::closed('\u{10FFFF}', '\u{10FFFF}'),
+        ];
+        static TRIE: CharTrie<'static> = crate::generate_const!(&RANGES);
+        assert_eq!(TRIE.validate(), Ok(()));
+        assert!(TRIE.ranges().eq(RANGES.iter().copied()));
+
+        const FULL: ConstTables = ConstTables::from_ranges(&RANGES);
+        // empty, full, U+0840, the end of U+1F600..=U+1F64F, and U+10FFFF
+        assert_eq!(FULL.leaf_count(), 5);
+        // the empty block, and those of U+1F600 and U+10FFFF
+        assert_eq!(FULL.block_count(), 3);
+
+        static EMPTY: CharTrie<'static> = crate::generate_const!(&[]);
+        assert_eq!(EMPTY.ranges().next(), None);
+        assert_eq!(EMPTY.memory_footprint(), 32 * 8 + 992 + 256 + 64 + 8);
+    }
+
+    #[test]
+    fn empty_ranges() {
+        static RANGES: [CharRange; 4] = [
+            CharRange::closed('a', 'a'),
+            CharRange::empty(),
+            CharRange::closed('b', 'b'),
+            CharRange::closed('\u{1F600}', '\u{1F600}'),
+        ];
+        static TRIE: CharTrie<'static> = crate::generate_const!(&RANGES);
+        let expected = [
+            CharRange::closed('a', 'b'),
+            CharRange::singleton('\u{1F600}'),
+        ];
+        assert!(TRIE.ranges().eq(expected.iter().copied()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn unsorted() {
+        let _ =
+            ConstTables::from_ranges(&[CharRange::closed('b', 'c'), CharRange::closed('a', 'a')]);
+    }
+}
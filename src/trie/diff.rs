@@ -0,0 +1,139 @@
+#[cfg(all(feature = "set", feature = "owned-set"))]
+use crate::set::{CharSet, CharSetBuf};
+use {
+    crate::{trie::CharTrie, CharRange, AFTER_SURROGATE, BEFORE_SURROGATE},
+    alloc::vec::Vec,
+    core::char,
+};
+
+/// The ranges of codepoints where a trie's membership bits differ from `chunks`,
+/// the membership bits of every chunk of 64 codepoints in order.
+///
+/// The chunks of surrogates are skipped, as they are never looked up.
+pub(crate) fn mismatches(trie: &CharTrie<'_>, chunks: impl Iterator<Item = u64>) -> Vec<CharRange> {
+    let mut ranges: Vec<CharRange> = Vec::new();
+    let bases = (0..=char::MAX as u32).step_by(64);
+    for (base, chunk) in bases.zip(chunks) {
+        let surrogate = (BEFORE_SURROGATE as u32..AFTER_SURROGATE as u32).contains(&base);
+        let mut bits = if surrogate {
+            0
+        } else {
+            chunk ^ trie.chunk(base)
+        };
+        // each run of set bits is one range, which may continue from the last chunk
+        while bits != 0 {
+            let first = bits.trailing_zeros();
+            let len = (bits >> first).trailing_ones();
+            bits &= (!0u64).checked_shl(first + len).unwrap_or(0);
+            let (low, high) = (base + first, base + first + len - 1);
+            let (low, high) = (char::from_u32(low).unwrap(), char::from_u32(high).unwrap());
+            match ranges.last_mut() {
+                Some(r) if r.high as u32 + 1 == low as u32 => r.high = high,
+                _ => ranges.push(CharRange::closed(low, high)),
+            }
+        }
+    }
+    ranges
+}
+
+/// The codepoints which are in exactly one of a trie and a set.
+///
+/// The membership bits of each chunk of 64 codepoints are compared in one step,
+/// so this costs the same as iterating the trie, however large either side is.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::{set::{CharSet, CharSetBuf}, trie::{self, CharTrie}, CharRange};
+/// static LEVEL1: [u64; 32] = {
+///     let mut level1 = [0; 32];
+///     level1[0] = 0x03FF << 0x30;
+///     level1
+/// };
+/// static DIGITS: CharTrie<'static> =
+///     CharTrie::from_raw(&LEVEL1, &[0; 992], (&[0; 256], &[[0; 64]]), &[0]);
+///
+/// static HEX: [CharRange; 2] = [CharRange::closed('1', '9'), CharRange::closed('A', 'F')];
+/// assert_eq!(trie::diff(&DIGITS, CharSet::from_raw(&HEX)), CharSetBuf::from(&['0'..='0', 'A'..='F']));
+/// ```
+#[cfg(all(feature = "set", feature = "owned-set"))]
+pub fn diff(trie: &CharTrie<'_>, set: &CharSet) -> CharSetBuf {
+    CharSetBuf::from_sorted_ranges_unchecked(mismatches(trie, set.chunks()))
+}
+
+#[cfg(all(test, feature = "set", feature = "owned-set"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff() {
+        const LEVEL2: [u8; 992] = {
+            let mut level2 = [0; 992];
+            level2[0] = 1;
+            level2
+        };
+        const LEVEL3: [u8; 256] = {
+            let mut level3 = [0; 256];
+            level3[255] = 1;
+            level3
+        };
+        const CHONK: [u8; 64] = {
+            let mut chonk = [0; 64];
+            chonk[63] = 1;
+            chonk
+        };
+        // U+0800..=U+083F and U+10FFC0..=U+10FFFF
+        static TRIE: CharTrie<'static> =
+            CharTrie::from_raw(&[0; 32], &LEVEL2, (&LEVEL3, &[[0; 64], CHONK]), &[0, !0]);
+
+        let r = CharRange::closed;
+        let same = [r('\u{800}', '\u{83F}'), r('\u{10FFC0}', char::MAX)];
+        assert_eq!(
+            super::diff(&TRIE, CharSet::from_raw(&same)),
+            CharSetBuf::new()
+        );
+
+        // runs which continue into the next chunk are merged
+        let set = [
+            r('a', 'a'),
+            r('\u{7C0}', '\u{81F}'),
+            r('\u{840}', '\u{87F}'),
+        ];
+        let mut set = CharSetBuf::from_sorted_ranges_unchecked(set.to_vec());
+        set.insert('\u{10FFBF}');
+        assert_eq!(
+            super::diff(&TRIE, &set).ranges().collect::<Vec<_>>(),
+            [
+                r('a', 'a'),
+                r('\u{7C0}', '\u{7FF}'),
+                r('\u{820}', '\u{87F}'),
+                r('\u{10FFBF}', char::MAX),
+            ],
+        );
+        // two runs in one chunk, the second ending at its last bit
+        let set = [r('a', 'c'), r('x', '\x7F')];
+        assert_eq!(
+            super::diff(&TRIE, CharSet::from_raw(&set))
+                .ranges()
+                .collect::<Vec<_>>(),
+            [
+                r('a', 'c'),
+                r('x', '\x7F'),
+                r('\u{800}', '\u{83F}'),
+                r('\u{10FFC0}', char::MAX),
+            ],
+        );
+        // the surrogates are in neither, so never differ
+        let everything = [r('\0', char::MAX)];
+        assert_eq!(
+            super::diff(&TRIE, CharSet::from_raw(&everything))
+                .ranges()
+                .collect::<Vec<_>>(),
+            [
+                r('\0', '\u{7FF}'),
+                r('\u{840}', '\u{D7FF}'),
+                r('\u{E000}', '\u{10FFBF}'),
+            ],
+        );
+    }
+}
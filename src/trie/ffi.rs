@@ -0,0 +1,168 @@
+use {
+    crate::trie::{CharTrie, Levels},
+    core::{char, ffi::c_void, slice},
+};
+
+/// The raw parts of a `CharTrie`, in a layout stable for use from C.
+///
+/// This is the C struct
+///
+/// ```c
+/// struct RawTrie {
+///     const uint64_t *level1;       // 32 chunks
+///     const void *level2;           // 992 indices
+///     const uint8_t *level3_first;  // 256 block indices
+///     const void *level3_blocks;    // level3_block_count blocks of 64 indices
+///     size_t level3_block_count;
+///     const uint64_t *leaves;       // leaf_count chunks
+///     size_t leaf_count;
+///     bool wide;                    // indices are uint16_t rather than uint8_t
+/// };
+/// ```
+///
+/// and `mileage_trie_contains` is
+///
+/// ```c
+/// bool mileage_trie_contains(const struct RawTrie *trie, uint32_t cp);
+/// ```
+///
+/// The tables are as described by `CharTrie::from_raw` and `CharTrie::from_raw_wide`,
+/// with the indices of level2 and of the level3 blocks as `uint16_t` if `wide`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct RawTrie {
+    /// The membership bits of the first 32 chunks.
+    pub level1: *const u64,
+    /// The 992 indices into the leaves for U+0800..U+FFFF, as `u8` or `u16`.
+    pub level2: *const c_void,
+    /// The 256 indices into the level3 blocks for U+10000..=U+10FFFF.
+    pub level3_first: *const u8,
+    /// The level3 blocks of 64 indices into the leaves each, as `u8` or `u16`.
+    pub level3_blocks: *const c_void,
+    /// The number of level3 blocks.
+    pub level3_block_count: usize,
+    /// The membership bits of the distinct chunks from U+0800.
+    pub leaves: *const u64,
+    /// The number of leaves.
+    pub leaf_count: usize,
+    /// Whether the indices into the leaves are `u16` rather than `u8`.
+    pub wide: bool,
+}
+
+impl CharTrie<'_> {
+    /// The raw parts of this trie, for use from C.
+    ///
+    /// The pointers borrow the tables of this trie.
+    pub fn to_raw(&self) -> RawTrie {
+        let (level2, level3_first, level3_blocks, level3_block_count, wide) = match self.levels {
+            Levels::Narrow(level2, level3) => (
+                level2.as_ptr() as *const c_void,
+                level3.0.as_ptr(),
+                level3.1.as_ptr() as *const c_void,
+                level3.1.len(),
+                false,
+            ),
+            Levels::Wide(level2, level3) => (
+                level2.as_ptr() as *const c_void,
+                level3.0.as_ptr(),
+                level3.1.as_ptr() as *const c_void,
+                level3.1.len(),
+                true,
+            ),
+        };
+        RawTrie {
+            level1: self.level1.as_ptr(),
+            level2,
+            level3_first,
+            level3_blocks,
+            level3_block_count,
+            leaves: self.leaves.as_ptr(),
+            leaf_count: self.leaves.len(),
+            wide,
+        }
+    }
+}
+
+impl RawTrie {
+    /// Borrow the trie of these raw parts.
+    ///
+    /// # Safety
+    ///
+    /// The pointers must point to tables of the lengths described on `RawTrie`,
+    /// which stay valid and unchanged for `'a`.
+    #[allow(unsafe_code)]
+    pub unsafe fn as_trie<'a>(&self) -> CharTrie<'a> {
+        // SAFETY: the caller guarantees the tables and their lengths.
+        unsafe {
+            let level1 = &*(self.level1 as *const [u64; 32]);
+            let level3_first = &*(self.level3_first as *const [u8; 256]);
+            let leaves = slice::from_raw_parts(self.leaves, self.leaf_count);
+            if self.wide {
+                let level2 = &*(self.level2 as *const [u16; 992]);
+                let blocks = self.level3_blocks as *const [u16; 64];
+                let blocks = slice::from_raw_parts(blocks, self.level3_block_count);
+                CharTrie::from_raw_wide(level1, level2, (level3_first, blocks), leaves)
+            } else {
+                let level2 = &*(self.level2 as *const [u8; 992]);
+                let blocks = self.level3_blocks as *const [u8; 64];
+                let blocks = slice::from_raw_parts(blocks, self.level3_block_count);
+                CharTrie::from_raw(level1, level2, (level3_first, blocks), leaves)
+            }
+        }
+    }
+}
+
+/// Does the trie of these raw parts contain this codepoint?
+///
+/// This is false for surrogates and for values past U+10FFFF.
+///
+/// # Safety
+///
+/// `trie` must point to a `RawTrie` as required by `RawTrie::as_trie`,
+/// whose indices are all in bounds, as checked by `CharTrie::validate`.
+#[no_mangle]
+#[allow(unsafe_code)]
+pub unsafe extern "C" fn mileage_trie_contains(trie: *const RawTrie, cp: u32) -> bool {
+    match char::from_u32(cp) {
+        // SAFETY: the caller guarantees the trie.
+        Some(c) => unsafe { (*trie).as_trie() }.contains(c),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::CharRange};
+
+    #[test]
+    #[allow(unsafe_code)]
+    fn contains() {
+        static LEVEL1: [u64; 32] = {
+            let mut level1 = [0; 32];
+            level1[1] = !0;
+            level1
+        };
+        static NARROW: CharTrie<'static> =
+            CharTrie::from_raw(&LEVEL1, &[1; 992], (&[0; 256], &[[0; 64]]), &[0, 0b1010]);
+        static WIDE: CharTrie<'static> = CharTrie::from_raw_wide(
+            &LEVEL1,
+            &[0; 992],
+            (&[1; 256], &[[0; 64], [1; 64]]),
+            &[0, 0b1010],
+        );
+
+        for trie in &[NARROW, WIDE] {
+            let raw = trie.to_raw();
+            for c in CharRange::from(..) {
+                // SAFETY: the raw parts borrow a static trie.
+                let contains = unsafe { mileage_trie_contains(&raw, c as u32) };
+                assert_eq!(contains, trie.contains(c), "{:?}", c);
+            }
+            // SAFETY: the raw parts borrow a static trie.
+            unsafe {
+                assert!(!mileage_trie_contains(&raw, 0xD801));
+                assert!(!mileage_trie_contains(&raw, 0x11_0001));
+            }
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use {
+    crate::{trie::CharTrie, CharRange},
+    core::{char, iter},
+};
+
+/// An iterator over the compact ranges of a trie.
+///
+/// Constructed via `CharTrie::ranges`. See `CharTrie` for more information.
+#[derive(Copy, Clone, Debug)]
+pub struct RangeIter<'a> {
+    trie: CharTrie<'a>,
+    next: u32,
+}
+
+impl<'a> CharTrie<'a> {
+    /// Iterate the compact ranges of this set.
+    ///
+    /// Ranges are found by scanning the membership bits, so iterating
+    /// a trie looks at every chunk of 64 codepoints once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{trie::CharTrie, CharRange};
+    /// static LEVEL2: [u8; 992] = [0; 992];
+    /// static ASCII_LETTERS: CharTrie<'static> = CharTrie::from_raw(
+    ///     &[0, 0x07FF_FFFE_07FF_FFFE, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    ///       0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    ///     &LEVEL2,
+    ///     (&[0; 256], &[[0; 64]]),
+    ///     &[0],
+    /// );
+    /// assert!(ASCII_LETTERS.ranges().eq([
+    ///     CharRange::from('A'..='Z'),
+    ///     CharRange::from('a'..='z'),
+    /// ]));
+    /// ```
+    pub fn ranges(&self) -> RangeIter<'a> {
+        RangeIter {
+            trie: *self,
+            next: 0,
+        }
+    }
+}
+
+/// An iterator over the codepoints of a trie.
+///
+/// Constructed via `CharTrie::chars`. See `CharTrie` for more information.
+#[derive(Copy, Clone, Debug)]
+pub struct Chars<'a> {
+    trie: CharTrie<'a>,
+    /// The lowest codepoint not yet yielded from the front.
+    front: u32,
+    /// One past the highest codepoint not yet yielded from the back.
+    back: u32,
+}
+
+impl<'a> CharTrie<'a> {
+    /// Iterate the codepoints of this set.
+    ///
+    /// Like `ranges`, this scans the membership bits a chunk at a time,
+    /// from the bottom up, or from the top down when iterated in reverse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::trie::CharTrie;
+    /// static LEVEL1: [u64; 32] = {
+    ///     let mut level1 = [0; 32];
+    ///     level1[0] = 0x03FF << 0x30;
+    ///     level1
+    /// };
+    /// static DIGITS: CharTrie<'static> =
+    ///     CharTrie::from_raw(&LEVEL1, &[0; 992], (&[0; 256], &[[0; 64]]), &[0]);
+    /// assert!(DIGITS.chars().eq('0'..='9'));
+    /// assert_eq!(DIGITS.chars().next_back(), Some('9'));
+    /// assert!(DIGITS.chars().rev().eq(('0'..='9').rev()));
+    /// ```
+    pub fn chars(&self) -> Chars<'a> {
+        Chars {
+            trie: *self,
+            front: 0,
+            back: char::MAX as u32 + 1,
+        }
+    }
+}
+
+/// The membership bits of the chunk holding `c`, ignoring any bits set for surrogates.
+fn bits(trie: &CharTrie<'_>, c: u32) -> u64 {
+    if (0xD800..0xE000).contains(&c) {
+        0
+    } else {
+        trie.chunk(c)
+    }
+}
+
+impl Iterator for Chars<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        while self.front < self.back {
+            let bits = bits(&self.trie, self.front) >> (self.front & 0b_111_111);
+            if bits == 0 {
+                self.front = (self.front | 0b_111_111) + 1;
+                continue;
+            }
+            let c = self.front + bits.trailing_zeros();
+            if c >= self.back {
+                break;
+            }
+            self.front = c + 1;
+            return char::from_u32(c);
+        }
+        self.front = self.back;
+        None
+    }
+
+    fn last(mut self) -> Option<char> {
+        self.next_back()
+    }
+}
+
+impl DoubleEndedIterator for Chars<'_> {
+    fn next_back(&mut self) -> Option<char> {
+        while self.front < self.back {
+            let last = self.back - 1;
+            let bits = bits(&self.trie, last) << (63 - (last & 0b_111_111));
+            if bits == 0 {
+                self.back = last & !0b_111_111;
+                continue;
+            }
+            let c = last - bits.leading_zeros();
+            if c < self.front {
+                break;
+            }
+            self.back = c;
+            return char::from_u32(c);
+        }
+        self.back = self.front;
+        None
+    }
+}
+
+impl iter::FusedIterator for Chars<'_> {}
+
+impl RangeIter<'_> {
+    /// The membership bits of the chunk holding `c`, ignoring any bits set for surrogates.
+    fn bits(&self, c: u32) -> u64 {
+        bits(&self.trie, c)
+    }
+
+    /// The first codepoint from `c` for which `f(bits)` has its bit set,
+    /// or one past `char::MAX` if there is none.
+    fn seek(&self, mut c: u32, f: impl Fn(u64) -> u64) -> u32 {
+        while c <= char::MAX as u32 {
+            let bits = f(self.bits(c)) >> (c & 0b_111_111);
+            if bits == 0 {
+                c = (c | 0b_111_111) + 1;
+            } else {
+                return c + bits.trailing_zeros();
+            }
+        }
+        c
+    }
+}
+
+impl Iterator for RangeIter<'_> {
+    type Item = CharRange;
+
+    fn next(&mut self) -> Option<CharRange> {
+        let low = self.seek(self.next, |bits| bits);
+        if low > char::MAX as u32 {
+            self.next = low;
+            return None;
+        }
+        let high = self.seek(low, |bits| !bits);
+        self.next = high;
+        let (low, high) = (char::from_u32(low)?, char::from_u32(high - 1)?);
+        Some(CharRange::closed(low, high))
+    }
+}
+
+impl iter::FusedIterator for RangeIter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, alloc::vec::Vec};
+
+    #[test]
+    fn ranges() {
+        // ASCII, U+0800..=U+083F, U+D7C0..=U+D7FF, U+E000, U+10001, U+10003,
+        // and U+10FFC0..=U+10FFFF, with junk bits set for the surrogates
+        const LEVEL2: [u8; 992] = {
+            let mut level2 = [0; 992];
+            level2[0] = 1;
+            let mut idx = (0xD7C0 - 0x800) >> 6;
+            while idx < (0xE000 - 0x800) >> 6 {
+                level2[idx] = 1;
+                idx += 1;
+            }
+            level2[(0xE000 - 0x800) >> 6] = 3;
+            level2
+        };
+        const LEVEL3: [u8; 256] = {
+            let mut level3 = [0; 256];
+            level3[0] = 1;
+            level3[255] = 2;
+            level3
+        };
+        const CHONK: [u8; 64] = {
+            let mut chonk = [0; 64];
+            chonk[0] = 2;
+            chonk
+        };
+        const LAST_CHONK: [u8; 64] = {
+            let mut chonk = [0; 64];
+            chonk[63] = 1;
+            chonk
+        };
+        static TRIE: CharTrie<'static> = CharTrie::from_raw(
+            &[
+                !0, !0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0,
+            ],
+            &LEVEL2,
+            (&LEVEL3, &[[0; 64], CHONK, LAST_CHONK]),
+            &[0, !0, 0b1010, 1],
+        );
+
+        let r = CharRange::closed;
+        assert_eq!(
+            TRIE.ranges().collect::<Vec<_>>(),
+            [
+                r('\0', '\x7F'),
+                r('\u{800}', '\u{83F}'),
+                r('\u{D7C0}', '\u{D7FF}'),
+                r('\u{E000}', '\u{E000}'),
+                r('\u{10001}', '\u{10001}'),
+                r('\u{10003}', '\u{10003}'),
+                r('\u{10FFC0}', char::MAX),
+            ],
+        );
+        let mut ranges = TRIE.ranges();
+        ranges.by_ref().for_each(drop);
+        assert_eq!(ranges.next(), None);
+
+        let brute = CharRange::from(..).iter().filter(|&c| TRIE.contains(c));
+        assert!(TRIE.ranges().flat_map(CharRange::iter).eq(brute.clone()));
+
+        assert!(TRIE.chars().eq(brute.clone()));
+        assert!(TRIE.chars().rev().eq(brute.clone().rev()));
+        assert_eq!(TRIE.chars().last(), Some(char::MAX));
+        // the ends meet in the middle, whichever chunk it falls in
+        let mut chars = TRIE.chars();
+        let members: Vec<char> = brute.collect();
+        for (&lo, &hi) in members
+            .iter()
+            .zip(members.iter().rev())
+            .take(members.len() / 2)
+        {
+            assert_eq!(chars.next(), Some(lo));
+            assert_eq!(chars.next_back(), Some(hi));
+        }
+        if members.len() % 2 == 1 {
+            assert_eq!(chars.next(), Some(members[members.len() / 2]));
+        }
+        assert_eq!(chars.next(), None);
+        assert_eq!(chars.next_back(), None);
+    }
+}
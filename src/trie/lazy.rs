@@ -0,0 +1,208 @@
+#[cfg(feature = "set")]
+use crate::set::CharSet;
+use crate::trie::CharTrie;
+#[cfg(feature = "owned-trie")]
+use crate::trie::CharTrieBuf;
+
+/// A set of codepoints which a lazy combinator can look up in.
+///
+/// Each variant borrows its set, so combinators can be nested and built
+/// in statics without allocating.
+#[derive(Copy, Clone, Debug)]
+pub enum Operand<'a> {
+    /// A trie.
+    Trie(&'a CharTrie<'a>),
+    /// An owned trie.
+    #[cfg(feature = "owned-trie")]
+    TrieBuf(&'a CharTrieBuf),
+    /// A set.
+    #[cfg(feature = "set")]
+    Set(&'a CharSet),
+    /// The union of two sets.
+    Union(&'a Union<'a>),
+    /// The intersection of two sets.
+    Intersection(&'a Intersection<'a>),
+    /// The complement of a set.
+    Complement(&'a Complement<'a>),
+}
+
+impl Operand<'_> {
+    /// Does this set contain this codepoint?
+    pub fn contains(&self, c: char) -> bool {
+        match self {
+            Operand::Trie(trie) => trie.contains(c),
+            #[cfg(feature = "owned-trie")]
+            Operand::TrieBuf(trie) => trie.contains(c),
+            #[cfg(feature = "set")]
+            Operand::Set(set) => set.contains(c),
+            Operand::Union(union) => union.contains(c),
+            Operand::Intersection(intersection) => intersection.contains(c),
+            Operand::Complement(complement) => complement.contains(c),
+        }
+    }
+}
+
+impl<'a> From<&'a CharTrie<'a>> for Operand<'a> {
+    fn from(trie: &'a CharTrie<'a>) -> Self {
+        Operand::Trie(trie)
+    }
+}
+
+#[cfg(feature = "owned-trie")]
+impl<'a> From<&'a CharTrieBuf> for Operand<'a> {
+    fn from(trie: &'a CharTrieBuf) -> Self {
+        Operand::TrieBuf(trie)
+    }
+}
+
+#[cfg(feature = "set")]
+impl<'a> From<&'a CharSet> for Operand<'a> {
+    fn from(set: &'a CharSet) -> Self {
+        Operand::Set(set)
+    }
+}
+
+impl<'a> From<&'a Union<'a>> for Operand<'a> {
+    fn from(union: &'a Union<'a>) -> Self {
+        Operand::Union(union)
+    }
+}
+
+impl<'a> From<&'a Intersection<'a>> for Operand<'a> {
+    fn from(intersection: &'a Intersection<'a>) -> Self {
+        Operand::Intersection(intersection)
+    }
+}
+
+impl<'a> From<&'a Complement<'a>> for Operand<'a> {
+    fn from(complement: &'a Complement<'a>) -> Self {
+        Operand::Complement(complement)
+    }
+}
+
+/// The codepoints in either of two sets, looked up lazily.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::trie::{CharTrie, Complement, Intersection, Operand};
+/// static ASCII: CharTrie<'static> = CharTrie::from_raw(
+///     &[!0, !0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+///     &[0; 992],
+///     (&[0; 256], &[[0; 64]]),
+///     &[0],
+/// );
+/// static DIGITS: CharTrie<'static> = CharTrie::from_raw(
+///     &[0x03FF << 48, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+///     &[0; 992],
+///     (&[0; 256], &[[0; 64]]),
+///     &[0],
+/// );
+/// static NOT_DIGITS: Complement<'_> = Complement::new(Operand::Trie(&DIGITS));
+/// static ASCII_NOT_DIGITS: Intersection<'_> =
+///     Intersection::new(Operand::Trie(&ASCII), Operand::Complement(&NOT_DIGITS));
+///
+/// assert!(ASCII_NOT_DIGITS.contains('a'));
+/// assert!(!ASCII_NOT_DIGITS.contains('7'));
+/// assert!(!ASCII_NOT_DIGITS.contains('\u{E9}'));
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Union<'a> {
+    a: Operand<'a>,
+    b: Operand<'a>,
+}
+
+impl<'a> Union<'a> {
+    /// The union of two sets.
+    pub const fn new(a: Operand<'a>, b: Operand<'a>) -> Self {
+        Union { a, b }
+    }
+
+    /// Does either set contain this codepoint?
+    pub fn contains(&self, c: char) -> bool {
+        self.a.contains(c) || self.b.contains(c)
+    }
+}
+
+/// The codepoints in both of two sets, looked up lazily.
+///
+/// See `Union` for an example.
+#[derive(Copy, Clone, Debug)]
+pub struct Intersection<'a> {
+    a: Operand<'a>,
+    b: Operand<'a>,
+}
+
+impl<'a> Intersection<'a> {
+    /// The intersection of two sets.
+    pub const fn new(a: Operand<'a>, b: Operand<'a>) -> Self {
+        Intersection { a, b }
+    }
+
+    /// Do both sets contain this codepoint?
+    pub fn contains(&self, c: char) -> bool {
+        self.a.contains(c) && self.b.contains(c)
+    }
+}
+
+/// The codepoints not in a set, looked up lazily.
+///
+/// See `Union` for an example.
+#[derive(Copy, Clone, Debug)]
+pub struct Complement<'a> {
+    of: Operand<'a>,
+}
+
+impl<'a> Complement<'a> {
+    /// The complement of a set.
+    pub const fn new(of: Operand<'a>) -> Self {
+        Complement { of }
+    }
+
+    /// Does the set not contain this codepoint?
+    pub fn contains(&self, c: char) -> bool {
+        !self.of.contains(c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::CharRange};
+
+    #[test]
+    fn combinators() {
+        // ASCII
+        static ASCII: CharTrie<'static> = CharTrie::from_raw(
+            &[
+                !0, !0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0,
+            ],
+            &[0; 992],
+            (&[0; 256], &[[0; 64]]),
+            &[0],
+        );
+        // U+0040..=U+00BF
+        static MIDDLE: CharTrie<'static> = CharTrie::from_raw(
+            &[
+                0, !0, !0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0,
+            ],
+            &[0; 992],
+            (&[0; 256], &[[0; 64]]),
+            &[0],
+        );
+        let not_middle = Complement::new(Operand::Trie(&MIDDLE));
+        let union = Union::new((&ASCII).into(), (&MIDDLE).into());
+        let intersection = Intersection::new((&ASCII).into(), (&not_middle).into());
+        let nested = Union::new((&union).into(), (&intersection).into());
+
+        for c in CharRange::closed('\0', '\u{FFF}') {
+            let (ascii, middle) = (ASCII.contains(c), MIDDLE.contains(c));
+            assert_eq!(not_middle.contains(c), !middle);
+            assert_eq!(union.contains(c), ascii || middle);
+            assert_eq!(intersection.contains(c), ascii && !middle);
+            assert_eq!(nested.contains(c), ascii || middle);
+        }
+        assert!(not_middle.contains(char::MAX));
+    }
+}
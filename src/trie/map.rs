@@ -0,0 +1,290 @@
+#[cfg(feature = "new-trie")]
+use crate::{trie::Tables, CharRange};
+use {
+    crate::trie::{check_narrow, check_wide, chunk, Levels, Shape, TrieError},
+    core::fmt,
+};
+
+/// A compressed map from codepoints to small values, in the layout of `CharTrie`.
+///
+/// Where a `CharTrie` stores a bit for each codepoint, a `CharTrieMap` stores
+/// a `u8`, so each leaf holds the values of 64 codepoints rather than their bits.
+/// This suits enumerated properties such as General_Category, which are dense
+/// over the codepoints and would need very many ranges in a `CharMapRef`.
+///
+/// Every codepoint has a value; generation fills in a default for the rest.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::trie::CharTrieMap;
+/// const DIGITS: [u8; 64] = {
+///     let mut leaf = [0; 64];
+///     let mut i = 0;
+///     while i < 10 {
+///         leaf[0x30 + i] = 1;
+///         i += 1;
+///     }
+///     leaf
+/// };
+/// static LEVEL1: [[u8; 64]; 32] = {
+///     let mut level1 = [[0; 64]; 32];
+///     level1[0] = DIGITS;
+///     level1
+/// };
+/// static IS_DIGIT: CharTrieMap =
+///     CharTrieMap::from_raw(&LEVEL1, &[0; 992], (&[0; 256], &[[0; 64]]), &[[0; 64]]);
+/// assert_eq!(IS_DIGIT.get('7'), 1);
+/// assert_eq!(IS_DIGIT.get('x'), 0);
+/// ```
+#[derive(Copy, Clone)]
+pub struct CharTrieMap {
+    level1: &'static [[u8; 64]; 32],
+    levels: Levels<'static>,
+    leaves: &'static [[u8; 64]],
+}
+
+impl fmt::Debug for CharTrieMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (index, chonks) = match self.levels {
+            Levels::Narrow(_, level3) => ("u8", level3.1.len()),
+            Levels::Wide(_, level3) => ("u16", level3.1.len()),
+        };
+        f.debug_struct("CharTrieMap")
+            .field("level1", &Shape(format_args!("&[[u8; 64]; 32]")))
+            .field("level2", &Shape(format_args!("&[{}; 992]", index)))
+            .field(
+                "level3",
+                &Shape(format_args!(
+                    "(&[u8; 256], &[[{}; 64]; dyn {}])",
+                    index, chonks
+                )),
+            )
+            .field(
+                "level4",
+                &Shape(format_args!("&[[u8; 64]; dyn {}]", self.leaves.len())),
+            )
+            .finish()
+    }
+}
+
+impl CharTrieMap {
+    /// The value of this codepoint.
+    #[inline]
+    pub fn get(&self, c: char) -> u8 {
+        let c = c as u32;
+        let leaf = match self.levels {
+            Levels::Narrow(level2, level3) => chunk(self.level1, level2, level3, self.leaves, c),
+            Levels::Wide(level2, level3) => chunk(self.level1, level2, level3, self.leaves, c),
+        };
+        leaf[(c & 0b_111_111) as usize]
+    }
+
+    /// Create a codepoint map from components as `CharTrie::from_raw`,
+    /// but with leaves of 64 values.
+    pub const fn from_raw(
+        level1: &'static [[u8; 64]; 32],
+        level2: &'static [u8; 992],
+        level3: (&'static [u8; 256], &'static [[u8; 64]]),
+        leaves: &'static [[u8; 64]],
+    ) -> Self {
+        CharTrieMap {
+            level1,
+            levels: Levels::Narrow(level2, level3),
+            leaves,
+        }
+    }
+
+    /// Create a codepoint map from components as `CharTrie::from_raw_wide`,
+    /// but with leaves of 64 values.
+    pub const fn from_raw_wide(
+        level1: &'static [[u8; 64]; 32],
+        level2: &'static [u16; 992],
+        level3: (&'static [u8; 256], &'static [[u16; 64]]),
+        leaves: &'static [[u8; 64]],
+    ) -> Self {
+        CharTrieMap {
+            level1,
+            levels: Levels::Wide(level2, level3),
+            leaves,
+        }
+    }
+
+    /// Check that every index of this map is in bounds,
+    /// such that looking up any codepoint will not panic.
+    pub fn validate(&self) -> Result<(), TrieError> {
+        match self.levels {
+            Levels::Narrow(level2, level3) => check_narrow(level2, level3, self.leaves.len()),
+            Levels::Wide(level2, level3) => check_wide(level2, level3, self.leaves.len()),
+        }
+    }
+}
+
+#[cfg(feature = "new-trie")]
+impl Tables<[u8; 64]> {
+    /// An expression constructing the only `CharTrieMap` of these tables.
+    fn to_map_tokens(&self) -> proc_macro2::TokenStream {
+        use quote::quote;
+
+        let (level1, level2, first) = &self.tries[0];
+        let constructor = self.constructor();
+        let level1 = level1.iter().map(|leaf| quote!([#(#leaf),*]));
+        let level2 = self.level2_tokens(level2);
+        let second = self.chonk_tokens();
+        let leaves = self.leaves.iter().map(|leaf| quote!([#(#leaf),*]));
+        quote! {
+            CharTrieMap::#constructor(
+                &[#(#level1),*],
+                #level2,
+                (&[#(#first),*], &[#(#second),*]),
+                &[#(#leaves),*],
+            )
+        }
+    }
+}
+
+/// Generate the tables of a map from the value of each codepoint, as 64-codepoint leaves.
+///
+/// The surrogates have the value 0.
+#[cfg(feature = "new-trie")]
+fn generate_map_from_values(
+    values: &[u8],
+) -> Result<proc_macro2::TokenStream, super::GenerateError> {
+    use core::convert::TryInto;
+
+    let mut tables = Tables::new();
+    tables.push(values.chunks(64).map(|leaf| leaf.try_into().unwrap()))?;
+    Ok(tables.to_map_tokens())
+}
+
+/// Generate a new codepoint map from the value of each codepoint.
+///
+/// This constructs Rust code that is legal in expression position that
+/// evaluates to a `CharTrieMap`. Requires that `CharTrieMap` is in scope.
+///
+/// Maps with more than 256 distinct leaves fall back to 16 bit indices,
+/// as `CharTrieMap::from_raw_wide`.
+#[cfg(feature = "new-trie")]
+pub fn generate_map(
+    f: impl Fn(char) -> u8,
+) -> Result<proc_macro2::TokenStream, super::GenerateError> {
+    let values: alloc::vec::Vec<u8> = (0..=char::MAX as u32)
+        .map(|c| char::from_u32(c).map_or(0, &f))
+        .collect();
+    generate_map_from_values(&values)
+}
+
+/// Generate a new codepoint map from ranges of codepoints and their values.
+///
+/// This behaves as `generate_map`, but codepoints not in any range have
+/// the value `default`. Where ranges overlap, the later range wins.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::{trie::{generate_map, generate_map_from_ranges}, CharRange};
+/// let classes = [(CharRange::from('a'..='z'), 1), (CharRange::from('0'..='9'), 2)];
+/// assert_eq!(
+///     generate_map_from_ranges(classes.iter().copied(), 3)?.to_string(),
+///     generate_map(|c| match c {
+///         'a'..='z' => 1,
+///         '0'..='9' => 2,
+///         _ => 3,
+///     })?
+///     .to_string(),
+/// );
+/// # Ok::<(), mileage::trie::GenerateError>(())
+/// ```
+#[cfg(feature = "new-trie")]
+pub fn generate_map_from_ranges(
+    ranges: impl IntoIterator<Item = (CharRange, u8)>,
+    default: u8,
+) -> Result<proc_macro2::TokenStream, super::GenerateError> {
+    let mut values = alloc::vec![default; char::MAX as usize + 1];
+    for (r, value) in ranges {
+        if !r.is_empty() {
+            values[r.low as usize..=r.high as usize].fill(value);
+        }
+    }
+    values[0xD800..0xE000].fill(0);
+    generate_map_from_values(&values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get() {
+        const LEVEL2: [u16; 992] = {
+            let mut level2 = [0; 992];
+            level2[0] = 1;
+            level2
+        };
+        const LEVEL3: [u8; 256] = {
+            let mut level3 = [0; 256];
+            level3[0xF0] = 1;
+            level3
+        };
+        const COUNTING: [u8; 64] = {
+            let mut leaf = [0; 64];
+            let mut i = 0;
+            while i < 64 {
+                leaf[i] = i as u8;
+                i += 1;
+            }
+            leaf
+        };
+        const CHONK: [u16; 64] = {
+            let mut chonk = [0; 64];
+            chonk[0] = 1;
+            chonk
+        };
+        // U+0800..=U+083F and U+100000..=U+10003F count up, the rest is 0
+        static MAP: CharTrieMap = CharTrieMap::from_raw_wide(
+            &[[0; 64]; 32],
+            &LEVEL2,
+            (&LEVEL3, &[[0; 64], CHONK]),
+            &[[0; 64], COUNTING],
+        );
+
+        assert_eq!(MAP.validate(), Ok(()));
+        assert_eq!(MAP.get('a'), 0);
+        assert_eq!(MAP.get('\u{800}'), 0);
+        assert_eq!(MAP.get('\u{80A}'), 10);
+        assert_eq!(MAP.get('\u{840}'), 0);
+        assert_eq!(MAP.get('\u{10003F}'), 63);
+        assert_eq!(MAP.get('\u{100040}'), 0);
+        assert_eq!(MAP.get(char::MAX), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "new-trie")]
+    fn generate() {
+        use {alloc::string::ToString, quote::quote};
+
+        let map = generate_map(|c| (c == '\u{10001}') as u8).unwrap();
+
+        let zeros = [0u8; 64];
+        let level1 = (0..32).map(|_| quote!([#(#zeros),*]));
+        let level2 = [0u8; 992];
+        let mut level3_0 = [1u8; 256];
+        level3_0[0] = 0;
+        let mut chonk = [0u8; 64];
+        chonk[0] = 1;
+        let mut leaf = [0u8; 64];
+        leaf[1] = 1;
+        assert_eq!(
+            map.to_string(),
+            quote! {
+                CharTrieMap::from_raw(
+                    &[#(#level1),*],
+                    &[#(#level2),*],
+                    (&[#(#level3_0),*], &[[#(#chonk),*], [#(#zeros),*]]),
+                    &[[#(#zeros),*], [#(#leaf),*]],
+                )
+            }
+            .to_string(),
+        );
+    }
+}
@@ -1,4 +1,74 @@
-use core::fmt;
+#[cfg(feature = "set")]
+use crate::set::CharSet;
+use {
+    crate::{provenance::Provenance, CharRange, AFTER_SURROGATE, BEFORE_SURROGATE},
+    core::{char, fmt, mem},
+};
+
+#[cfg(feature = "rkyv")]
+mod archive;
+mod bmp;
+#[cfg(feature = "owned-trie")]
+mod builder;
+mod bytes;
+#[cfg(feature = "const-generate")]
+mod const_generate;
+#[cfg(any(feature = "test-utils", all(feature = "set", feature = "owned-set")))]
+mod diff;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod iter;
+mod lazy;
+mod map;
+#[cfg(feature = "owned-trie")]
+mod owned;
+#[cfg(feature = "par-iter")]
+mod par_iter;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "trie-source")]
+mod source;
+mod split;
+#[cfg(any(feature = "new-trie", feature = "trie-source"))]
+mod stats;
+mod surrogates;
+#[cfg(feature = "ucd-trie")]
+mod trie_set;
+#[cfg(feature = "test-utils")]
+mod verify;
+
+#[cfg(feature = "rkyv")]
+pub use self::archive::ArchivedCharTrie;
+#[cfg(feature = "const-generate")]
+pub use self::const_generate::ConstTables;
+#[cfg(all(feature = "set", feature = "owned-set"))]
+pub use self::diff::diff;
+#[cfg(feature = "ffi")]
+pub use self::ffi::{mileage_trie_contains, RawTrie};
+#[cfg(feature = "trie-source")]
+pub use self::source::{generate_source, generate_source_from_ranges, generate_source_wrapped};
+#[cfg(any(feature = "new-trie", feature = "trie-source"))]
+pub use self::stats::{analyze, analyze_from_ranges, TrieStats};
+#[cfg(feature = "test-utils")]
+pub use self::verify::verify;
+#[cfg(feature = "new-trie")]
+pub use self::{
+    bmp::generate_bmp,
+    map::{generate_map, generate_map_from_ranges},
+    split::generate_split,
+    surrogates::generate_u32,
+};
+pub use self::{
+    bmp::CharTrieBmp,
+    bytes::BytesError,
+    iter::{Chars, RangeIter},
+    lazy::{Complement, Intersection, Operand, Union},
+    map::CharTrieMap,
+    split::CharTrieSplit,
+    surrogates::CharTrieU32,
+};
+#[cfg(feature = "owned-trie")]
+pub use self::{builder::CharTrieBuilder, owned::CharTrieBuf};
 
 /// A compressed trie-like set tailored for read-only sets of unicode codepoints.
 ///
@@ -32,160 +102,1087 @@ use core::fmt;
 ///
 /// This format fits the full table into a maximum of 20KB, and less than 2KB if
 /// a relatively compressible pattern of codepoints above 0x800 are included.
+///
+/// Sets with more than 256 distinct 64-bit chunks above 0x800 cannot be
+/// indexed with 8 bits, so instead use 16 bit indices into the shared table.
+/// See `CharTrie::from_raw_wide`.
+///
+/// The tables are borrowed for `'a`. This is `'static` for generated tries,
+/// but may be shorter for tables loaded at runtime, such as by
+/// `CharTrie::from_bytes`, or borrowed from a `CharTrieBuf` by `as_trie`.
 #[derive(Copy, Clone)]
-pub struct CharTrie {
-    level1: &'static [u64; 32],
-    level2: &'static [u8; 992],
-    level3: (&'static [u8; 256], &'static [[u8; 64]]),
-    leaves: &'static [u64],
+pub struct CharTrie<'a> {
+    level1: &'a [u64; 32],
+    levels: Levels<'a>,
+    leaves: &'a [u64],
+    provenance: Option<&'a Provenance<'a>>,
+}
+
+/// The index levels of a trie, with either 8 or 16 bit indices into the leaves.
+///
+/// Each variant holds level2 and level3, as passed to `CharTrie::from_raw`
+/// or `CharTrie::from_raw_wide`. See `CharTrie::as_raw`.
+#[derive(Copy, Clone, Debug)]
+pub enum Levels<'a> {
+    /// The levels of a trie with at most 256 leaves.
+    Narrow(&'a [u8; 992], (&'a [u8; 256], &'a [[u8; 64]])),
+    /// The levels of a trie with more than 256 leaves.
+    Wide(&'a [u16; 992], (&'a [u8; 256], &'a [[u16; 64]])),
 }
 
-impl fmt::Debug for CharTrie {
+/// Formats as its arguments, to describe the shape of a table in `Debug` output.
+pub(crate) struct Shape<'a>(pub(crate) fmt::Arguments<'a>);
+
+impl fmt::Debug for Shape<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        struct S<'a>(fmt::Arguments<'a>);
-        impl fmt::Debug for S<'_> {
-            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-                f.write_fmt(self.0)
-            }
-        }
+        f.write_fmt(self.0)
+    }
+}
 
+impl fmt::Debug for CharTrie<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (index, chonks) = match self.levels {
+            Levels::Narrow(_, level3) => ("u8", level3.1.len()),
+            Levels::Wide(_, level3) => ("u16", level3.1.len()),
+        };
         f.debug_struct("CharTrie")
-            .field("level1", &S(format_args!("&[u64; 32]")))
-            .field("level2", &S(format_args!("&[u8; 992]")))
+            .field("level1", &Shape(format_args!("&[u64; 32]")))
+            .field("level2", &Shape(format_args!("&[{}; 992]", index)))
             .field(
                 "level3",
-                &S(format_args!(
-                    "(&[u8; 256], &[[u8; 64]; dyn {}])",
-                    self.level3.1.len()
+                &Shape(format_args!(
+                    "(&[u8; 256], &[[{}; 64]; dyn {}])",
+                    index, chonks
                 )),
             )
             .field(
                 "level4",
-                &S(format_args!("&[u64; dyn {}]", self.leaves.len())),
+                &Shape(format_args!("&[u64; dyn {}]", self.leaves.len())),
             )
+            .field("provenance", &self.provenance)
             .finish()
     }
 }
 
-impl CharTrie {
+/// Compares membership, so a trie equals a range with exactly the same codepoints.
+///
+/// This compares the chunks of the trie, rather than testing every codepoint.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::{trie::CharTrie, CharRange};
+/// static ASCII: [u64; 32] = {
+///     let mut level1 = [0; 32];
+///     level1[0] = !0;
+///     level1[1] = !0;
+///     level1
+/// };
+/// let trie = CharTrie::from_raw(&ASCII, &[0; 992], (&[0; 256], &[[0; 64]]), &[0]);
+/// assert_eq!(trie, CharRange::from('\0'..='\x7F'));
+/// assert_ne!(trie, CharRange::from('\0'..='\x7E'));
+/// ```
+impl PartialEq<CharRange> for CharTrie<'_> {
+    fn eq(&self, other: &CharRange) -> bool {
+        self.same_members(Some(*other).filter(|r| !r.is_empty()))
+    }
+}
+
+/// Compares membership, so a trie equals a set with exactly the same codepoints.
+///
+/// This compares the chunks of the trie, rather than testing every codepoint.
+#[cfg(feature = "set")]
+impl PartialEq<CharSet> for CharTrie<'_> {
+    fn eq(&self, other: &CharSet) -> bool {
+        self.same_members(other.ranges())
+    }
+}
+
+/// Compares membership, so a trie equals a set with exactly the same codepoints.
+#[cfg(feature = "set")]
+impl PartialEq<&CharSet> for CharTrie<'_> {
+    fn eq(&self, other: &&CharSet) -> bool {
+        *self == **other
+    }
+}
+
+impl<'a> CharTrie<'a> {
     /// Does this set contain this codepoint?
+    #[inline]
     pub fn contains(&self, c: char) -> bool {
         let c = c as u32;
         let bit_index = u64::from(c & 0b_111_111);
-        // FUTURE(rust-lang/rust#37854): match with exclusive range
-        let chunk = if c < 0x800 {
-            let chunk_index = c >> 6;
-            self.level1[chunk_index as usize]
-        } else if 0x800 <= c && c < 0x10000 {
-            let c = c - 0x800;
-            let chunk_index = self.level2[(c >> 6) as usize];
-            self.leaves[chunk_index as usize]
-        } else if 0x10000 <= c && c < 0x11_0000 {
-            let c = c - 0x10000;
-            let chonk_index = self.level3.0[(c >> 12) as usize];
-            let chonk = &self.level3.1[chonk_index as usize];
-            let chunk_index = chonk[((c >> 6) & 63) as usize];
-            self.leaves[chunk_index as usize]
-        } else {
-            unreachable!()
-        };
-        (chunk >> bit_index) & 1 == 1
+        (self.chunk(c) >> bit_index) & 1 == 1
+    }
+
+    /// Which of these codepoints does this set contain?
+    ///
+    /// Bit `i` of the result is set if `chars[i]` is a member. The leaf of the
+    /// previous codepoint is reused while codepoints stay within the same chunk
+    /// of 64, so runs of text from one script are looked up with few table reads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than 64 codepoints.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::trie::CharTrie;
+    /// static LEVEL1: [u64; 32] = {
+    ///     let mut level1 = [0; 32];
+    ///     level1[0] = 0x03FF << 0x30;
+    ///     level1
+    /// };
+    /// static DIGITS: CharTrie<'static> =
+    ///     CharTrie::from_raw(&LEVEL1, &[0; 992], (&[0; 256], &[[0; 64]]), &[0]);
+    /// let chars: Vec<char> = "a1b22".chars().collect();
+    /// assert_eq!(DIGITS.contains_many(&chars), 0b11010);
+    /// ```
+    pub fn contains_many(&self, chars: &[char]) -> u64 {
+        assert!(
+            chars.len() <= 64,
+            "at most 64 codepoints can be tested at once"
+        );
+        let mut members = 0;
+        let mut cached: Option<(u32, u64)> = None;
+        for (i, &c) in chars.iter().enumerate() {
+            let c = c as u32;
+            let chunk = match cached {
+                Some((base, chunk)) if base == c >> 6 => chunk,
+                _ => {
+                    let chunk = self.chunk(c);
+                    cached = Some((c >> 6, chunk));
+                    chunk
+                }
+            };
+            members |= ((chunk >> (c & 0b_111_111)) & 1) << i;
+        }
+        members
+    }
+
+    /// Does this set have exactly the codepoints of these ranges?
+    ///
+    /// The ranges must be nonempty and sorted by their low end, but may overlap.
+    fn same_members(&self, ranges: impl IntoIterator<Item = CharRange>) -> bool {
+        let bases = (0..=char::MAX as u32).step_by(64);
+        range_chunks(ranges).zip(bases).all(|(chunk, base)| {
+            let surrogate = (BEFORE_SURROGATE as u32..AFTER_SURROGATE as u32).contains(&base);
+            surrogate || self.chunk(base) == chunk
+        })
+    }
+
+    /// The membership bits of the 64-aligned chunk of codepoints holding `c`.
+    ///
+    /// `c` need not be a valid `char`, but must be at most `char::MAX`.
+    #[inline]
+    pub(crate) fn chunk(&self, c: u32) -> u64 {
+        match self.levels {
+            Levels::Narrow(level2, level3) => *chunk(self.level1, level2, level3, self.leaves, c),
+            Levels::Wide(level2, level3) => *chunk(self.level1, level2, level3, self.leaves, c),
+        }
+    }
+
+    /// Does this set contain this ASCII byte?
+    ///
+    /// ASCII is always stored directly in level1, so this needs no indirection.
+    fn contains_ascii(&self, b: u8) -> bool {
+        debug_assert!(b.is_ascii());
+        (self.level1[usize::from(b >> 6)] >> (b & 0b_111_111)) & 1 == 1
+    }
+
+    /// Does this set contain every codepoint of this string?
+    ///
+    /// This is trivially true for the empty string.
+    /// A leading run of ASCII is checked bytewise, without decoding.
+    pub fn contains_all(&self, s: &str) -> bool {
+        let (ascii, rest) = split_ascii(s);
+        ascii.iter().all(|&b| self.contains_ascii(b)) && rest.chars().all(|c| self.contains(c))
+    }
+
+    /// Does this set contain any codepoint of this string?
+    ///
+    /// This is trivially false for the empty string.
+    /// A leading run of ASCII is checked bytewise, without decoding.
+    pub fn contains_any(&self, s: &str) -> bool {
+        let (ascii, rest) = split_ascii(s);
+        ascii.iter().any(|&b| self.contains_ascii(b)) || rest.chars().any(|c| self.contains(c))
+    }
+
+    /// The first member of this set after `c`, if any.
+    ///
+    /// This skips whole chunks of 64 codepoints at a time, so is much faster
+    /// than testing each codepoint with `contains`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::trie::CharTrie;
+    /// static LEVEL1: [u64; 32] = {
+    ///     let mut level1 = [0; 32];
+    ///     level1[0] = 0x03FF << 0x30;
+    ///     level1
+    /// };
+    /// static DIGITS: CharTrie<'static> =
+    ///     CharTrie::from_raw(&LEVEL1, &[0; 992], (&[0; 256], &[[0; 64]]), &[0]);
+    /// assert_eq!(DIGITS.next_member('\0'), Some('0'));
+    /// assert_eq!(DIGITS.next_member('0'), Some('1'));
+    /// assert_eq!(DIGITS.next_member('9'), None);
+    /// ```
+    pub fn next_member(&self, c: char) -> Option<char> {
+        let mut from = c as u32 + 1;
+        while from <= char::MAX as u32 {
+            let base = from & !0b_111_111;
+            let chunk = self.chunk(base) & (!0 << (from - base));
+            if chunk == 0 {
+                from = base + 64;
+                continue;
+            }
+            let next = base + chunk.trailing_zeros();
+            match char::from_u32(next) {
+                Some(next) => return Some(next),
+                // only reachable through bits set for surrogates
+                None => from = next + 1,
+            }
+        }
+        None
+    }
+
+    /// The last member of this set before `c`, if any.
+    ///
+    /// As `next_member`, this skips whole chunks of 64 codepoints at a time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::trie::CharTrie;
+    /// static LEVEL1: [u64; 32] = {
+    ///     let mut level1 = [0; 32];
+    ///     level1[0] = 0x03FF << 0x30;
+    ///     level1
+    /// };
+    /// static DIGITS: CharTrie<'static> =
+    ///     CharTrie::from_raw(&LEVEL1, &[0; 992], (&[0; 256], &[[0; 64]]), &[0]);
+    /// assert_eq!(DIGITS.prev_member(char::MAX), Some('9'));
+    /// assert_eq!(DIGITS.prev_member('9'), Some('8'));
+    /// assert_eq!(DIGITS.prev_member('0'), None);
+    /// ```
+    pub fn prev_member(&self, c: char) -> Option<char> {
+        let mut to = (c as u32).checked_sub(1)?;
+        loop {
+            let base = to & !0b_111_111;
+            let chunk = self.chunk(base) & (!0 >> (63 - (to - base)));
+            if chunk == 0 {
+                to = base.checked_sub(1)?;
+                continue;
+            }
+            let prev = base + 63 - chunk.leading_zeros();
+            match char::from_u32(prev) {
+                Some(prev) => return Some(prev),
+                // only reachable through bits set for surrogates
+                None => to = prev - 1,
+            }
+        }
+    }
+
+    /// The number of members of this set in a range.
+    ///
+    /// This counts the bits of each chunk of 64 codepoints overlapping the range,
+    /// masking the chunks at either end, rather than testing every codepoint.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{trie::CharTrie, CharRange};
+    /// static LEVEL1: [u64; 32] = {
+    ///     let mut level1 = [0; 32];
+    ///     level1[0] = 0x03FF << 0x30;
+    ///     level1
+    /// };
+    /// static DIGITS: CharTrie<'static> =
+    ///     CharTrie::from_raw(&LEVEL1, &[0; 992], (&[0; 256], &[[0; 64]]), &[0]);
+    /// assert_eq!(DIGITS.count_in_range(CharRange::from(..)), 10);
+    /// assert_eq!(DIGITS.count_in_range(CharRange::closed('5', 'z')), 5);
+    /// ```
+    pub fn count_in_range(&self, r: CharRange) -> usize {
+        if r.is_empty() {
+            return 0;
+        }
+        let (low, high) = (r.low as u32, r.high as u32);
+        (low & !0b_111_111..=high)
+            .step_by(64)
+            .map(|base| (self.chunk(base) & chunk_mask(base, low, high)).count_ones() as usize)
+            .sum()
     }
 
     /// Create a codepoint trie from the components as described above.
+    ///
+    /// The indices are not checked; looking up a codepoint through an index
+    /// which is out of bounds panics. Use `try_from_raw` to check them up front.
     pub const fn from_raw(
-        level1: &'static [u64; 32],
-        level2: &'static [u8; 992],
-        level3: (&'static [u8; 256], &'static [[u8; 64]]),
-        leaves: &'static [u64],
+        level1: &'a [u64; 32],
+        level2: &'a [u8; 992],
+        level3: (&'a [u8; 256], &'a [[u8; 64]]),
+        leaves: &'a [u64],
+    ) -> Self {
+        CharTrie {
+            level1,
+            levels: Levels::Narrow(level2, level3),
+            leaves,
+            provenance: None,
+        }
+    }
+
+    /// Create a codepoint trie from components as `from_raw`,
+    /// but with 16 bit indices into the leaves.
+    ///
+    /// This is needed for sets with more than 256 distinct leaves.
+    pub const fn from_raw_wide(
+        level1: &'a [u64; 32],
+        level2: &'a [u16; 992],
+        level3: (&'a [u8; 256], &'a [[u16; 64]]),
+        leaves: &'a [u64],
     ) -> Self {
         CharTrie {
             level1,
-            level2,
-            level3,
+            levels: Levels::Wide(level2, level3),
             leaves,
+            provenance: None,
+        }
+    }
+
+    /// Create a codepoint trie from components as `from_raw`,
+    /// checking that every index is in bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::trie::{CharTrie, TrieError};
+    /// static LEVEL2: [u8; 992] = [1; 992];
+    /// let trie = CharTrie::try_from_raw(&[0; 32], &LEVEL2, (&[0; 256], &[[0; 64]]), &[0]);
+    /// assert_eq!(trie.unwrap_err(), TrieError::Level2(0));
+    /// ```
+    pub const fn try_from_raw(
+        level1: &'a [u64; 32],
+        level2: &'a [u8; 992],
+        level3: (&'a [u8; 256], &'a [[u8; 64]]),
+        leaves: &'a [u64],
+    ) -> Result<Self, TrieError> {
+        match check_narrow(level2, level3, leaves.len()) {
+            Ok(()) => Ok(CharTrie::from_raw(level1, level2, level3, leaves)),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Create a codepoint trie from components as `from_raw_wide`,
+    /// checking that every index is in bounds.
+    pub const fn try_from_raw_wide(
+        level1: &'a [u64; 32],
+        level2: &'a [u16; 992],
+        level3: (&'a [u8; 256], &'a [[u16; 64]]),
+        leaves: &'a [u64],
+    ) -> Result<Self, TrieError> {
+        match check_wide(level2, level3, leaves.len()) {
+            Ok(()) => Ok(CharTrie::from_raw_wide(level1, level2, level3, leaves)),
+            Err(err) => Err(err),
         }
     }
+
+    /// Check that every index of this trie is in bounds,
+    /// such that looking up any codepoint will not panic.
+    pub fn validate(&self) -> Result<(), TrieError> {
+        match self.levels {
+            Levels::Narrow(level2, level3) => check_narrow(level2, level3, self.leaves.len()),
+            Levels::Wide(level2, level3) => check_wide(level2, level3, self.leaves.len()),
+        }
+    }
+
+    /// The size of every table this trie references, in bytes.
+    ///
+    /// This counts the tables, not the `CharTrie` itself or its provenance,
+    /// and counts tables shared with other tries in full. For a generated trie,
+    /// this is the `total_bytes` reported by `trie::analyze`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::trie::CharTrie;
+    /// static EMPTY: CharTrie<'static> =
+    ///     CharTrie::from_raw(&[0; 32], &[0; 992], (&[0; 256], &[[0; 64]]), &[0]);
+    /// assert_eq!(EMPTY.memory_footprint(), 32 * 8 + 992 + 256 + 64 + 8);
+    /// ```
+    pub fn memory_footprint(&self) -> usize {
+        let levels = match self.levels {
+            Levels::Narrow(level2, (first, blocks)) => {
+                mem::size_of_val(level2) + mem::size_of_val(first) + mem::size_of_val(blocks)
+            }
+            Levels::Wide(level2, (first, blocks)) => {
+                mem::size_of_val(level2) + mem::size_of_val(first) + mem::size_of_val(blocks)
+            }
+        };
+        mem::size_of_val(self.level1) + levels + mem::size_of_val(self.leaves)
+    }
+
+    /// The tables of this trie: level1, level2 and level3 with their index width, and the leaves.
+    ///
+    /// These are the tables as passed to `CharTrie::from_raw` or `CharTrie::from_raw_wide`,
+    /// for tools which inspect or serialize tries themselves.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::trie::{CharTrie, Levels};
+    /// static EMPTY: CharTrie<'static> =
+    ///     CharTrie::from_raw(&[0; 32], &[0; 992], (&[0; 256], &[[0; 64]]), &[0]);
+    /// let (level1, levels, leaves) = EMPTY.as_raw();
+    /// assert_eq!(level1, &[0; 32]);
+    /// match levels {
+    ///     Levels::Narrow(level2, (first, blocks)) => {
+    ///         assert!(level2.iter().chain(first).all(|&idx| idx == 0));
+    ///         assert_eq!(blocks.len(), 1);
+    ///     }
+    ///     Levels::Wide(..) => unreachable!(),
+    /// }
+    /// assert_eq!(leaves, [0]);
+    /// ```
+    pub fn as_raw(&self) -> (&'a [u64; 32], Levels<'a>, &'a [u64]) {
+        (self.level1, self.levels, self.leaves)
+    }
+
+    /// Attach a record of the data this trie was generated from.
+    pub const fn with_provenance(self, provenance: &'a Provenance<'a>) -> Self {
+        CharTrie {
+            provenance: Some(provenance),
+            ..self
+        }
+    }
+
+    /// The record of the data this trie was generated from, if one was attached.
+    pub fn provenance(&self) -> Option<&'a Provenance<'a>> {
+        self.provenance
+    }
 }
 
-/// Generate a new trie from a membership function.
+/// An error from validating the tables of a trie.
 ///
-/// This constructs Rust code that is legal in expression position that
-/// evaluates to a `CharTrie`. Requires that `CharTrie` is in scope.
+/// Each variant holds the position of the first offending index.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum TrieError {
+    /// The entry of level2 at this index is past the end of the leaves.
+    Level2(usize),
+    /// The entry of the first table of level3 at this index is past the end
+    /// of the level3 blocks.
+    Level3(usize),
+    /// The entry of this level3 block at this index is past the end of the leaves.
+    Level3Block(usize, usize),
+}
+
+impl fmt::Display for TrieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrieError::Level2(i) => write!(f, "level2 index {} is out of bounds", i),
+            TrieError::Level3(i) => write!(f, "level3 index {} is out of bounds", i),
+            TrieError::Level3Block(block, i) => {
+                write!(f, "index {} of level3 block {} is out of bounds", i, block)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TrieError {}
+
+/// An error from generating or building the tables of a trie.
 ///
-/// Fails if the set was unable to be compressed into the trie format.
-#[cfg(feature = "new-trie")]
-pub fn generate(
-    f: impl Fn(char) -> bool + Copy,
-) -> Result<proc_macro2::TokenStream, core::num::TryFromIntError> {
-    use {
-        crate::CharRange, alloc::vec::Vec, bitvec::prelude::*, core::char, core::convert::TryFrom,
-        indexmap::IndexSet, itertools::Itertools, quote::quote,
-    };
+/// Each variant reports how many entries the set needed, how many can be
+/// indexed, and the region of codepoints where the limit was first exceeded.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum GenerateError {
+    /// There are more distinct leaves than can be indexed.
+    Leaves {
+        /// How many distinct leaves were needed.
+        needed: usize,
+        /// How many leaves can be indexed.
+        max: usize,
+        /// The chunk of 64 codepoints whose leaf was the first past `max`.
+        region: CharRange,
+    },
+    /// There are more distinct level3 blocks than can be indexed.
+    Level3Blocks {
+        /// How many distinct level3 blocks were needed.
+        needed: usize,
+        /// How many level3 blocks can be indexed.
+        max: usize,
+        /// The 4096 codepoints whose level3 block was the first past `max`.
+        region: CharRange,
+    },
+}
 
-    fn level1(f: impl Fn(char) -> bool + Copy) -> proc_macro2::TokenStream {
-        let level1: BitVec<u64, Lsb0> = CharRange::from('\0'..'\u{800}').iter().map(f).collect();
-        let level1 = level1.as_raw_slice();
-        quote!(&[#(#level1),*],)
+#[cfg(any(feature = "new-trie", feature = "trie-source", feature = "owned-trie"))]
+impl GenerateError {
+    /// Too many leaves, first exceeded by the chunk holding `c`.
+    pub(crate) fn leaves(needed: usize, max: usize, c: u32) -> Self {
+        let region = region(c, 0b_111_111);
+        GenerateError::Leaves {
+            needed,
+            max,
+            region,
+        }
     }
 
-    fn level2(
-        leaves: &mut IndexSet<u64>,
-        f: impl Fn(char) -> bool + Copy,
-    ) -> Result<proc_macro2::TokenStream, core::num::TryFromIntError> {
-        let mut level2 = Vec::with_capacity(992);
-        // level2 has to manually include the surrogate range
-        let level2_chunks = (0x800u32..0x10000)
-            .map(|cp| char::try_from(cp).map(f).unwrap_or(false))
-            .chunks(64);
-        for chunk in &level2_chunks {
-            let chunk: BitVec<u64, Lsb0> = chunk.collect();
-            assert_eq!(chunk.len(), 64);
-            let chunk = chunk.load();
-            level2.push(u8::try_from(leaves.insert_full(chunk).0)?);
+    /// Too many level3 blocks, first exceeded by the block holding `c`.
+    pub(crate) fn level3_blocks(needed: usize, c: u32) -> Self {
+        let region = region(c, 0xFFF);
+        GenerateError::Level3Blocks {
+            needed,
+            max: 256,
+            region,
+        }
+    }
+}
+
+/// The aligned region of codepoints holding `c`, without surrogates at its ends.
+#[cfg(any(feature = "new-trie", feature = "trie-source", feature = "owned-trie"))]
+fn region(c: u32, mask: u32) -> CharRange {
+    let (low, high) = (c & !mask, c | mask);
+    CharRange::closed(
+        char::from_u32(low).unwrap_or(AFTER_SURROGATE),
+        char::from_u32(high).unwrap_or(BEFORE_SURROGATE),
+    )
+}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (what, needed, max, region) = match *self {
+            GenerateError::Leaves {
+                needed,
+                max,
+                region,
+            } => ("leaves", needed, max, region),
+            GenerateError::Level3Blocks {
+                needed,
+                max,
+                region,
+            } => ("level3 blocks", needed, max, region),
+        };
+        write!(
+            f,
+            "trie needs {} distinct {}, but at most {} can be indexed, first exceeded at U+{:04X}..U+{:04X}",
+            needed, what, max, region.low as u32, region.high as u32,
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for GenerateError {}
+
+/// Check that the indices of the levels of a trie are in bounds.
+///
+/// This is a macro rather than a generic function to be usable in `const fn`.
+macro_rules! check_indices {
+    ($level2:expr, $level3:expr, $leaves:expr) => {{
+        let (level2, level3, leaves) = ($level2, $level3, $leaves);
+        let mut i = 0;
+        while i < level2.len() {
+            if level2[i] as usize >= leaves {
+                return Err(TrieError::Level2(i));
+            }
+            i += 1;
+        }
+        let mut i = 0;
+        while i < level3.0.len() {
+            if level3.0[i] as usize >= level3.1.len() {
+                return Err(TrieError::Level3(i));
+            }
+            i += 1;
+        }
+        let mut block = 0;
+        while block < level3.1.len() {
+            let mut i = 0;
+            while i < 64 {
+                if level3.1[block][i] as usize >= leaves {
+                    return Err(TrieError::Level3Block(block, i));
+                }
+                i += 1;
+            }
+            block += 1;
+        }
+        Ok(())
+    }};
+}
+
+/// Check the indices of the levels of a trie with 8 bit indices.
+const fn check_narrow(
+    level2: &[u8; 992],
+    level3: (&[u8; 256], &[[u8; 64]]),
+    leaves: usize,
+) -> Result<(), TrieError> {
+    check_indices!(level2, level3, leaves)
+}
+
+/// Check the indices of the levels of a trie with 16 bit indices.
+const fn check_wide(
+    level2: &[u16; 992],
+    level3: (&[u8; 256], &[[u16; 64]]),
+    leaves: usize,
+) -> Result<(), TrieError> {
+    check_indices!(level2, level3, leaves)
+}
+
+/// Look up the chunk of codepoints holding `c` in the tables of a trie.
+///
+/// The chunks are the membership bits of a `CharTrie`, or the values of a `CharTrieMap`.
+fn chunk<'a, I: Copy + Into<usize>, T>(
+    level1: &'a [T; 32],
+    level2: &[I; 992],
+    level3: (&[u8; 256], &[[I; 64]]),
+    leaves: &'a [T],
+    c: u32,
+) -> &'a T {
+    // FUTURE(rust-lang/rust#37854): match with exclusive range
+    if c < 0x800 {
+        let chunk_index = c >> 6;
+        &level1[chunk_index as usize]
+    } else if (0x800..0x10000).contains(&c) {
+        let c = c - 0x800;
+        let chunk_index = level2[(c >> 6) as usize];
+        &leaves[chunk_index.into()]
+    } else if (0x10000..0x11_0000).contains(&c) {
+        let c = c - 0x10000;
+        let chonk_index = level3.0[(c >> 12) as usize];
+        let chonk = &level3.1[chonk_index as usize];
+        let chunk_index = chonk[((c >> 6) & 63) as usize];
+        &leaves[chunk_index.into()]
+    } else {
+        unreachable!()
+    }
+}
+
+/// Split a string into its leading ASCII bytes and the rest,
+/// which is empty or starts with a non-ASCII codepoint.
+fn split_ascii(s: &str) -> (&[u8], &str) {
+    let mid = s.bytes().position(|b| !b.is_ascii()).unwrap_or(s.len());
+    (&s.as_bytes()[..mid], &s[mid..])
+}
+
+/// The membership bits of the 64-codepoint chunk at `base`, testing each codepoint.
+///
+/// The surrogates are not `char`s, so are never members.
+#[cfg(any(
+    feature = "new-trie",
+    feature = "trie-source",
+    feature = "owned-trie",
+    feature = "test-utils"
+))]
+pub(crate) fn chunk_of(base: u32, f: impl Fn(char) -> bool) -> u64 {
+    chunk_of_u32(base, |c| char::from_u32(c).is_some_and(&f))
+}
+
+/// The membership bits of the 64-codepoint chunk at `base`, testing each codepoint,
+/// including the surrogates.
+#[cfg(any(
+    feature = "new-trie",
+    feature = "trie-source",
+    feature = "owned-trie",
+    feature = "test-utils"
+))]
+pub(crate) fn chunk_of_u32(base: u32, f: impl Fn(u32) -> bool) -> u64 {
+    (0..64)
+        .filter(|&bit| f(base + bit))
+        .fold(0, |chunk, bit| chunk | 1 << bit)
+}
+
+/// The bits of the 64-codepoint chunk at `base` which are in `low..=high`.
+///
+/// The range must overlap the chunk. The chunks of surrogates are always empty.
+pub(crate) const fn chunk_mask(base: u32, low: u32, high: u32) -> u64 {
+    if base >= 0xD800 && base < 0xE000 {
+        return 0;
+    }
+    let first = low.saturating_sub(base);
+    let last = if high - base < 63 { high - base } else { 63 };
+    (!0 >> (63 - last)) & (!0 << first)
+}
+
+/// The membership bits of each 64-codepoint chunk covered by some ranges,
+/// in order, as stored in the leaves of a trie.
+///
+/// The ranges must be nonempty and sorted by their low end, but may overlap.
+/// The chunks of surrogates are always empty.
+pub(crate) fn range_chunks(
+    ranges: impl IntoIterator<Item = CharRange>,
+) -> impl Iterator<Item = u64> {
+    let mut ranges = ranges.into_iter().peekable();
+    (0..=char::MAX as u32).step_by(64).map(move |base| {
+        let end = base + 64;
+        let mut chunk = 0u64;
+        while let Some(r) = ranges.peek() {
+            let (low, high) = (r.low as u32, r.high as u32);
+            if low >= end {
+                break;
+            }
+            if high < base {
+                // covered by an earlier, overlapping range
+                ranges.next();
+                continue;
+            }
+            chunk |= chunk_mask(base, low, high);
+            if high >= end {
+                // the range continues into the next chunk
+                break;
+            }
+            ranges.next();
+        }
+        chunk
+    })
+}
+
+/// How many leaves generated tables can index, with 16 bit indices.
+#[cfg(any(feature = "new-trie", feature = "trie-source"))]
+const LEAF_LIMIT: usize = 1 << 16;
+
+/// The tables of one or more generated tries, with indices wide enough for any set.
+///
+/// The chunks are the membership bits of a `CharTrie`, or the values of a `CharTrieMap`.
+#[cfg(any(feature = "new-trie", feature = "trie-source"))]
+struct Tables<T = u64> {
+    /// The level1, level2, and first table of level3 of each trie.
+    tries: alloc::vec::Vec<(
+        alloc::vec::Vec<T>,
+        alloc::vec::Vec<u16>,
+        alloc::vec::Vec<u8>,
+    )>,
+    /// The level3 blocks, shared by every trie.
+    chonks: alloc::vec::Vec<[u16; 64]>,
+    /// The leaves, shared by every trie.
+    leaves: alloc::vec::Vec<T>,
+    leaf_indices: alloc::collections::BTreeMap<T, u16>,
+}
+
+#[cfg(any(feature = "new-trie", feature = "trie-source"))]
+impl<T: Copy + Ord> Tables<T> {
+    /// Tables without any tries.
+    fn new() -> Self {
+        Tables {
+            tries: alloc::vec::Vec::new(),
+            chonks: alloc::vec::Vec::new(),
+            leaves: alloc::vec::Vec::new(),
+            leaf_indices: alloc::collections::BTreeMap::new(),
         }
-        assert_eq!(level2.len(), 992);
-        Ok(quote!(&[#(#level2),*],))
     }
 
-    fn level3(
-        leaves: &mut IndexSet<u64>,
-        f: impl Fn(char) -> bool,
-    ) -> Result<proc_macro2::TokenStream, core::num::TryFromIntError> {
+    /// Add the tables of a trie from each 64-codepoint chunk, in order.
+    ///
+    /// Leaves and level3 blocks are shared with the tries already added.
+    fn push(&mut self, chunks: impl IntoIterator<Item = T>) -> Result<(), GenerateError> {
+        use alloc::vec::Vec;
+
+        // Past the limits, the indices saturate, but the distinct leaves and
+        // blocks are still counted, to report how many are needed.
+        let (mut leaf_overflow, mut chonk_overflow) = (None, None);
+        let mut chunks = chunks.into_iter();
+        let (leaves, leaf_indices) = (&mut self.leaves, &mut self.leaf_indices);
+        let mut leaf = |chunk, c: u32| -> u16 {
+            *leaf_indices.entry(chunk).or_insert_with(|| {
+                leaves.push(chunk);
+                if leaves.len() > LEAF_LIMIT {
+                    leaf_overflow.get_or_insert(c);
+                }
+                (leaves.len() - 1).min(u16::MAX as usize) as u16
+            })
+        };
+
+        let level1: Vec<T> = chunks.by_ref().take(32).collect();
+        assert_eq!(level1.len(), 32);
+
+        // level2 has to manually include the surrogate range
+        let level2: Vec<u16> = (0x800..0x10000)
+            .step_by(64)
+            .zip(chunks.by_ref())
+            .map(|(c, chunk)| leaf(chunk, c))
+            .collect();
+        assert_eq!(level2.len(), 992);
+
         let mut first = Vec::with_capacity(256);
-        let mut second: IndexSet<Vec<u8>> = IndexSet::new();
-        let large_chunks = CharRange::from('\u{10000}'..).iter().map(f).chunks(4096);
-        for large_chunk in &large_chunks {
-            let large_chunk: BitVec<u8, Lsb0> = large_chunk.collect();
-            assert_eq!(large_chunk.len(), 4096);
-            let small_chunks = large_chunk.into_iter().chunks(64);
-            let mut chunk_indices = Vec::with_capacity(64);
-            for small_chunk in &small_chunks {
-                let small_chunk: BitVec<u64, Lsb0> = small_chunk.collect();
-                assert_eq!(small_chunk.len(), 64);
-                let small_chunk = small_chunk.load();
-                chunk_indices.push(u8::try_from(leaves.insert_full(small_chunk).0)?);
+        for block in (0x10000..=char::MAX as u32).step_by(0x1000) {
+            let mut chunk_indices = [0; 64];
+            for (c, idx) in (block..).step_by(64).zip(chunk_indices.iter_mut()) {
+                *idx = leaf(chunks.next().expect("too few chunks"), c);
             }
-            assert_eq!(chunk_indices.len(), 64);
-            first.push(u8::try_from(second.insert_full(chunk_indices).0)?);
+            let chonk = match self.chonks.iter().position(|&it| it == chunk_indices) {
+                Some(chonk) => chonk,
+                None => {
+                    self.chonks.push(chunk_indices);
+                    if self.chonks.len() > 256 {
+                        chonk_overflow.get_or_insert(block);
+                    }
+                    self.chonks.len() - 1
+                }
+            };
+            first.push(chonk.min(u8::MAX as usize) as u8);
         }
-        assert_eq!(first.len(), 256);
-        let second = second.into_iter();
-        Ok(quote!((&[#(#first),*], &[#([#(#second),*]),*]),))
+
+        if let Some(c) = leaf_overflow {
+            return Err(GenerateError::leaves(self.leaves.len(), LEAF_LIMIT, c));
+        }
+        if let Some(c) = chonk_overflow {
+            return Err(GenerateError::level3_blocks(self.chonks.len(), c));
+        }
+        self.tries.push((level1, level2, first));
+        Ok(())
     }
 
-    let mut src = proc_macro2::TokenStream::new();
+    /// Can the leaves be indexed with 8 bit indices?
+    fn is_narrow(&self) -> bool {
+        self.leaves.len() <= 256
+    }
 
-    let mut leaves: IndexSet<u64> = IndexSet::new();
+    /// The level3 blocks, as tokens of the index width of these tables.
+    #[cfg(feature = "new-trie")]
+    fn chonk_tokens(&self) -> alloc::vec::Vec<proc_macro2::TokenStream> {
+        let narrow = self.is_narrow();
+        let chonks = self.chonks.iter().map(|chonk| {
+            if narrow {
+                let chonk = chonk.iter().map(|&idx| idx as u8);
+                quote::quote!([#(#chonk),*])
+            } else {
+                quote::quote!([#(#chonk),*])
+            }
+        });
+        chonks.collect()
+    }
 
-    src.extend(level1(f));
-    src.extend(level2(&mut leaves, f)?);
-    src.extend(level3(&mut leaves, f)?);
+    /// The level2 table of a trie, as tokens of the index width of these tables.
+    #[cfg(feature = "new-trie")]
+    fn level2_tokens(&self, level2: &[u16]) -> proc_macro2::TokenStream {
+        if self.is_narrow() {
+            let level2 = level2.iter().map(|&idx| idx as u8);
+            quote::quote!(&[#(#level2),*])
+        } else {
+            quote::quote!(&[#(#level2),*])
+        }
+    }
 
-    let leaves = leaves.into_iter();
-    src.extend(quote!(&[#(#leaves),*],));
+    /// The constructor of `CharTrie` for the index width of these tables.
+    #[cfg(feature = "new-trie")]
+    fn constructor(&self) -> proc_macro2::Ident {
+        let name = if self.is_narrow() {
+            "from_raw"
+        } else {
+            "from_raw_wide"
+        };
+        proc_macro2::Ident::new(name, proc_macro2::Span::call_site())
+    }
+}
+
+#[cfg(any(feature = "new-trie", feature = "trie-source"))]
+impl Tables {
+    /// Build the tables of a trie from a membership function.
+    fn from_fn(f: impl Fn(char) -> bool) -> Result<Self, GenerateError> {
+        let mut tables = Tables::new();
+        tables.push_fn(f)?;
+        Ok(tables)
+    }
 
-    Ok(quote!( CharTrie::from_raw(#src) ))
+    /// Build the tables of a trie from ranges, which may be in any order and may overlap.
+    fn from_ranges(ranges: impl IntoIterator<Item = CharRange>) -> Result<Self, GenerateError> {
+        let mut ranges: alloc::vec::Vec<_> = ranges.into_iter().filter(|r| !r.is_empty()).collect();
+        ranges.sort_unstable_by_key(|r| r.low);
+        let mut tables = Tables::new();
+        tables.push(range_chunks(ranges))?;
+        Ok(tables)
+    }
+
+    /// Add the tables of a trie from a membership function.
+    fn push_fn(&mut self, f: impl Fn(char) -> bool) -> Result<(), GenerateError> {
+        self.push(
+            (0..=char::MAX as u32)
+                .step_by(64)
+                .map(|base| chunk_of(base, &f)),
+        )
+    }
+
+    /// An expression constructing the only `CharTrie` of these tables.
+    #[cfg(feature = "new-trie")]
+    fn to_tokens(&self) -> proc_macro2::TokenStream {
+        let (level1, level2, first) = &self.tries[0];
+        let constructor = self.constructor();
+        let level2 = self.level2_tokens(level2);
+        let second = self.chonk_tokens();
+        let leaves = &self.leaves;
+        quote::quote! {
+            CharTrie::#constructor(
+                &[#(#level1),*],
+                #level2,
+                (&[#(#first),*], &[#(#second),*]),
+                &[#(#leaves),*],
+            )
+        }
+    }
+
+    /// An expression constructing an array of every `CharTrie` of these tables,
+    /// sharing the leaves and level3 blocks between them.
+    #[cfg(feature = "new-trie")]
+    fn to_tokens_many(&self) -> proc_macro2::TokenStream {
+        let constructor = self.constructor();
+        let index = if self.is_narrow() {
+            quote::quote!(u8)
+        } else {
+            quote::quote!(u16)
+        };
+        let chonks = self.chonk_tokens();
+        let leaves = &self.leaves;
+        let tries = self.tries.iter().map(|(level1, level2, first)| {
+            let level2 = self.level2_tokens(level2);
+            quote::quote! {
+                CharTrie::#constructor(&[#(#level1),*], #level2, (&[#(#first),*], CHONKS), LEAVES)
+            }
+        });
+        quote::quote! {
+            {
+                const CHONKS: &[[#index; 64]] = &[#(#chonks),*];
+                const LEAVES: &[u64] = &[#(#leaves),*];
+                [#(#tries),*]
+            }
+        }
+    }
+}
+
+/// Generate a new trie from a membership function.
+///
+/// This constructs Rust code that is legal in expression position that
+/// evaluates to a `CharTrie`. Requires that `CharTrie` is in scope.
+///
+/// Sets with more than 256 distinct leaves fall back to 16 bit indices,
+/// as `CharTrie::from_raw_wide`.
+///
+/// The output depends only on the set. Leaves and level3 blocks are numbered
+/// in order of their first use, by codepoint, and every literal has a suffix
+/// of a fixed size, so the same set generates the same tokens on every run
+/// and platform. For a checked-in file, see also `generate_source_wrapped`.
+#[cfg(feature = "new-trie")]
+pub fn generate(
+    f: impl Fn(char) -> bool + Copy,
+) -> Result<proc_macro2::TokenStream, GenerateError> {
+    Ok(Tables::from_fn(f)?.to_tokens())
+}
+
+/// Generate a new trie for the complement of a membership function.
+///
+/// This behaves as `generate(|c| !f(c))`, for negated property tables.
+/// The surrogates are not members of the complement either.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::trie::{generate, generate_complement};
+/// assert_eq!(
+///     generate_complement(char::is_whitespace)?.to_string(),
+///     generate(|c| !c.is_whitespace())?.to_string(),
+/// );
+/// # Ok::<(), mileage::trie::GenerateError>(())
+/// ```
+#[cfg(feature = "new-trie")]
+pub fn generate_complement(
+    f: impl Fn(char) -> bool,
+) -> Result<proc_macro2::TokenStream, GenerateError> {
+    Ok(Tables::from_fn(|c| !f(c))?.to_tokens())
+}
+
+/// Generate a new trie from ranges of codepoints.
+///
+/// This behaves as `generate`, but builds the trie directly from the ranges
+/// rather than testing every codepoint, so is much faster for sets which are
+/// already known as ranges, such as a `CharSet` or parsed UCD data.
+/// The ranges may be in any order and may overlap.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::{trie::{generate, generate_from_ranges}, CharRange};
+/// let digits = [CharRange::from('0'..='9'), CharRange::from('a'..='f')];
+/// assert_eq!(
+///     generate_from_ranges(digits.iter().copied())?.to_string(),
+///     generate(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase())?.to_string(),
+/// );
+/// # Ok::<(), mileage::trie::GenerateError>(())
+/// ```
+#[cfg(feature = "new-trie")]
+pub fn generate_from_ranges(
+    ranges: impl IntoIterator<Item = CharRange>,
+) -> Result<proc_macro2::TokenStream, GenerateError> {
+    Ok(Tables::from_ranges(ranges)?.to_tokens())
+}
+
+/// Generate several new tries from membership functions, sharing their tables.
+///
+/// This constructs Rust code that is legal in expression position that
+/// evaluates to an array of a `CharTrie` for each function, in order.
+/// Identical leaves and level3 blocks are stored once and shared by every trie,
+/// which saves a lot of space for related sets such as Unicode properties.
+/// Requires that `CharTrie` is in scope.
+///
+/// If the tries together have more than 256 distinct leaves, they all use
+/// 16 bit indices, as `CharTrie::from_raw_wide`. Fails if the tries together
+/// have more than 256 distinct level3 blocks.
+#[cfg(feature = "new-trie")]
+pub fn generate_many(
+    fns: &[&dyn Fn(char) -> bool],
+) -> Result<proc_macro2::TokenStream, GenerateError> {
+    let mut tables = Tables::new();
+    for f in fns {
+        tables.push_fn(f)?;
+    }
+    Ok(tables.to_tokens_many())
+}
+
+/// Generate a new trie from a membership function, recording its provenance.
+///
+/// This behaves as `generate`, but the generated expression additionally
+/// attaches `provenance` to the trie, such that it is available at runtime
+/// from `CharTrie::provenance`. Requires that `Provenance` and `SourceFile`
+/// are in scope in addition to `CharTrie`.
+#[cfg(feature = "new-trie")]
+pub fn generate_with_provenance(
+    f: impl Fn(char) -> bool + Copy,
+    provenance: &Provenance<'_>,
+) -> Result<proc_macro2::TokenStream, GenerateError> {
+    let trie = generate(f)?;
+    Ok(quote::quote!( #trie.with_provenance(&#provenance) ))
+}
+
+/// Generate a new trie for each property of a Unicode Character Database property file.
+///
+/// The file is parsed as by `parse::parse_ucd_properties`, and each property's
+/// trie is generated as by `generate_from_ranges`, keyed by property name.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::{trie::{generate, generate_ucd}, parse::UcdError};
+/// let tries = generate_ucd("0030..0039 ; Digit\n0041..005A ; Upper\n")?;
+/// assert_eq!(
+///     tries["Digit"].to_string(),
+///     generate(|c| c.is_ascii_digit()).unwrap().to_string(),
+/// );
+/// # Ok::<(), UcdError>(())
+/// ```
+#[cfg(all(feature = "new-trie", feature = "ucd"))]
+pub fn generate_ucd(
+    s: &str,
+) -> Result<
+    alloc::collections::BTreeMap<alloc::string::String, proc_macro2::TokenStream>,
+    crate::parse::UcdError,
+> {
+    let props = crate::parse::parse_ucd_properties(s)?;
+    Ok(props
+        .into_iter()
+        .map(|(name, set)| {
+            let tables = Tables::from_ranges(set.ranges())
+                .expect("the leaves of a single trie always fit 16 bit indices");
+            (name, tables.to_tokens())
+        })
+        .collect())
 }
 
 #[cfg(test)]
@@ -236,4 +1233,478 @@ mod tests {
             assert_eq!(trie.contains(c), c.is_ascii(), "{:?}", c);
         }
     }
+
+    #[test]
+    #[cfg(feature = "new-trie")]
+    fn deterministic() {
+        use alloc::string::ToString;
+
+        let ranges = [
+            CharRange::closed('\u{900}', '\u{93F}'),
+            CharRange::singleton('\u{10000}'),
+            CharRange::closed('\u{10FFC0}', char::MAX),
+        ];
+        let f = |c| ranges.iter().any(|r| r.contains(c));
+
+        // leaves and level3 blocks are numbered by their first use
+        let tables = Tables::from_fn(f).unwrap();
+        assert_eq!(tables.leaves, [0, !0, 1]);
+        assert_eq!(tables.chonks.len(), 3);
+        assert_eq!(tables.tries[0].2[..2], [0, 1]);
+        assert_eq!(tables.tries[0].2[255], 2);
+
+        let tokens = generate(f).unwrap().to_string();
+        assert_eq!(generate(f).unwrap().to_string(), tokens);
+        let mut reversed = ranges;
+        reversed.reverse();
+        assert_eq!(
+            super::generate_from_ranges(reversed.iter().copied())
+                .unwrap()
+                .to_string(),
+            tokens
+        );
+        assert!(!tokens.contains("usize"));
+    }
+
+    #[test]
+    fn provenance() {
+        use crate::provenance::SourceFile;
+
+        static PROVENANCE: Provenance<'static> = Provenance {
+            unicode_version: Some("12.1.0"),
+            sources: &[SourceFile {
+                path: "PropList.txt",
+                hash: "sha256:0",
+            }],
+            options: "ascii",
+        };
+        static TRIE: CharTrie<'static> = CharTrie::from_raw(
+            &[
+                !0, !0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0,
+            ],
+            &[0u8; 992],
+            (&[0u8; 256], &[[0u8; 64]]),
+            &[0],
+        )
+        .with_provenance(&PROVENANCE);
+
+        assert_eq!(TRIE.provenance(), Some(&PROVENANCE));
+        assert!(TRIE.contains('a'));
+    }
+
+    #[test]
+    fn wide() {
+        // U+0800..=U+083F and U+10000
+        const LEVEL2: [u16; 992] = {
+            let mut level2 = [0; 992];
+            level2[0] = 1;
+            level2
+        };
+        const LEVEL3: [u8; 256] = {
+            let mut level3 = [0; 256];
+            level3[0] = 1;
+            level3
+        };
+        const CHONK: [u16; 64] = {
+            let mut chonk = [0; 64];
+            chonk[0] = 2;
+            chonk
+        };
+        static TRIE: CharTrie<'static> =
+            CharTrie::from_raw_wide(&[0; 32], &LEVEL2, (&LEVEL3, &[[0; 64], CHONK]), &[0, !0, 1]);
+
+        let r = CharRange::closed;
+        assert!(TRIE
+            .ranges()
+            .eq([r('\u{800}', '\u{83F}'), r('\u{10000}', '\u{10000}')]));
+        assert!(TRIE.contains('\u{800}'));
+        assert!(!TRIE.contains('\u{840}'));
+        assert!(TRIE.contains('\u{10000}'));
+        assert!(!TRIE.contains('\u{11000}'));
+        assert_eq!(
+            TRIE.memory_footprint(),
+            32 * 8 + 992 * 2 + 256 + 2 * 64 * 2 + 3 * 8
+        );
+
+        let (level1, levels, leaves) = TRIE.as_raw();
+        match levels {
+            Levels::Wide(level2, level3) => {
+                assert_eq!(level2, &LEVEL2);
+                assert_eq!(level3.0, &LEVEL3);
+                assert_eq!(level3.1, [[0; 64], CHONK]);
+                let copy = CharTrie::from_raw_wide(level1, level2, level3, leaves);
+                assert!(copy.ranges().eq(TRIE.ranges()));
+            }
+            Levels::Narrow(..) => panic!("the trie has 16 bit indices"),
+        }
+        assert_eq!(leaves, [0, !0, 1]);
+    }
+
+    #[test]
+    #[cfg(feature = "new-trie")]
+    fn generate_many() {
+        use {alloc::string::ToString, quote::quote};
+
+        let tries = super::generate_many(&[&|c| c.is_ascii(), &|c| c == '\u{800}']).unwrap();
+
+        let ascii = [
+            !0u64, !0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0,
+        ];
+        let empty = [0u64; 32];
+        let level2 = [0u8; 992];
+        let mut level2_800 = [0u8; 992];
+        level2_800[0] = 1;
+        let level3_0 = [0u8; 256];
+        let chonk = [0u8; 64];
+        assert_eq!(
+            tries.to_string(),
+            quote! {
+                {
+                    const CHONKS: &[[u8; 64]] = &[[#(#chonk),*]];
+                    const LEAVES: &[u64] = &[0u64, 1u64];
+                    [
+                        CharTrie::from_raw(&[#(#ascii),*], &[#(#level2),*], (&[#(#level3_0),*], CHONKS), LEAVES),
+                        CharTrie::from_raw(&[#(#empty),*], &[#(#level2_800),*], (&[#(#level3_0),*], CHONKS), LEAVES)
+                    ]
+                }
+            }
+            .to_string(),
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "new-trie")]
+    fn generate_error() {
+        use alloc::string::ToString;
+
+        // every level3 block is different, and different between the tries
+        let first = |c: char| c as u32 & 0xFFF == c as u32 >> 12;
+        let second = |c: char| c as u32 & 0xFFF == (c as u32 >> 12) + 0x200;
+        let err = super::generate_many(&[&first, &second]).unwrap_err();
+        assert_eq!(
+            err,
+            GenerateError::Level3Blocks {
+                needed: 512,
+                max: 256,
+                region: CharRange::closed('\u{10000}', '\u{10FFF}'),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "trie needs 512 distinct level3 blocks, but at most 256 can be indexed, \
+             first exceeded at U+10000..U+10FFF",
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "new-trie")]
+    fn generate_wide() {
+        use alloc::string::ToString;
+
+        // every 64-codepoint chunk below U+10000 is different
+        let distinct =
+            |c: char| (c as u32 & 63) < 16 && ((c as u32 >> 6) >> (c as u32 & 63)) & 1 == 1;
+        let trie = generate(distinct).unwrap().to_string();
+        assert!(trie.starts_with("CharTrie :: from_raw_wide"), "{}", trie);
+        assert!(generate(|c| c.is_ascii())
+            .unwrap()
+            .to_string()
+            .starts_with("CharTrie :: from_raw ("));
+    }
+
+    #[test]
+    fn validate() {
+        const BAD_LEVEL2: [u8; 992] = {
+            let mut level2 = [0; 992];
+            level2[7] = 1;
+            level2
+        };
+        const BAD_LEVEL3: [u8; 256] = {
+            let mut level3 = [0; 256];
+            level3[9] = 1;
+            level3
+        };
+        const BAD_CHONK: [u16; 64] = {
+            let mut chonk = [0; 64];
+            chonk[3] = 2;
+            chonk
+        };
+        static OK: Result<CharTrie<'static>, TrieError> =
+            CharTrie::try_from_raw(&[0; 32], &[0; 992], (&[0; 256], &[[0; 64]]), &[0]);
+        assert!(OK.is_ok());
+
+        #[rustfmt::skip]
+        let test_data = [
+            (CharTrie::from_raw(&[0; 32], &BAD_LEVEL2, (&[0; 256], &[[0; 64]]), &[0]), Err(TrieError::Level2(7))),
+            (CharTrie::from_raw(&[0; 32], &[0; 992], (&BAD_LEVEL3, &[[0; 64]]), &[0]), Err(TrieError::Level3(9))),
+            (CharTrie::from_raw(&[0; 32], &[0; 992], (&[0; 256], &[[0; 64]]), &[]), Err(TrieError::Level2(0))),
+            (CharTrie::from_raw_wide(&[0; 32], &[1; 992], (&[1; 256], &[[0; 64], BAD_CHONK]), &[0, 0]), Err(TrieError::Level3Block(1, 3))),
+            (CharTrie::from_raw_wide(&[0; 32], &[1; 992], (&[1; 256], &[[0; 64], [1; 64]]), &[0, 0]), Ok(())),
+        ];
+        for (trie, result) in &test_data {
+            assert_eq!(trie.validate(), *result);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "set")]
+    fn eq_set() {
+        use crate::set::CharSet;
+
+        static LEVEL2: [u8; 992] = {
+            let mut level2 = [0; 992];
+            level2[0x340] = 1; // U+D800..=U+D83F
+            level2[0x360] = 1; // U+E000..=U+E03F
+            level2
+        };
+        // U+0080..=U+00BF and U+E000..=U+E03F, with bits set for some surrogates
+        static LEVEL1: [u64; 32] = {
+            let mut level1 = [0; 32];
+            level1[2] = !0;
+            level1
+        };
+        let trie = CharTrie::from_raw(&LEVEL1, &LEVEL2, (&[0; 256], &[[0; 64]]), &[0, !0]);
+
+        let ranges = [
+            CharRange::closed('\u{80}', '\u{BF}'),
+            CharRange::closed('\u{E000}', '\u{E03F}'),
+        ];
+        assert_eq!(trie, CharSet::from_raw(&ranges));
+        assert_ne!(trie, CharSet::from_raw(&ranges[..1]));
+        assert_ne!(
+            trie,
+            CharSet::from_raw(&[CharRange::closed('\u{80}', '\u{E03F}')])
+        );
+        assert_ne!(trie, CharRange::closed('\u{80}', '\u{BF}'));
+
+        let empty = CharTrie::from_raw(&[0; 32], &[0; 992], (&[0; 256], &[[0; 64]]), &[0]);
+        assert_eq!(empty, CharSet::from_raw(&[]));
+        assert_eq!(empty, CharRange::closed('b', 'a'));
+        assert_ne!(empty, CharRange::singleton(char::MAX));
+    }
+
+    const SPARSE_LEVEL2: [u8; 992] = {
+        let mut level2 = [0; 992];
+        level2[0] = 1;
+        level2[991] = 2;
+        level2
+    };
+    const SPARSE_LEVEL3: [u8; 256] = {
+        let mut level3 = [0; 256];
+        level3[0] = 1;
+        level3[255] = 1;
+        level3
+    };
+    const SPARSE_CHONK: [u8; 64] = {
+        let mut chonk = [0; 64];
+        chonk[0] = 2;
+        chonk[63] = 1;
+        chonk
+    };
+    static SPARSE_LEVEL1: [u64; 32] = {
+        let mut level1 = [0; 32];
+        level1[0] = 1;
+        level1[31] = 1 << 63;
+        level1
+    };
+    // U+0000, U+07FF, U+0808..=U+080F, U+FFC0, U+FFFF, and in the first and last
+    // level3 blocks, U+x0000, U+x003F, and U+x0FC8..=U+x0FCF
+    static SPARSE: CharTrie<'static> = CharTrie::from_raw(
+        &SPARSE_LEVEL1,
+        &SPARSE_LEVEL2,
+        (&SPARSE_LEVEL3, &[[0; 64], SPARSE_CHONK]),
+        &[0, 0xFF00, 0x8000_0000_0000_0001],
+    );
+
+    #[test]
+    fn successors() {
+        let members: alloc::vec::Vec<char> = SPARSE.ranges().flat_map(CharRange::iter).collect();
+        assert_eq!(members.len(), 1 + 1 + 8 + 2 + (2 + 8) * 2);
+        assert_eq!(members[0], '\0');
+        let forward = core::iter::successors(Some('\0'), |&c| SPARSE.next_member(c));
+        assert!(forward.eq(members.iter().copied()));
+        let backward = core::iter::successors(members.last().copied(), |&c| SPARSE.prev_member(c));
+        assert!(backward.eq(members.iter().rev().copied()));
+
+        #[rustfmt::skip]
+        let cases = [
+            ('\0',         Some('\u{7FF}'),   None),
+            ('\u{7FF}',    Some('\u{808}'),   Some('\0')),
+            ('\u{800}',    Some('\u{808}'),   Some('\u{7FF}')),
+            ('\u{D7FF}',   Some('\u{FFC0}'),  Some('\u{80F}')),
+            ('\u{FFFF}',   Some('\u{10000}'), Some('\u{FFC0}')),
+            ('\u{10000}',  Some('\u{1003F}'), Some('\u{FFFF}')),
+            ('\u{10FFFF}', None,               Some('\u{10FFCF}')),
+        ];
+        for &(c, next, prev) in &cases {
+            assert_eq!(SPARSE.next_member(c), next, "{:?}", c);
+            assert_eq!(SPARSE.prev_member(c), prev, "{:?}", c);
+        }
+    }
+
+    #[test]
+    fn contains_many() {
+        let chars = [
+            '\0',
+            '\u{1}',
+            '\u{7FF}',
+            '\u{800}',
+            '\u{808}',
+            '\u{80F}',
+            '\u{810}',
+            '\u{FFC0}',
+            '\u{FFFF}',
+            '\u{10000}',
+            '\u{10FFFF}',
+            '\u{10FFC0}',
+            '\u{10FFC8}',
+        ];
+        let expected = chars
+            .iter()
+            .enumerate()
+            .filter(|&(_, &c)| SPARSE.contains(c))
+            .fold(0, |members, (i, _)| members | 1 << i);
+        assert_eq!(expected, 0b1_0011_1011_0101);
+        assert_eq!(SPARSE.contains_many(&chars), expected);
+        assert_eq!(SPARSE.contains_many(&[]), 0);
+        assert_eq!(SPARSE.contains_many(&['\0'; 64]), !0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn contains_too_many() {
+        SPARSE.contains_many(&['\0'; 65]);
+    }
+
+    #[test]
+    fn count_in_range() {
+        let r = CharRange::closed;
+        #[rustfmt::skip]
+        let cases = [
+            (CharRange::from(..),              1 + 1 + 8 + 2 + (2 + 8) * 2),
+            (CharRange::empty(),               0),
+            (r('\0', '\0'),                    1),
+            (r('\u{1}', '\u{7FE}'),            0),
+            (r('\u{7FF}', '\u{80B}'),          1 + 4),
+            (r('\u{80C}', '\u{FFC0}'),         4 + 1),
+            (r('\u{FFFF}', '\u{10000}'),       2),
+            (r('\u{1003F}', '\u{10FCF}'),      1 + 8),
+            (r('\u{10FFCA}', '\u{10FFFF}'),    6),
+        ];
+        for &(range, count) in &cases {
+            assert_eq!(SPARSE.count_in_range(range), count, "{:?}", range);
+            assert_eq!(range.iter().filter(|&c| SPARSE.contains(c)).count(), count);
+        }
+    }
+
+    #[test]
+    fn count_in_range_surrogates() {
+        const LEVEL2: [u8; 992] = {
+            let mut level2 = [0; 992];
+            let mut idx = (0xD7C0 - 0x800) >> 6;
+            while idx < (0xE040 - 0x800) >> 6 {
+                level2[idx] = 1;
+                idx += 1;
+            }
+            level2
+        };
+        // U+D7C0..=U+E03F, with the bits for the surrogates set
+        static TRIE: CharTrie<'static> =
+            CharTrie::from_raw(&[0; 32], &LEVEL2, (&[0; 256], &[[0; 64]]), &[0, !0]);
+        let r = CharRange::closed;
+        assert_eq!(TRIE.count_in_range(CharRange::from(..)), 128);
+        assert_eq!(TRIE.count_in_range(r('\u{D7FF}', '\u{E000}')), 2);
+        assert_eq!(TRIE.count_in_range(r('\u{E000}', '\u{E000}')), 1);
+    }
+
+    #[test]
+    fn contains_str() {
+        // The ASCII set
+        static TRIE: CharTrie<'static> = CharTrie::from_raw(
+            &[
+                !0, !0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0,
+            ],
+            &[0u8; 992],
+            (&[0u8; 256], &[[0u8; 64]]),
+            &[0],
+        );
+
+        assert!(TRIE.contains_all(""));
+        assert!(TRIE.contains_all("hello"));
+        assert!(!TRIE.contains_all("h\u{E9}llo"));
+        assert!(!TRIE.contains_any(""));
+        assert!(TRIE.contains_any("h\u{E9}llo"));
+        assert!(!TRIE.contains_all("hello\u{E9}"));
+        assert!(TRIE.contains_any("\u{E9}a"));
+        assert!(!TRIE.contains_any("\u{E9}\u{1F600}"));
+
+        // The ASCII digits
+        static DIGITS: [u64; 32] = {
+            let mut level1 = [0; 32];
+            level1[0] = 0x03FF << 0x30;
+            level1
+        };
+        let digits = CharTrie::from_raw(&DIGITS, &[0u8; 992], (&[0u8; 256], &[[0u8; 64]]), &[0]);
+        assert!(digits.contains_all("0123456789"));
+        assert!(!digits.contains_all("12a"));
+        assert!(digits.contains_any("route 66"));
+        assert!(!digits.contains_any("route sixty-six"));
+        assert!(digits.contains_any("\u{E9}7"));
+    }
+
+    #[test]
+    #[cfg(feature = "new-trie")]
+    fn generate_from_ranges() {
+        use alloc::string::ToString;
+
+        let r = CharRange::closed;
+        #[rustfmt::skip]
+        let ranges = [
+            r('\u{1F600}', '\u{1F64F}'), r('a', 'z'), r('\u{D000}', '\u{F000}'),
+            r('b', 'c'), r('\u{7C0}', '\u{840}'), r('\u{10FFF0}', char::MAX),
+            r('\u{E100}', '\u{E100}'), r('z', 'a'),
+        ];
+        let contains = |c| ranges.iter().any(|r| r.contains(c));
+        assert_eq!(
+            super::generate_from_ranges(ranges.iter().copied())
+                .unwrap()
+                .to_string(),
+            super::generate(contains).unwrap().to_string(),
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "new-trie")]
+    fn generate_with_provenance() {
+        use {crate::provenance::SourceFile, alloc::string::ToString, quote::quote};
+
+        let provenance = Provenance {
+            unicode_version: None,
+            sources: &[SourceFile {
+                path: "ascii.txt",
+                hash: "sha256:0",
+            }],
+            options: "ascii",
+        };
+        let trie = super::generate(|c| c.is_ascii()).unwrap();
+        assert_eq!(
+            super::generate_with_provenance(|c| c.is_ascii(), &provenance)
+                .unwrap()
+                .to_string(),
+            quote! {
+                #trie.with_provenance(&Provenance {
+                    unicode_version: None,
+                    sources: &[SourceFile {
+                        path: "ascii.txt",
+                        hash: "sha256:0",
+                    }],
+                    options: "ascii",
+                })
+            }
+            .to_string(),
+        );
+    }
 }
@@ -0,0 +1,487 @@
+#[cfg(feature = "set")]
+use crate::set::CharSet;
+use {
+    crate::{
+        trie::{chunk_mask, chunk_of, CharTrie, GenerateError, Shape},
+        CharRange, AFTER_SURROGATE, BEFORE_SURROGATE,
+    },
+    alloc::{boxed::Box, collections::BTreeSet, vec, vec::Vec},
+    core::{char, convert::TryFrom, fmt},
+};
+
+/// An owned set of codepoints in the same compressed format as `CharTrie`,
+/// which can be built and edited at runtime.
+///
+/// Lookups cost the same as for a `CharTrie`. Unlike generated tries, which
+/// fall back to 16 bit indices, a `CharTrieBuf` only has 8 bit indices, so
+/// stores at most 256 distinct leaf chunks and 256 distinct level3 blocks.
+/// Building or editing a trie which needs more, such as with `from_set`,
+/// fails with `GenerateError`.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::{trie::CharTrieBuf, CharRange};
+/// let mut trie = CharTrieBuf::from_fn(|c| c.is_ascii_digit()).unwrap();
+/// trie.insert_range(CharRange::from('a'..='f')).unwrap();
+/// assert!(trie.contains('7'));
+/// assert!(trie.contains('c'));
+/// assert!(!trie.contains('g'));
+/// ```
+#[derive(Clone)]
+pub struct CharTrieBuf {
+    level1: [u64; 32],
+    level2: Box<[u8; 992]>,
+    level3: (Box<[u8; 256]>, Vec<[u8; 64]>),
+    leaves: Vec<u64>,
+}
+
+impl fmt::Debug for CharTrieBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CharTrieBuf")
+            .field("level1", &Shape(format_args!("[u64; 32]")))
+            .field("level2", &Shape(format_args!("Box<[u8; 992]>")))
+            .field(
+                "level3",
+                &Shape(format_args!(
+                    "(Box<[u8; 256]>, Vec<[u8; 64]; {}>)",
+                    self.level3.1.len()
+                )),
+            )
+            .field(
+                "level4",
+                &Shape(format_args!("Vec<u64; {}>", self.leaves.len())),
+            )
+            .finish()
+    }
+}
+
+impl Default for CharTrieBuf {
+    fn default() -> Self {
+        CharTrieBuf::new()
+    }
+}
+
+impl CharTrieBuf {
+    /// An empty trie.
+    pub fn new() -> Self {
+        CharTrieBuf {
+            level1: [0; 32],
+            level2: Box::new([0; 992]),
+            level3: (Box::new([0; 256]), vec![[0; 64]]),
+            leaves: vec![0],
+        }
+    }
+
+    /// Build a trie from a membership function.
+    ///
+    /// This tests every codepoint once.
+    pub fn from_fn(f: impl Fn(char) -> bool) -> Result<Self, GenerateError> {
+        CharTrieBuf::from_chunks(
+            (0..=char::MAX as u32)
+                .step_by(64)
+                .map(|base| chunk_of(base, &f)),
+        )
+    }
+
+    /// Build a trie with the same members as a set.
+    ///
+    /// This works from the ranges of the set, rather than testing every codepoint.
+    #[cfg(feature = "set")]
+    pub fn from_set(set: &CharSet) -> Result<Self, GenerateError> {
+        CharTrieBuf::from_chunks(set.chunks())
+    }
+
+    /// Build a trie from the membership bits of each 64-codepoint chunk, in order.
+    pub(super) fn from_chunks(
+        chunks: impl IntoIterator<Item = u64>,
+    ) -> Result<Self, GenerateError> {
+        let mut chunks: Vec<u64> = chunks.into_iter().collect();
+        chunks.resize(0x110000 >> 6, 0);
+        check_capacity(&chunks)?;
+        let mut trie = CharTrieBuf {
+            level1: [0; 32],
+            level2: Box::new([0; 992]),
+            level3: (Box::new([0; 256]), Vec::new()),
+            leaves: Vec::new(),
+        };
+        trie.level1.copy_from_slice(&chunks[..32]);
+        for (idx, &chunk) in chunks[32..1024].iter().enumerate() {
+            let c = 0x800 + (idx as u32) * 64;
+            trie.level2[idx] = trie.leaf_index(chunk, c)?;
+        }
+        for (idx, block) in chunks[1024..].chunks(64).enumerate() {
+            let c = 0x10000 + (idx as u32) * 0x1000;
+            let mut chonk = [0; 64];
+            for (slot, &chunk) in chonk.iter_mut().zip(block) {
+                *slot = trie.leaf_index(chunk, c)?;
+            }
+            trie.level3.0[idx] = trie.chonk_index(chonk, c)?;
+        }
+        Ok(trie)
+    }
+
+    /// Borrow this set as a `CharTrie`, to use the code written for tries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::{trie::CharTrieBuf, CharRange};
+    /// let mut trie = CharTrieBuf::new();
+    /// trie.insert_range(CharRange::from('a'..='z')).unwrap();
+    /// assert_eq!(trie.as_trie().ranges().collect::<Vec<_>>(), [CharRange::from('a'..='z')]);
+    /// ```
+    pub fn as_trie(&self) -> CharTrie<'_> {
+        let level3 = (&*self.level3.0, &self.level3.1[..]);
+        CharTrie::from_raw(&self.level1, &self.level2, level3, &self.leaves)
+    }
+
+    /// Does this set contain this codepoint?
+    pub fn contains(&self, c: char) -> bool {
+        self.as_trie().contains(c)
+    }
+
+    /// Serialize this trie, as `CharTrie::to_bytes`.
+    ///
+    /// The bytes can be loaded back with `CharTrie::from_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.as_trie().to_bytes()
+    }
+
+    /// Insert a single codepoint into this set.
+    ///
+    /// Returns whether the codepoint was newly inserted.
+    pub fn insert(&mut self, c: char) -> Result<bool, GenerateError> {
+        let bit = 1 << (c as u32 & 0b_111_111);
+        let old = self.update_chunk(c as u32, |chunk| chunk | bit)?;
+        Ok(old & bit == 0)
+    }
+
+    /// Remove a single codepoint from this set.
+    ///
+    /// Returns whether the codepoint was present in the set.
+    pub fn remove(&mut self, c: char) -> Result<bool, GenerateError> {
+        let bit = 1 << (c as u32 & 0b_111_111);
+        let old = self.update_chunk(c as u32, |chunk| chunk & !bit)?;
+        Ok(old & bit != 0)
+    }
+
+    /// Insert a range of codepoints into this set.
+    ///
+    /// Returns how many codepoints were newly inserted.
+    /// If this fails, the chunks of the range below the failing one have still been inserted.
+    pub fn insert_range(&mut self, r: CharRange) -> Result<usize, GenerateError> {
+        let mut inserted = 0;
+        self.for_each_chunk(r, |this, base, mask| {
+            let old = this.update_chunk(base, |chunk| chunk | mask)?;
+            inserted += (mask & !old).count_ones() as usize;
+            Ok(())
+        })?;
+        Ok(inserted)
+    }
+
+    /// Remove a range of codepoints from this set.
+    ///
+    /// Returns how many codepoints were removed.
+    /// If this fails, the chunks of the range below the failing one have still been removed.
+    pub fn remove_range(&mut self, r: CharRange) -> Result<usize, GenerateError> {
+        let mut removed = 0;
+        self.for_each_chunk(r, |this, base, mask| {
+            let old = this.update_chunk(base, |chunk| chunk & !mask)?;
+            removed += (mask & old).count_ones() as usize;
+            Ok(())
+        })?;
+        Ok(removed)
+    }
+
+    /// Replace this set with its complement, every codepoint it does not contain.
+    ///
+    /// This flips the bits of each distinct leaf once, rather than testing every
+    /// codepoint. The surrogates stay out of the set. If this fails, the set is unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mileage::trie::CharTrieBuf;
+    /// let mut trie = CharTrieBuf::from_fn(char::is_whitespace).unwrap();
+    /// trie.complement().unwrap();
+    /// assert!(!trie.contains(' '));
+    /// assert!(trie.contains('a'));
+    /// ```
+    pub fn complement(&mut self) -> Result<(), GenerateError> {
+        self.compact();
+        // the full leaf becomes the empty one, for the surrogates
+        let empty = self.leaf_index(!0, 0x800)?;
+        for chunk in self.level1.iter_mut().chain(&mut self.leaves) {
+            *chunk = !*chunk;
+        }
+        let surrogates =
+            (BEFORE_SURROGATE as usize + 1 - 0x800) >> 6..(AFTER_SURROGATE as usize - 0x800) >> 6;
+        for idx in &mut self.level2[surrogates] {
+            *idx = empty;
+        }
+        Ok(())
+    }
+
+    /// Call `f` with the base and mask of each chunk overlapping a range,
+    /// skipping the chunks of surrogates.
+    fn for_each_chunk(
+        &mut self,
+        r: CharRange,
+        mut f: impl FnMut(&mut Self, u32, u64) -> Result<(), GenerateError>,
+    ) -> Result<(), GenerateError> {
+        if r.is_empty() {
+            return Ok(());
+        }
+        let (low, high) = (r.low as u32, r.high as u32);
+        for base in (low & !0b_111_111..=high).step_by(64) {
+            let mask = chunk_mask(base, low, high);
+            if mask != 0 {
+                f(self, base, mask)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The membership bits of the 64-aligned chunk of codepoints holding `c`.
+    fn chunk(&self, c: u32) -> u64 {
+        self.as_trie().chunk(c)
+    }
+
+    /// Replace the chunk of codepoints holding `c`, returning the old chunk.
+    ///
+    /// If this fails, the set is unchanged.
+    fn update_chunk(&mut self, c: u32, f: impl FnOnce(u64) -> u64) -> Result<u64, GenerateError> {
+        let old = self.chunk(c);
+        let new = f(old);
+        if new == old {
+            return Ok(old);
+        }
+        if self.leaves.len() > u8::MAX as usize || self.level3.1.len() > u8::MAX as usize {
+            self.compact();
+        }
+        if c < 0x800 {
+            self.level1[(c >> 6) as usize] = new;
+        } else if c < 0x10000 {
+            let leaf = self.leaf_index(new, c)?;
+            self.level2[((c - 0x800) >> 6) as usize] = leaf;
+        } else {
+            let leaf = self.leaf_index(new, c)?;
+            let idx = ((c - 0x10000) >> 12) as usize;
+            let mut chonk = self.level3.1[self.level3.0[idx] as usize];
+            chonk[((c >> 6) & 63) as usize] = leaf;
+            let chonk = self.chonk_index(chonk, c)?;
+            self.level3.0[idx] = chonk;
+        }
+        Ok(old)
+    }
+
+    /// The index of a leaf chunk for the codepoints around `c`, adding it if it is new.
+    fn leaf_index(&mut self, chunk: u64, c: u32) -> Result<u8, GenerateError> {
+        let needed = self.leaves.len() + 1;
+        index_of(&mut self.leaves, chunk).ok_or_else(|| GenerateError::leaves(needed, 256, c))
+    }
+
+    /// The index of a level3 block for the codepoints around `c`, adding it if it is new.
+    fn chonk_index(&mut self, chonk: [u8; 64], c: u32) -> Result<u8, GenerateError> {
+        let needed = self.level3.1.len() + 1;
+        index_of(&mut self.level3.1, chonk).ok_or_else(|| GenerateError::level3_blocks(needed, c))
+    }
+
+    /// Drop the leaves and level3 blocks which edits have left unused.
+    fn compact(&mut self) {
+        let mut used = [false; 256];
+        for &idx in self.level3.0.iter() {
+            used[idx as usize] = true;
+        }
+        let remap = retain_used(&mut self.level3.1, &used);
+        for idx in self.level3.0.iter_mut() {
+            *idx = remap[*idx as usize];
+        }
+
+        let mut used = [false; 256];
+        for &idx in self.level2.iter().chain(self.level3.1.iter().flatten()) {
+            used[idx as usize] = true;
+        }
+        let remap = retain_used(&mut self.leaves, &used);
+        for idx in self
+            .level2
+            .iter_mut()
+            .chain(self.level3.1.iter_mut().flatten())
+        {
+            *idx = remap[*idx as usize];
+        }
+    }
+}
+
+/// The index of an item in a table of at most 256 distinct items,
+/// adding it if it is new, or `None` if the table is full.
+fn index_of<T: PartialEq>(table: &mut Vec<T>, item: T) -> Option<u8> {
+    match table.iter().position(|it| *it == item) {
+        Some(idx) => u8::try_from(idx).ok(),
+        None => {
+            let idx = u8::try_from(table.len()).ok()?;
+            table.push(item);
+            Some(idx)
+        }
+    }
+}
+
+/// Check that a full list of chunks fits in at most 256 distinct leaves
+/// and 256 distinct level3 blocks, counting how many are needed if not.
+fn check_capacity(chunks: &[u64]) -> Result<(), GenerateError> {
+    let mut leaves = BTreeSet::new();
+    let mut overflow = None;
+    for (idx, &chunk) in chunks[32..].iter().enumerate() {
+        if leaves.insert(chunk) && leaves.len() == 257 {
+            overflow = Some(0x800 + (idx as u32) * 64);
+        }
+    }
+    if let Some(c) = overflow {
+        return Err(GenerateError::leaves(leaves.len(), 256, c));
+    }
+
+    let mut blocks = BTreeSet::new();
+    for (idx, block) in chunks[1024..].chunks(64).enumerate() {
+        if blocks.insert(block) && blocks.len() == 257 {
+            overflow = Some(0x10000 + (idx as u32) * 0x1000);
+        }
+    }
+    match overflow {
+        Some(c) => Err(GenerateError::level3_blocks(blocks.len(), c)),
+        None => Ok(()),
+    }
+}
+
+/// Keep only the used items of a table, returning where each old index moved.
+fn retain_used<T: Copy>(table: &mut Vec<T>, used: &[bool; 256]) -> [u8; 256] {
+    let mut remap = [0; 256];
+    let mut kept = 0;
+    for idx in 0..table.len() {
+        if used[idx] {
+            table[kept] = table[idx];
+            remap[idx] = kept as u8;
+            kept += 1;
+        }
+    }
+    table.truncate(kept);
+    remap
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn complement() {
+        let f = |c: char| c.is_whitespace() || c == '\u{1F600}';
+        let mut trie = CharTrieBuf::from_fn(f).unwrap();
+        trie.complement().unwrap();
+        for c in CharRange::from(..) {
+            assert_eq!(trie.contains(c), !f(c), "{:?}", c);
+        }
+        assert_eq!(trie.chunk(0xD800), 0);
+        trie.complement().unwrap();
+        for c in CharRange::from(..) {
+            assert_eq!(trie.contains(c), f(c), "{:?}", c);
+        }
+
+        // 256 leaves, none of them full
+        let mut trie = CharTrieBuf::from_fn(|c| {
+            (c as u32 & 63) < 8 && (c as u32 >> 6) >> (c as u32 & 7) & 1 == 1
+        })
+        .unwrap();
+        let before = trie.to_bytes();
+        assert!(trie.complement().is_err());
+        assert_eq!(trie.to_bytes(), before);
+    }
+
+    #[test]
+    fn from_fn() {
+        let trie = CharTrieBuf::from_fn(|c| c.is_ascii_alphabetic() || c == '\u{1F600}').unwrap();
+        for c in CharRange::from(..) {
+            assert_eq!(
+                trie.contains(c),
+                c.is_ascii_alphabetic() || c == '\u{1F600}',
+                "{:?}",
+                c
+            );
+        }
+
+        // every 64-codepoint chunk is different, apart from the empty surrogates
+        let distinct =
+            |c: char| (c as u32 & 63) < 16 && (c as u32 >> 6) >> (c as u32 & 63) & 1 == 1;
+        assert_eq!(
+            CharTrieBuf::from_fn(distinct).unwrap_err(),
+            GenerateError::Leaves {
+                needed: 17345,
+                max: 256,
+                region: CharRange::closed('\u{4800}', '\u{483F}'),
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "set")]
+    fn from_set() {
+        let ranges = [
+            CharRange::closed('0', '9'),
+            CharRange::closed('\u{7FF}', '\u{800}'),
+            CharRange::closed('\u{D7FF}', '\u{E000}'),
+            CharRange::closed('\u{FFFF}', '\u{10040}'),
+            CharRange::closed('\u{10FFFF}', '\u{10FFFF}'),
+        ];
+        let set = CharSet::from_raw(&ranges);
+        let trie = CharTrieBuf::from_set(set).unwrap();
+        for c in CharRange::from(..) {
+            assert_eq!(trie.contains(c), set.contains(c), "{:?}", c);
+        }
+    }
+
+    #[test]
+    fn edits() {
+        let mut trie = CharTrieBuf::new();
+        #[rustfmt::skip]
+        let ranges = [
+            CharRange::closed('a', 'z'),
+            CharRange::closed('\u{7C0}', '\u{840}'),
+            CharRange::closed('\u{D7F0}', '\u{E010}'),
+            CharRange::closed('\u{1F600}', '\u{1F64F}'),
+        ];
+        let mut len = 0;
+        for &r in &ranges {
+            len += r.len();
+            assert_eq!(trie.insert_range(r), Ok(r.len()));
+            assert_eq!(trie.insert_range(r), Ok(0));
+        }
+        assert_eq!(trie.insert('a'), Ok(false));
+        assert_eq!(trie.remove('b'), Ok(true));
+        assert_eq!(trie.remove('b'), Ok(false));
+        assert_eq!(trie.insert('b'), Ok(true));
+        assert_eq!(
+            trie.remove_range(CharRange::closed('\u{800}', '\u{1F610}')),
+            Ok(len - 26 - 64 - 63)
+        );
+        assert!(trie.contains('\u{7FF}'));
+        assert!(!trie.contains('\u{800}'));
+        assert!(trie.contains('\u{1F611}'));
+    }
+
+    #[test]
+    fn compaction() {
+        // every edit replaces the only astral leaf, so this needs compaction
+        let mut trie = CharTrieBuf::new();
+        for bit in 0..64 {
+            let c = char::from_u32(0x10000 + bit).unwrap();
+            for _ in 0..8 {
+                assert_eq!(trie.insert(c), Ok(true));
+                assert_eq!(trie.remove(c), Ok(true));
+            }
+            assert_eq!(trie.insert(c), Ok(true));
+        }
+        assert!(CharRange::closed('\u{10000}', '\u{1003F}')
+            .iter()
+            .all(|c| trie.contains(c)));
+        assert!(!trie.contains('\u{10040}'));
+    }
+}
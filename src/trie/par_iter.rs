@@ -0,0 +1,158 @@
+#[cfg(feature = "owned-trie")]
+use crate::trie::CharTrieBuf;
+use {
+    crate::trie::CharTrie,
+    core::{char, ops::Range},
+    rayon::{
+        iter::plumbing::{bridge_unindexed, Folder, UnindexedConsumer, UnindexedProducer},
+        prelude::*,
+    },
+};
+
+/// The number of chunks in a level3 block, below which the chunks are not split further.
+const BLOCK_CHUNKS: u32 = 64;
+
+/// A parallel iterator over the members of a trie.
+///
+/// The chunks of 64 codepoints are split between threads along the
+/// boundaries of level3 blocks, so each thread reads whole blocks.
+#[derive(Clone, Debug)]
+pub struct Iter<'a> {
+    trie: CharTrie<'a>,
+}
+
+impl ParallelIterator for Iter<'_> {
+    type Item = char;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        let producer = ChunksProducer {
+            trie: self.trie,
+            chunks: 0..(char::MAX as u32 >> 6) + 1,
+        };
+        bridge_unindexed(producer, consumer)
+    }
+}
+
+impl<'a> IntoParallelIterator for &CharTrie<'a> {
+    type Iter = Iter<'a>;
+    type Item = char;
+
+    fn into_par_iter(self) -> Iter<'a> {
+        Iter { trie: *self }
+    }
+}
+
+#[cfg(feature = "owned-trie")]
+impl<'a> IntoParallelIterator for &'a CharTrieBuf {
+    type Iter = Iter<'a>;
+    type Item = char;
+
+    fn into_par_iter(self) -> Iter<'a> {
+        Iter {
+            trie: self.as_trie(),
+        }
+    }
+}
+
+/// Produces the members of a trie in a range of chunk indices.
+struct ChunksProducer<'a> {
+    trie: CharTrie<'a>,
+    chunks: Range<u32>,
+}
+
+impl UnindexedProducer for ChunksProducer<'_> {
+    type Item = char;
+
+    fn split(self) -> (Self, Option<Self>) {
+        let Range { start, end } = self.chunks;
+        let blocks = (end - start) / BLOCK_CHUNKS;
+        if blocks < 2 {
+            return (self, None);
+        }
+        // the chunks always start and end on block boundaries
+        let mid = start + blocks / 2 * BLOCK_CHUNKS;
+        (
+            ChunksProducer {
+                trie: self.trie,
+                chunks: start..mid,
+            },
+            Some(ChunksProducer {
+                trie: self.trie,
+                chunks: mid..end,
+            }),
+        )
+    }
+
+    fn fold_with<F: Folder<Self::Item>>(self, mut folder: F) -> F {
+        for idx in self.chunks {
+            let base = idx << 6;
+            let mut chunk = self.trie.chunk(base);
+            while chunk != 0 {
+                let bit = chunk.trailing_zeros();
+                chunk &= chunk - 1;
+                if let Some(c) = char::from_u32(base + bit) {
+                    folder = folder.consume(c);
+                    if folder.full() {
+                        return folder;
+                    }
+                }
+            }
+        }
+        folder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::CharRange, alloc::vec::Vec};
+
+    #[test]
+    fn agrees() {
+        const LEVEL2: [u8; 992] = {
+            let mut level2 = [0; 992];
+            level2[0] = 1;
+            level2[991] = 1;
+            level2
+        };
+        const LEVEL3: [u8; 256] = {
+            let mut level3 = [0; 256];
+            level3[0] = 1;
+            level3[255] = 1;
+            level3
+        };
+        const CHONK: [u8; 64] = {
+            let mut chonk = [0; 64];
+            chonk[0] = 2;
+            chonk[63] = 1;
+            chonk
+        };
+        let level1 = {
+            let mut level1 = [0; 32];
+            level1[1] = 0x03FF << 0x30;
+            level1[31] = 1 << 63;
+            level1
+        };
+        static LEAVES: [u64; 3] = [0, !0, 0x8000_0000_0000_0001];
+        let trie = CharTrie::from_raw(&level1, &LEVEL2, (&LEVEL3, &[[0; 64], CHONK]), &LEAVES);
+
+        let chars: Vec<char> = trie.ranges().flat_map(CharRange::iter).collect();
+        assert_eq!(chars.len(), 10 + 1 + 64 * 2 + (2 + 64) * 2);
+        assert_eq!(trie.par_iter().collect::<Vec<_>>(), chars);
+        assert_eq!(trie.par_iter().count(), chars.len());
+        assert_eq!(trie.par_iter().find_first(|&c| c > 'z'), Some('\u{7FF}'));
+    }
+
+    #[test]
+    #[cfg(feature = "owned-trie")]
+    fn owned_agrees() {
+        let trie = CharTrieBuf::from_fn(|c| c.is_whitespace() || c.is_ascii_digit()).unwrap();
+        let chars: Vec<char> = CharRange::from(..)
+            .iter()
+            .filter(|&c| trie.contains(c))
+            .collect();
+        assert_eq!((&trie).into_par_iter().collect::<Vec<_>>(), chars);
+    }
+}
@@ -0,0 +1,117 @@
+use {
+    crate::trie::CharTrie,
+    serde::ser::{Serialize, Serializer},
+};
+#[cfg(feature = "owned-trie")]
+use {
+    crate::{trie::CharTrieBuf, CharRange},
+    core::fmt,
+    serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor},
+};
+
+/// A trie is serialized as the sequence of its ranges, each a `(low, high)` pair
+/// of codepoints. This is independent of the layout of the trie's tables.
+impl Serialize for CharTrie<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.ranges().map(|r| (r.low, r.high)))
+    }
+}
+
+/// Serialized as `CharTrie` is.
+#[cfg(feature = "owned-trie")]
+impl Serialize for CharTrieBuf {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_trie().serialize(serializer)
+    }
+}
+
+/// Deserialized from a sequence of `(low, high)` ranges, which may be in any
+/// order and may overlap. Fails if the set needs more than 256 distinct leaves
+/// or level3 blocks, with the message of the `GenerateError`.
+#[cfg(feature = "owned-trie")]
+impl<'de> Deserialize<'de> for CharTrieBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RangesVisitor;
+
+        impl<'de> Visitor<'de> for RangesVisitor {
+            type Value = CharTrieBuf;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a sequence of codepoint ranges")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<CharTrieBuf, A::Error> {
+                let mut trie = CharTrieBuf::new();
+                while let Some((low, high)) = seq.next_element::<(char, char)>()? {
+                    if low > high {
+                        return Err(de::Error::custom("codepoint range is reversed"));
+                    }
+                    trie.insert_range(CharRange::closed(low, high))
+                        .map_err(de::Error::custom)?;
+                }
+                Ok(trie)
+            }
+        }
+
+        deserializer.deserialize_seq(RangesVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialize() {
+        static ASCII: [u64; 32] = {
+            let mut level1 = [0; 32];
+            level1[0] = !0;
+            level1[1] = !0;
+            level1
+        };
+        let trie = CharTrie::from_raw(&ASCII, &[0; 992], (&[0; 256], &[[0; 64]]), &[0]);
+        assert_eq!(
+            serde_json::to_string(&trie).unwrap(),
+            "[[\"\\u0000\",\"\u{7F}\"]]"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "owned-trie")]
+    fn round_trip() {
+        #[rustfmt::skip]
+        let test_data: &[&str] = &[
+            r#"[]"#,
+            r#"[["a","z"]]"#,
+            r#"[["0","9"],["A","Z"],["a","z"]]"#,
+            "[[\"\u{D7FF}\",\"\u{D7FF}\"],[\"\u{E000}\",\"\u{10FFFF}\"]]",
+        ];
+        for &json in test_data {
+            let trie: CharTrieBuf = serde_json::from_str(json).unwrap();
+            assert_eq!(serde_json::to_string(&trie).unwrap(), json);
+        }
+
+        let trie: CharTrieBuf = serde_json::from_str(r#"[["x","z"],["a","y"],["b","c"]]"#).unwrap();
+        assert_eq!(serde_json::to_string(&trie).unwrap(), r#"[["a","z"]]"#);
+        assert!(serde_json::from_str::<CharTrieBuf>(r#"[["z","a"]]"#).is_err());
+        assert!(serde_json::from_str::<CharTrieBuf>(r#"[["a"]]"#).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "owned-trie")]
+    fn too_many_leaves() {
+        use alloc::{string::ToString, vec::Vec};
+
+        // a distinct leaf for each of 300 chunks
+        let ranges: Vec<(char, char)> = (0..300)
+            .map(|i| {
+                let low = 0x800 + i * 64 + i / 60;
+                let (low, high) = (char::from_u32(low), char::from_u32(low + i % 60));
+                (low.unwrap(), high.unwrap())
+            })
+            .collect();
+        let json = serde_json::to_string(&ranges).unwrap();
+        let err = serde_json::from_str::<CharTrieBuf>(&json).unwrap_err();
+        assert!(err.to_string().contains("distinct leaves"), "{}", err);
+    }
+}
@@ -0,0 +1,210 @@
+use {
+    crate::{
+        trie::{GenerateError, Tables},
+        CharRange,
+    },
+    alloc::string::String,
+    core::fmt::{Display, UpperHex, Write},
+};
+
+/// Generate the source of a new trie from a membership function.
+///
+/// This behaves as `generate`, but returns the Rust code as a `String`,
+/// so does not need `proc-macro2` or `quote`. The code is legal in expression
+/// position and evaluates to a `CharTrie`. Requires that `CharTrie` is in scope.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::trie::generate_source;
+/// let src = generate_source(|c| c.is_ascii())?;
+/// assert!(src.starts_with("CharTrie::from_raw(\n    &[0xFFFFFFFFFFFFFFFF, 0xFFFFFFFFFFFFFFFF, 0x0,"));
+/// # Ok::<(), mileage::trie::GenerateError>(())
+/// ```
+pub fn generate_source(f: impl Fn(char) -> bool) -> Result<String, GenerateError> {
+    Ok(Tables::from_fn(f)?.to_source())
+}
+
+/// Generate the source of a new trie from ranges of codepoints.
+///
+/// This behaves as `generate_from_ranges`, but returns the Rust code as a `String`,
+/// as `generate_source`.
+pub fn generate_source_from_ranges(
+    ranges: impl IntoIterator<Item = CharRange>,
+) -> Result<String, GenerateError> {
+    Ok(Tables::from_ranges(ranges)?.to_source())
+}
+
+/// Generate the source of a new trie from a membership function,
+/// laid out for a checked-in generated file.
+///
+/// This behaves as `generate_source`, but every table is split over lines
+/// of a fixed number of items, at most 100 columns wide, so a change to the
+/// set only changes the lines of the items it touches. The layout depends
+/// only on the tables, not on `rustfmt`, so the generated file should be
+/// excluded from formatting, as with `#[rustfmt::skip]` on the item it
+/// initializes.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::trie::generate_source_wrapped;
+/// let src = generate_source_wrapped(|c| c.is_ascii())?;
+/// assert!(src.starts_with("CharTrie::from_raw(\n    &[\n        0xFFFFFFFFFFFFFFFF, 0xFFFFFFFFFFFFFFFF, 0x0, 0x0,\n"));
+/// assert!(src.lines().all(|line| line.len() <= 100));
+/// # Ok::<(), mileage::trie::GenerateError>(())
+/// ```
+pub fn generate_source_wrapped(f: impl Fn(char) -> bool) -> Result<String, GenerateError> {
+    Ok(Tables::from_fn(f)?.to_source_wrapped())
+}
+
+/// Write a comma-separated list of items in brackets, in decimal or hexadecimal.
+fn list<T: Display + UpperHex>(src: &mut String, items: impl IntoIterator<Item = T>, hex: bool) {
+    src.push('[');
+    for (i, item) in items.into_iter().enumerate() {
+        if i != 0 {
+            src.push_str(", ");
+        }
+        if hex {
+            write!(src, "{:#X}", item).unwrap();
+        } else {
+            write!(src, "{}", item).unwrap();
+        }
+    }
+    src.push(']');
+}
+
+/// Write a list of items in brackets as `list`, indented one level past `indent`,
+/// with a fixed number of items on each line, so no line is wider than 100 columns.
+fn wrapped_list<T: Display + UpperHex>(
+    src: &mut String,
+    items: impl IntoIterator<Item = T>,
+    hex: bool,
+    indent: &str,
+) {
+    // a leaf is at most 18 characters in hexadecimal, and an index at most 5 in decimal
+    let per_line = if hex { 4 } else { 12 };
+    src.push_str("[\n");
+    for (i, item) in items.into_iter().enumerate() {
+        if i % per_line == 0 {
+            if i != 0 {
+                src.push('\n');
+            }
+            src.push_str(indent);
+            src.push_str("    ");
+        } else {
+            src.push(' ');
+        }
+        if hex {
+            write!(src, "{:#X},", item).unwrap();
+        } else {
+            write!(src, "{},", item).unwrap();
+        }
+    }
+    src.push('\n');
+    src.push_str(indent);
+    src.push(']');
+}
+
+impl Tables {
+    /// Rust source for an expression constructing the only `CharTrie` of these tables.
+    pub(super) fn to_source(&self) -> String {
+        let mut src = String::new();
+        if self.is_narrow() {
+            src.push_str("CharTrie::from_raw(\n    &");
+        } else {
+            src.push_str("CharTrie::from_raw_wide(\n    &");
+        }
+        let (level1, level2, first) = &self.tries[0];
+        list(&mut src, level1, true);
+        src.push_str(",\n    &");
+        list(&mut src, level2, false);
+        src.push_str(",\n    (&");
+        list(&mut src, first, false);
+        src.push_str(", &[");
+        for (i, chonk) in self.chonks.iter().enumerate() {
+            if i != 0 {
+                src.push_str(", ");
+            }
+            list(&mut src, chonk, false);
+        }
+        src.push_str("]),\n    &");
+        list(&mut src, &self.leaves, true);
+        src.push_str(",\n)");
+        src
+    }
+
+    /// Rust source for the same expression as `to_source`, with every table
+    /// split over lines as by `wrapped_list`.
+    pub(super) fn to_source_wrapped(&self) -> String {
+        let mut src = String::new();
+        if self.is_narrow() {
+            src.push_str("CharTrie::from_raw(\n    &");
+        } else {
+            src.push_str("CharTrie::from_raw_wide(\n    &");
+        }
+        let (level1, level2, first) = &self.tries[0];
+        wrapped_list(&mut src, level1, true, "    ");
+        src.push_str(",\n    &");
+        wrapped_list(&mut src, level2, false, "    ");
+        src.push_str(",\n    (\n        &");
+        wrapped_list(&mut src, first, false, "        ");
+        src.push_str(",\n        &[\n");
+        for chonk in &self.chonks {
+            src.push_str("            ");
+            wrapped_list(&mut src, chonk, false, "            ");
+            src.push_str(",\n");
+        }
+        src.push_str("        ],\n    ),\n    &");
+        wrapped_list(&mut src, &self.leaves, true, "    ");
+        src.push_str(",\n)");
+        src
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii() {
+        let zeros = |n, zero| [zero].repeat(n).join(", ");
+        let expected = alloc::format!(
+            "CharTrie::from_raw(\n    &[0xFFFFFFFFFFFFFFFF, 0xFFFFFFFFFFFFFFFF, {}],\n    &[{}],\n    (&[{}], &[[{}]]),\n    &[0x0],\n)",
+            zeros(30, "0x0"),
+            zeros(992, "0"),
+            zeros(256, "0"),
+            zeros(64, "0"),
+        );
+        assert_eq!(generate_source(|c| c.is_ascii()).unwrap(), expected);
+        let ascii = CharRange::closed('\0', '\x7F');
+        assert_eq!(generate_source_from_ranges(Some(ascii)).unwrap(), expected);
+    }
+
+    #[test]
+    fn wrapped() {
+        let src = generate_source_wrapped(|c| c.is_ascii()).unwrap();
+        assert!(src.lines().all(|line| line.len() <= 100));
+        // level1 in 8 lines, level2 in 83, the first table of level3 in 22,
+        // one block in 6, and the leaves in 1, each between its brackets
+        let tables = (8 + 2) + (83 + 2) + (1 + (22 + 2) + 1 + (6 + 2) + 1 + 1) + (1 + 2);
+        assert_eq!(src.lines().count(), tables + 2);
+        let unwrapped: String = src.split_whitespace().collect();
+        let expected: String = generate_source(|c| c.is_ascii())
+            .unwrap()
+            .split_whitespace()
+            .collect();
+        let trim = |src: String| src.replace(",]", "]").replace(",)", ")");
+        assert_eq!(trim(unwrapped), trim(expected));
+    }
+
+    #[test]
+    fn wide() {
+        // every 64-codepoint chunk below U+10000 is different
+        let distinct =
+            |c: char| (c as u32 & 63) < 16 && ((c as u32 >> 6) >> (c as u32 & 63)) & 1 == 1;
+        assert!(generate_source(distinct)
+            .unwrap()
+            .starts_with("CharTrie::from_raw_wide(\n"));
+    }
+}
@@ -0,0 +1,266 @@
+use {
+    crate::trie::{Shape, TrieError},
+    core::fmt,
+};
+
+/// A compressed trie-like set with a configurable split of the codepoint bits.
+///
+/// Where a `CharTrie` has a fixed geometry, leaves of 64 codepoints and
+/// level3 blocks of 64 leaves, this has leaves of `1 << LEAF_BITS` codepoints,
+/// and blocks of `1 << BLOCK_BITS` leaves, indexed by a root table over the
+/// remaining high bits. Sets with large runs, such as those of CJK ideographs,
+/// often compress better with larger leaves and blocks.
+///
+/// All indices are 16 bit. `LEAF_BITS` must be at least 6, and `LEAF_BITS + BLOCK_BITS`
+/// at most 21, or constructing the trie panics.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::trie::CharTrieSplit;
+/// // leaves of 256 codepoints, blocks of 16 leaves
+/// static ROOT: [u16; 272] = {
+///     let mut root = [0; 272];
+///     root[0] = 1;
+///     root
+/// };
+/// static BLOCKS: [u16; 32] = {
+///     let mut blocks = [0; 32];
+///     blocks[16] = 1;
+///     blocks
+/// };
+/// static LEAVES: [u64; 8] = [0, 0, 0, 0, 0x03FF << 0x30, 0, 0, 0];
+/// static DIGITS: CharTrieSplit<8, 4> = CharTrieSplit::from_raw(&ROOT, &BLOCKS, &LEAVES);
+/// assert!(DIGITS.contains('7'));
+/// assert!(!DIGITS.contains('a'));
+/// assert!(!DIGITS.contains('\u{1D7D8}'));
+/// ```
+#[derive(Copy, Clone)]
+pub struct CharTrieSplit<const LEAF_BITS: u32, const BLOCK_BITS: u32> {
+    root: &'static [u16],
+    blocks: &'static [u16],
+    leaves: &'static [u64],
+}
+
+impl<const LEAF_BITS: u32, const BLOCK_BITS: u32> fmt::Debug
+    for CharTrieSplit<LEAF_BITS, BLOCK_BITS>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CharTrieSplit")
+            .field("leaf_bits", &LEAF_BITS)
+            .field("block_bits", &BLOCK_BITS)
+            .field("root", &Shape(format_args!("&[u16; {}]", self.root.len())))
+            .field(
+                "blocks",
+                &Shape(format_args!("&[u16; dyn {}]", self.blocks.len())),
+            )
+            .field(
+                "leaves",
+                &Shape(format_args!("&[u64; dyn {}]", self.leaves.len())),
+            )
+            .finish()
+    }
+}
+
+impl<const LEAF_BITS: u32, const BLOCK_BITS: u32> CharTrieSplit<LEAF_BITS, BLOCK_BITS> {
+    /// How many entries the root table has.
+    pub const ROOT_LEN: usize = (0x10FFFF >> (LEAF_BITS + BLOCK_BITS)) + 1;
+
+    /// How many `u64`s each leaf is.
+    const LEAF_WORDS: usize = 1 << (LEAF_BITS - 6);
+
+    /// Does this set contain this codepoint?
+    #[inline]
+    pub fn contains(&self, c: char) -> bool {
+        let c = c as u32 as usize;
+        let block = usize::from(self.root[c >> (LEAF_BITS + BLOCK_BITS)]);
+        let in_block = (c >> LEAF_BITS) & ((1 << BLOCK_BITS) - 1);
+        let leaf = usize::from(self.blocks[(block << BLOCK_BITS) | in_block]);
+        let word = self.leaves[leaf * Self::LEAF_WORDS + ((c >> 6) & (Self::LEAF_WORDS - 1))];
+        (word >> (c & 0b_111_111)) & 1 == 1
+    }
+
+    /// Create a trie from its components.
+    ///
+    /// - `root` holds, for each `1 << (LEAF_BITS + BLOCK_BITS)` codepoints,
+    ///   the index of their block.
+    /// - `blocks` is the blocks laid end to end, each of `1 << BLOCK_BITS`
+    ///   indices of leaves.
+    /// - `leaves` is the leaves laid end to end, each of `1 << (LEAF_BITS - 6)`
+    ///   `u64`s of membership bits.
+    ///
+    /// Panics if the geometry is invalid or `root` is not `ROOT_LEN` long.
+    /// The indices are not checked; looking up a codepoint through an index
+    /// which is out of bounds panics. Use `validate` to check them.
+    pub const fn from_raw(
+        root: &'static [u16],
+        blocks: &'static [u16],
+        leaves: &'static [u64],
+    ) -> Self {
+        assert!(LEAF_BITS >= 6 && LEAF_BITS + BLOCK_BITS <= 21);
+        assert!(root.len() == Self::ROOT_LEN);
+        CharTrieSplit {
+            root,
+            blocks,
+            leaves,
+        }
+    }
+
+    /// Check that every index of this trie is in bounds,
+    /// such that looking up any codepoint will not panic.
+    ///
+    /// An out of bounds root index is reported as `TrieError::Level3`,
+    /// and an out of bounds leaf index of a block as `TrieError::Level3Block`.
+    pub fn validate(&self) -> Result<(), TrieError> {
+        let block_count = self.blocks.len() >> BLOCK_BITS;
+        let leaf_count = self.leaves.len() / Self::LEAF_WORDS;
+        if let Some(i) = self
+            .root
+            .iter()
+            .position(|&idx| usize::from(idx) >= block_count)
+        {
+            return Err(TrieError::Level3(i));
+        }
+        for (block, indices) in self.blocks.chunks_exact(1 << BLOCK_BITS).enumerate() {
+            if let Some(i) = indices
+                .iter()
+                .position(|&idx| usize::from(idx) >= leaf_count)
+            {
+                return Err(TrieError::Level3Block(block, i));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Generate a new trie with a configurable geometry from a membership function.
+///
+/// This constructs Rust code that is legal in expression position that
+/// evaluates to a `CharTrieSplit<LEAF_BITS, BLOCK_BITS>`.
+/// Requires that `CharTrieSplit` is in scope.
+///
+/// Unlike `generate`, this cannot run out of indices, as no geometry needs
+/// more than 16 bit indices. Panics if the geometry is invalid.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::trie::generate_split;
+/// let trie = generate_split::<8, 4>(|c| c.is_ascii_digit());
+/// assert!(trie.to_string().starts_with("CharTrieSplit :: < 8u32 , 4u32 > :: from_raw"));
+/// ```
+#[cfg(feature = "new-trie")]
+pub fn generate_split<const LEAF_BITS: u32, const BLOCK_BITS: u32>(
+    f: impl Fn(char) -> bool,
+) -> proc_macro2::TokenStream {
+    use {
+        alloc::{collections::BTreeMap, vec::Vec},
+        core::char,
+    };
+
+    assert!(LEAF_BITS >= 6 && LEAF_BITS + BLOCK_BITS <= 21);
+    let leaf_size = 1u32 << LEAF_BITS;
+    let block_size = leaf_size << BLOCK_BITS;
+
+    let mut leaves: Vec<u64> = Vec::new();
+    let mut leaf_indices = BTreeMap::new();
+    let mut blocks: Vec<u16> = Vec::new();
+    let mut block_indices = BTreeMap::new();
+    let mut root: Vec<u16> = Vec::new();
+    for block_base in (0..=char::MAX as u32).step_by(block_size as usize) {
+        let block: Vec<u16> = (block_base..block_base + block_size)
+            .step_by(leaf_size as usize)
+            .map(|leaf_base| {
+                let leaf: Vec<u64> = (leaf_base..leaf_base + leaf_size)
+                    .step_by(64)
+                    .map(|base| crate::trie::chunk_of(base, &f))
+                    .collect();
+                *leaf_indices.entry(leaf).or_insert_with_key(|leaf| {
+                    leaves.extend_from_slice(leaf);
+                    (leaves.len() / leaf.len() - 1) as u16
+                })
+            })
+            .collect();
+        root.push(*block_indices.entry(block).or_insert_with_key(|block| {
+            blocks.extend_from_slice(block);
+            (blocks.len() / block.len() - 1) as u16
+        }));
+    }
+
+    quote::quote! {
+        CharTrieSplit::<#LEAF_BITS, #BLOCK_BITS>::from_raw(
+            &[#(#root),*],
+            &[#(#blocks),*],
+            &[#(#leaves),*],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CharRange;
+
+    #[test]
+    fn contains() {
+        static ROOT: [u16; 0x11] = [1, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3];
+        static BLOCKS: [u16; 64] = {
+            let mut blocks = [0; 64];
+            blocks[16] = 1;
+            blocks[32 + 15] = 1;
+            blocks[48 + 15] = 2;
+            blocks
+        };
+        static LEAVES: [u64; 192] = {
+            let mut leaves = [0; 192];
+            let mut i = 64;
+            while i < 128 {
+                leaves[i] = !0;
+                i += 1;
+            }
+            leaves[191] = 1 << 63;
+            leaves
+        };
+        // U+0000..=U+0FFF, U+1F000..=U+1FFFF, and U+10FFFF
+        let trie = CharTrieSplit::<12, 4>::from_raw(&ROOT, &BLOCKS, &LEAVES);
+        assert_eq!(trie.validate(), Ok(()));
+        for c in CharRange::from(..) {
+            let expected = matches!(c, '\0'..='\u{FFF}' | '\u{1F000}'..='\u{1FFFF}' | '\u{10FFFF}');
+            assert_eq!(trie.contains(c), expected, "{:?}", c);
+        }
+
+        let trie = CharTrieSplit::<12, 4>::from_raw(&ROOT, &BLOCKS[..48], &LEAVES);
+        assert_eq!(trie.validate(), Err(TrieError::Level3(16)));
+        let trie = CharTrieSplit::<12, 4>::from_raw(&ROOT, &BLOCKS, &LEAVES[..128]);
+        assert_eq!(trie.validate(), Err(TrieError::Level3Block(3, 15)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn small_leaves() {
+        let _ = CharTrieSplit::<5, 4>::from_raw(&[0; 0x2200], &[0; 16], &[0]);
+    }
+
+    #[test]
+    #[cfg(feature = "new-trie")]
+    fn generate() {
+        use {alloc::string::ToString, quote::quote};
+
+        let trie = generate_split::<8, 12>(|c| c == '\u{100}');
+        let leaves = [0u64, 0, 0, 0, 1, 0, 0, 0];
+        let mut blocks = [0u16; 4096];
+        blocks[1] = 1;
+        let empty = [0u16; 4096];
+        assert_eq!(
+            trie.to_string(),
+            quote! {
+                CharTrieSplit::<8u32, 12u32>::from_raw(
+                    &[0u16, 1u16],
+                    &[#(#blocks),*, #(#empty),*],
+                    &[#(#leaves),*],
+                )
+            }
+            .to_string(),
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use {
+    crate::{trie::Tables, CharRange},
+    core::mem,
+};
+
+/// The size and sharing of the tables of a generated trie.
+///
+/// Constructed via `trie::analyze` or `trie::analyze_from_ranges`.
+/// Useful to decide at build time whether a property is better stored as a
+/// `CharTrie` or as a `CharSet`, which takes 8 bytes per range.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct TrieStats {
+    /// The size of every table of the trie, in bytes.
+    pub total_bytes: usize,
+    /// The number of distinct leaves, after deduplication.
+    pub leaf_count: usize,
+    /// The number of distinct level3 blocks, after deduplication.
+    pub level3_block_count: usize,
+    /// Whether the trie needs 16 bit indices, as `CharTrie::from_raw_wide`.
+    pub wide: bool,
+}
+
+impl TrieStats {
+    /// The number of 64-codepoint chunks looked up through the leaves,
+    /// that is every chunk not stored directly in level1.
+    pub const INDEXED_CHUNKS: usize = 992 + 256 * 64;
+
+    /// How many indexed chunks share each distinct leaf, on average.
+    ///
+    /// Higher is better; a trie without any sharing would have a ratio of 1.
+    pub fn dedup_ratio(&self) -> f64 {
+        Self::INDEXED_CHUNKS as f64 / self.leaf_count as f64
+    }
+}
+
+impl<T: Copy + Ord> Tables<T> {
+    /// The statistics of the tables of the only trie.
+    fn stats(&self) -> TrieStats {
+        let index = if self.is_narrow() { 1 } else { 2 };
+        let total_bytes = 32 * mem::size_of::<T>()
+            + 992 * index
+            + 256
+            + self.chonks.len() * 64 * index
+            + self.leaves.len() * mem::size_of::<T>();
+        TrieStats {
+            total_bytes,
+            leaf_count: self.leaves.len(),
+            level3_block_count: self.chonks.len(),
+            wide: !self.is_narrow(),
+        }
+    }
+}
+
+/// Analyze the trie that `generate` would build from a membership function,
+/// without generating any code.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::trie::analyze;
+/// let ascii = analyze(|c| c.is_ascii())?;
+/// assert_eq!(ascii.leaf_count, 1);
+/// assert_eq!(ascii.level3_block_count, 1);
+/// assert_eq!(ascii.total_bytes, 256 + 992 + 256 + 64 + 8);
+/// assert!(!ascii.wide);
+/// # Ok::<(), mileage::trie::GenerateError>(())
+/// ```
+pub fn analyze(f: impl Fn(char) -> bool) -> Result<TrieStats, super::GenerateError> {
+    Ok(Tables::from_fn(f)?.stats())
+}
+
+/// Analyze the trie that `generate_from_ranges` would build from ranges of codepoints,
+/// without generating any code.
+///
+/// The ranges may be in any order and may overlap.
+pub fn analyze_from_ranges(
+    ranges: impl IntoIterator<Item = CharRange>,
+) -> Result<TrieStats, super::GenerateError> {
+    Ok(Tables::from_ranges(ranges)?.stats())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn analyze() {
+        let ascii = super::analyze(|c| c.is_ascii()).unwrap();
+        assert_eq!(
+            ascii,
+            analyze_from_ranges(Some(CharRange::from('\0'..='\x7F'))).unwrap()
+        );
+        assert_eq!(ascii.dedup_ratio(), TrieStats::INDEXED_CHUNKS as f64);
+
+        // 64 distinct chunks, plus the empty chunks of the surrogates
+        let distinct = super::analyze(|c| c as u32 & 63 == (c as u32 >> 6) & 63).unwrap();
+        assert_eq!(distinct.leaf_count, 65);
+        assert!(!distinct.wide);
+
+        // each chunk holds the low 16 bits of its own index
+        let wide = super::analyze(|c| (c as u32 >> 6) >> (c as u32 & 15) & 1 == 1).unwrap();
+        assert!(wide.wide);
+        assert_eq!(
+            wide.total_bytes,
+            32 * 8 + 992 * 2 + 256 + wide.level3_block_count * 128 + wide.leaf_count * 8,
+        );
+        assert!(wide.dedup_ratio() < ascii.dedup_ratio());
+    }
+}
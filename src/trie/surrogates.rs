@@ -0,0 +1,160 @@
+use crate::trie::{CharTrie, TrieError};
+
+/// A compressed trie-like set of codepoints which may include the surrogates.
+///
+/// This has the same tables as a `CharTrie`, but is keyed by `u32`, so the
+/// membership bits of U+D800..=U+DFFF are meaningful rather than ignored.
+/// This is for tools which handle ill-formed text, such as WTF-8 or CESU-8,
+/// where lone surrogates are data.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::trie::{CharTrie, CharTrieU32};
+/// static LEVEL2: [u8; 992] = {
+///     let mut level2 = [0; 992];
+///     let mut idx = (0xD800 - 0x800) >> 6;
+///     while idx < (0xE000 - 0x800) >> 6 {
+///         level2[idx] = 1;
+///         idx += 1;
+///     }
+///     level2
+/// };
+/// static SURROGATES: CharTrieU32<'static> = CharTrieU32::new(CharTrie::from_raw(
+///     &[0; 32],
+///     &LEVEL2,
+///     (&[0; 256], &[[0; 64]]),
+///     &[0, !0],
+/// ));
+/// assert!(SURROGATES.contains(0xD800));
+/// assert!(SURROGATES.contains(0xDFFF));
+/// assert!(!SURROGATES.contains(0xE000));
+/// assert!(!SURROGATES.contains(0x11_0000));
+/// assert_eq!(SURROGATES.as_char_trie().ranges().next(), None);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct CharTrieU32<'a> {
+    trie: CharTrie<'a>,
+}
+
+impl<'a> CharTrieU32<'a> {
+    /// Use the tables of a trie, including any bits set for the surrogates.
+    pub const fn new(trie: CharTrie<'a>) -> Self {
+        CharTrieU32 { trie }
+    }
+
+    /// Does this set contain this codepoint?
+    ///
+    /// This is always false past U+10FFFF.
+    #[inline]
+    pub fn contains(&self, c: u32) -> bool {
+        c <= char::MAX as u32 && (self.trie.chunk(c) >> (c & 0b_111_111)) & 1 == 1
+    }
+
+    /// Does this set contain this scalar value?
+    #[inline]
+    pub fn contains_char(&self, c: char) -> bool {
+        self.trie.contains(c)
+    }
+
+    /// This set without the surrogates, as a `CharTrie` sharing the same tables.
+    ///
+    /// Any bits set for the surrogates stay in the tables, but lookups,
+    /// iteration, and counting through the `CharTrie` ignore them.
+    pub fn as_char_trie(&self) -> CharTrie<'a> {
+        self.trie
+    }
+
+    /// Check that every index of this trie is in bounds,
+    /// such that looking up any codepoint will not panic.
+    pub fn validate(&self) -> Result<(), TrieError> {
+        self.trie.validate()
+    }
+}
+
+/// Generate a new trie which may include the surrogates from a membership function.
+///
+/// This behaves as `generate`, but tests every codepoint as a `u32`, including
+/// the surrogates, and constructs Rust code which evaluates to a `CharTrieU32`.
+/// Requires that `CharTrie` and `CharTrieU32` are in scope.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::trie::generate_u32;
+/// let tokens = generate_u32(|c| (0xD800..0xDC00).contains(&c))?;
+/// assert!(tokens.to_string().starts_with("CharTrieU32 :: new (CharTrie :: from_raw ("));
+/// # Ok::<(), mileage::trie::GenerateError>(())
+/// ```
+#[cfg(feature = "new-trie")]
+pub fn generate_u32(
+    f: impl Fn(u32) -> bool,
+) -> Result<proc_macro2::TokenStream, crate::trie::GenerateError> {
+    let mut tables = crate::trie::Tables::new();
+    tables.push(
+        (0..=char::MAX as u32)
+            .step_by(64)
+            .map(|base| crate::trie::chunk_of_u32(base, &f)),
+    )?;
+    let trie = tables.to_tokens();
+    Ok(quote::quote!(CharTrieU32::new(#trie)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contains() {
+        const LEVEL2: [u8; 992] = {
+            let mut level2 = [0; 992];
+            level2[(0xD7C0 - 0x800) >> 6] = 1;
+            level2[(0xD800 - 0x800) >> 6] = 1;
+            level2[(0xDFC0 - 0x800) >> 6] = 2;
+            level2
+        };
+        // U+D7C0..=U+D7FF, U+D800..=U+D83F, and U+DFFF
+        static TRIE: CharTrieU32<'static> = CharTrieU32::new(CharTrie::from_raw(
+            &[0; 32],
+            &LEVEL2,
+            (&[0; 256], &[[0; 64]]),
+            &[0, !0, 1 << 63],
+        ));
+        assert_eq!(TRIE.validate(), Ok(()));
+        for c in 0..=0x11_0000 {
+            let expected = matches!(c, 0xD7C0..=0xD83F | 0xDFFF);
+            assert_eq!(TRIE.contains(c), expected, "{:#X}", c);
+        }
+        assert!(TRIE.contains_char('\u{D7FF}'));
+        assert!(!TRIE.contains_char('\u{E000}'));
+        assert!(TRIE
+            .as_char_trie()
+            .ranges()
+            .eq(Some(crate::CharRange::closed('\u{D7C0}', '\u{D7FF}'))));
+        assert_eq!(
+            TRIE.as_char_trie()
+                .count_in_range(crate::CharRange::from(..)),
+            64
+        );
+        assert_eq!(TRIE.as_char_trie().chars().count(), 64);
+    }
+
+    #[test]
+    #[cfg(feature = "new-trie")]
+    fn generate_u32() {
+        use alloc::string::ToString;
+
+        // the surrogates are whole chunks, so share a full leaf
+        let tokens = super::generate_u32(|c| (0xD800..=0xDFFF).contains(&c)).unwrap();
+        assert!(tokens
+            .to_string()
+            .contains("& [0u64 , 18446744073709551615u64]"));
+        // without the surrogates, the tables are those of `generate`
+        let tokens = super::generate_u32(|c| c < 0x80).unwrap();
+        let trie = crate::trie::generate(|c| c.is_ascii()).unwrap();
+        assert_eq!(
+            tokens.to_string(),
+            quote::quote!(CharTrieU32::new(#trie)).to_string()
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use {
+    crate::{
+        trie::{CharTrie, CharTrieBuf, GenerateError},
+        CharRange,
+    },
+    core::{char, convert::TryFrom},
+    ucd_trie::{TrieSetOwned, TrieSetSlice},
+};
+
+/// The membership bits of the chunk of 64 codepoints at `base`,
+/// looked up as `TrieSetSlice::contains_u32` does for a single codepoint.
+fn chunk_of(set: &TrieSetSlice<'_>, base: u32) -> u64 {
+    let idx = (base >> 6) as usize;
+    if base < 0x800 {
+        set.tree1_level1.get(idx).copied().unwrap_or(0)
+    } else if base < 0x10000 {
+        match set.tree2_level1.get(idx - 0x20) {
+            Some(&leaf) => set.tree2_level2[leaf as usize],
+            None => 0,
+        }
+    } else {
+        match set.tree3_level1.get((base >> 12) as usize - 0x10) {
+            Some(&child) => {
+                let leaf = set.tree3_level2[child as usize * 64 + (idx & 63)];
+                set.tree3_level3[leaf as usize]
+            }
+            None => 0,
+        }
+    }
+}
+
+/// Build a trie with the same members as a `ucd_trie` set.
+///
+/// The leaves of the set are copied a chunk at a time, rather than testing
+/// every codepoint. `ucd_trie` lays out its tables differently, so a static
+/// `TrieSet` cannot be borrowed as a `CharTrie`, only copied into a `CharTrieBuf`.
+///
+/// # Examples
+///
+/// ```
+/// # use {mileage::trie::CharTrieBuf, std::convert::TryFrom};
+/// let set = ucd_trie::TrieSetOwned::from_scalars('a'..='z').unwrap();
+/// let trie = CharTrieBuf::try_from(&set.as_slice()).unwrap();
+/// assert!(trie.contains('q'));
+/// assert!(!trie.contains('Q'));
+/// ```
+impl TryFrom<&TrieSetSlice<'_>> for CharTrieBuf {
+    type Error = GenerateError;
+
+    fn try_from(set: &TrieSetSlice<'_>) -> Result<Self, GenerateError> {
+        CharTrieBuf::from_chunks(
+            (0..=char::MAX as u32)
+                .step_by(64)
+                .map(|base| chunk_of(set, base)),
+        )
+    }
+}
+
+impl TryFrom<&TrieSetOwned> for CharTrieBuf {
+    type Error = GenerateError;
+
+    fn try_from(set: &TrieSetOwned) -> Result<Self, GenerateError> {
+        CharTrieBuf::try_from(&set.as_slice())
+    }
+}
+
+/// Build a `ucd_trie` set with the same members as a trie.
+///
+/// # Examples
+///
+/// ```
+/// # use {mileage::trie::CharTrieBuf, std::convert::TryFrom};
+/// let trie = CharTrieBuf::from_fn(|c| c.is_ascii_hexdigit()).unwrap();
+/// let set = ucd_trie::TrieSetOwned::try_from(&trie.as_trie()).unwrap();
+/// assert!(set.contains_char('F'));
+/// assert!(!set.contains_char('G'));
+/// ```
+impl TryFrom<&CharTrie<'_>> for TrieSetOwned {
+    type Error = ucd_trie::Error;
+
+    fn try_from(trie: &CharTrie<'_>) -> Result<Self, ucd_trie::Error> {
+        TrieSetOwned::from_scalars(trie.ranges().flat_map(CharRange::iter))
+    }
+}
+
+impl TryFrom<&CharTrieBuf> for TrieSetOwned {
+    type Error = ucd_trie::Error;
+
+    fn try_from(trie: &CharTrieBuf) -> Result<Self, ucd_trie::Error> {
+        TrieSetOwned::try_from(&trie.as_trie())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, alloc::vec::Vec};
+
+    #[test]
+    fn round_trip() {
+        let ranges = [
+            CharRange::closed('0', '9'),
+            CharRange::closed('\u{7C0}', '\u{840}'),
+            CharRange::closed('\u{FFFF}', '\u{10000}'),
+            CharRange::closed('\u{1F600}', '\u{1F64F}'),
+            CharRange::closed('\u{10FFFF}', '\u{10FFFF}'),
+        ];
+        let set =
+            TrieSetOwned::from_scalars(ranges.iter().copied().flat_map(CharRange::iter)).unwrap();
+        let trie = CharTrieBuf::try_from(&set).unwrap();
+        assert_eq!(trie.as_trie().ranges().collect::<Vec<_>>(), ranges);
+
+        let back = TrieSetOwned::try_from(&trie).unwrap();
+        assert!(CharRange::from(..)
+            .iter()
+            .all(|c| back.contains_char(c) == trie.contains(c)));
+
+        let empty = TrieSetOwned::from_scalars("".chars()).unwrap();
+        assert_eq!(
+            CharTrieBuf::try_from(&empty)
+                .unwrap()
+                .as_trie()
+                .ranges()
+                .next(),
+            None
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use {
+    crate::{
+        trie::{chunk_of, diff::mismatches, CharTrie},
+        CharRange,
+    },
+    alloc::vec::Vec,
+    core::char,
+};
+
+/// Check that a trie has exactly the members for which the predicate holds.
+///
+/// Every codepoint is tested once, and the membership bits of each chunk of
+/// 64 codepoints are compared against the trie's leaf in one step. Returns the
+/// ranges of codepoints where the two disagree, in order.
+///
+/// # Examples
+///
+/// ```
+/// # use mileage::{trie::{self, CharTrie}, CharRange};
+/// static LEVEL1: [u64; 32] = {
+///     let mut level1 = [0; 32];
+///     level1[0] = 0x03FF << 0x30;
+///     level1
+/// };
+/// static DIGITS: CharTrie<'static> =
+///     CharTrie::from_raw(&LEVEL1, &[0; 992], (&[0; 256], &[[0; 64]]), &[0]);
+///
+/// assert_eq!(trie::verify(&DIGITS, |c| c.is_ascii_digit()), Ok(()));
+/// assert_eq!(
+///     trie::verify(&DIGITS, |c| c.is_ascii_hexdigit()),
+///     Err(vec![CharRange::closed('A', 'F'), CharRange::closed('a', 'f')]),
+/// );
+/// ```
+pub fn verify(trie: &CharTrie<'_>, f: impl Fn(char) -> bool) -> Result<(), Vec<CharRange>> {
+    let chunks = (0..=char::MAX as u32)
+        .step_by(64)
+        .map(|base| chunk_of(base, &f));
+    let diff = mismatches(trie, chunks);
+    if diff.is_empty() {
+        Ok(())
+    } else {
+        Err(diff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify() {
+        const LEVEL2: [u8; 992] = {
+            let mut level2 = [0; 992];
+            level2[0] = 1;
+            level2
+        };
+        const LEVEL3: [u8; 256] = {
+            let mut level3 = [0; 256];
+            level3[255] = 1;
+            level3
+        };
+        const CHONK: [u8; 64] = {
+            let mut chonk = [0; 64];
+            chonk[63] = 1;
+            chonk
+        };
+        // U+0800..=U+083F and U+10FFC0..=U+10FFFF
+        static TRIE: CharTrie<'static> =
+            CharTrie::from_raw(&[0; 32], &LEVEL2, (&LEVEL3, &[[0; 64], CHONK]), &[0, !0]);
+
+        let f = |c| ('\u{800}'..='\u{83F}').contains(&c) || c >= '\u{10FFC0}';
+        assert_eq!(super::verify(&TRIE, f), Ok(()));
+        assert_eq!(
+            super::verify(&TRIE, |c| f(c) && c != '\u{801}' || c == '\u{840}'),
+            Err(alloc::vec![
+                CharRange::singleton('\u{801}'),
+                CharRange::singleton('\u{840}'),
+            ]),
+        );
+        // differences in consecutive chunks are merged
+        assert_eq!(
+            super::verify(&TRIE, |c| ('\u{780}'..='\u{81F}').contains(&c)),
+            Err(alloc::vec![
+                CharRange::closed('\u{780}', '\u{7FF}'),
+                CharRange::closed('\u{820}', '\u{83F}'),
+                CharRange::closed('\u{10FFC0}', char::MAX),
+            ]),
+        );
+    }
+}